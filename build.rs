@@ -0,0 +1,41 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Compiles the C reference implementation for the `c-reference` feature. Nothing else needs a build step.
+fn main() {
+	println!("cargo:rerun-if-changed=build.rs");
+
+	if env::var_os("CARGO_FEATURE_C_REFERENCE").is_none() {
+		return;
+	}
+
+	// The C source is left out of the published crate; the feature is only for testing from a checkout.
+	assert!(Path::new("c-reference/bcrypt.c").exists(), "the c-reference feature needs c-reference/bcrypt.c from a checkout of the repository");
+
+	println!("cargo:rerun-if-changed=c-reference/bcrypt.c");
+	println!("cargo:rerun-if-env-changed=CC");
+	println!("cargo:rerun-if-env-changed=AR");
+
+	let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+	let object = out_dir.join("bcrypt-reference.o");
+	let library = out_dir.join("libbcrypt_reference.a");
+
+	let status = Command::new(env::var_os("CC").unwrap_or_else(|| "cc".into()))
+		.args(["-O2", "-fPIC", "-c", "c-reference/bcrypt.c", "-o"])
+		.arg(&object)
+		.status()
+		.expect("failed to run the C compiler");
+	assert!(status.success(), "failed to compile the C reference implementation");
+
+	let status = Command::new(env::var_os("AR").unwrap_or_else(|| "ar".into()))
+		.arg("crs")
+		.arg(&library)
+		.arg(&object)
+		.status()
+		.expect("failed to run ar");
+	assert!(status.success(), "failed to archive the C reference implementation");
+
+	println!("cargo:rustc-link-search=native={}", out_dir.display());
+	println!("cargo:rustc-link-lib=static=bcrypt_reference");
+}
@@ -0,0 +1,129 @@
+/* The C API of bcrypt-only, declared by hand to match src/capi.rs. The `capi_header` test in src/tests.rs checks the two agree, and capi/test.c that C sees the same ABI. */
+
+#ifndef BCRYPT_ONLY_H
+#define BCRYPT_ONLY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* Success. */
+#define BCRYPT_ONLY_OK 0
+
+/* The key was longer than 72 bytes. */
+#define BCRYPT_ONLY_ERR_KEY_LENGTH -1
+
+/* The key contained a 0 byte. */
+#define BCRYPT_ONLY_ERR_KEY_ZERO_BYTE -2
+
+/* A setting or hash string wasn’t valid. */
+#define BCRYPT_ONLY_ERR_PARSE -3
+
+/* A work factor was outside 4 to 31. */
+#define BCRYPT_ONLY_ERR_WORK_FACTOR -4
+
+/* A required pointer was null. */
+#define BCRYPT_ONLY_ERR_NULL -5
+
+/* A session was finished before all of its rounds had run. */
+#define BCRYPT_ONLY_ERR_INCOMPLETE -6
+
+/* The size of the buffer bcrypt_only_gensalt writes, including the NUL terminator. */
+#define BCRYPT_ONLY_SETTING_BUFFER_SIZE 30
+
+/* The size of the buffer bcrypt_only_hash writes, including the NUL terminator. */
+#define BCRYPT_ONLY_HASH_BUFFER_SIZE 61
+
+/* The size of a bcrypt_only_session, in bytes. */
+#define BCRYPT_ONLY_SESSION_SIZE 4608
+
+/* The flags bcrypt_only_capabilities returns, for what this build of the library supports. Flags are only ever added, never renumbered. */
+#define BCRYPT_ONLY_CAP_VERSION_2A 0x1
+#define BCRYPT_ONLY_CAP_VERSION_2B 0x2
+#define BCRYPT_ONLY_CAP_VERSION_2Y 0x4
+#define BCRYPT_ONLY_CAP_BACKEND_PORTABLE 0x100
+#define BCRYPT_ONLY_CAP_BACKEND_SYSTEM_CRYPT 0x200
+#define BCRYPT_ONLY_CAP_BACKEND_C_REFERENCE 0x400
+#define BCRYPT_ONLY_CAP_BACKEND_BCRYPT_CRATE 0x800
+#define BCRYPT_ONLY_CAP_STD 0x10000
+#define BCRYPT_ONLY_CAP_RAM_TABLES 0x20000
+#define BCRYPT_ONLY_CAP_RUNTIME_TABLES 0x40000
+#define BCRYPT_ONLY_CAP_SIZE_OPT 0x80000
+#define BCRYPT_ONLY_CAP_TEST_SUPPORT 0x100000
+#define BCRYPT_ONLY_CAP_CAPI 0x200000
+#define BCRYPT_ONLY_CAP_MCF 0x400000
+#define BCRYPT_ONLY_CAP_UNSTABLE 0x800000
+#define BCRYPT_ONLY_CAP_BCRYPT_PBKDF 0x1000000
+#define BCRYPT_ONLY_CAP_DERIVED_SALT 0x2000000
+#define BCRYPT_ONLY_CAP_COMPACT_ERRORS 0x4000000
+#define BCRYPT_ONLY_CAP_ROUND_COUNT 0x8000000
+#define BCRYPT_ONLY_CAP_TRACING 0x10000000
+#define BCRYPT_ONLY_CAP_PASSWORD_HASH 0x20000000
+#define BCRYPT_ONLY_CAP_SERDE 0x40000000
+#define BCRYPT_ONLY_CAP_ARBITRARY 0x80000000
+#define BCRYPT_ONLY_CAP_PROPTEST 0x100000000
+#define BCRYPT_ONLY_CAP_HYBRID_ARRAY 0x200000000
+#define BCRYPT_ONLY_CAP_BORSH 0x400000000
+#define BCRYPT_ONLY_CAP_SCHEMARS 0x800000000
+#define BCRYPT_ONLY_CAP_WASM 0x1000000000
+#define BCRYPT_ONLY_CAP_TOKIO 0x2000000000
+#define BCRYPT_ONLY_CAP_UFMT 0x4000000000
+#define BCRYPT_ONLY_CAP_SQLX 0x8000000000
+#define BCRYPT_ONLY_CAP_DIESEL 0x10000000000
+#define BCRYPT_ONLY_CAP_PYTHON 0x20000000000
+#define BCRYPT_ONLY_CAP_UNIFFI 0x40000000000
+#define BCRYPT_ONLY_CAP_NODE 0x80000000000
+
+/* Storage for a hash in progress. The caller places it wherever suits – on a task’s stack, in a static – so sessions never allocate. Its contents are private. */
+typedef struct {
+	uint64_t opaque[BCRYPT_ONLY_SESSION_SIZE / 8];
+} bcrypt_only_session;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Returns which versions, backends, and features this build of the library supports, as BCRYPT_ONLY_CAP_* flags. Unknown bits may be set by newer versions.
+ */
+uint64_t bcrypt_only_capabilities(void);
+
+/*
+ * Writes a $2b$ setting for `log_rounds` and the 16 random bytes at `random` into the BCRYPT_ONLY_SETTING_BUFFER_SIZE-byte buffer at `output`. The caller supplies the randomness, so this works on targets without an operating system.
+ */
+int bcrypt_only_gensalt(unsigned int log_rounds, const uint8_t *random, char *output);
+
+/*
+ * Hashes the `key_len`-byte key at `key` (which can be null if `key_len` is 0) with a NUL-terminated setting from bcrypt_only_gensalt, or a whole hash string, whose setting part is used. Writes the hash string into the BCRYPT_ONLY_HASH_BUFFER_SIZE-byte buffer at `output`. The hash string keeps the setting’s version prefix.
+ */
+int bcrypt_only_hash(const uint8_t *key, size_t key_len, const char *setting, char *output);
+
+/*
+ * Checks whether the `key_len`-byte key at `key` (which can be null if `key_len` is 0) matches a NUL-terminated hash string, comparing the hashes in constant time. Returns 1 for a match, 0 for a mismatch, or a negative error code.
+ */
+int bcrypt_only_verify(const uint8_t *key, size_t key_len, const char *hash_string);
+
+/*
+ * Starts hashing the `key_len`-byte key at `key` with a setting, as bcrypt_only_hash would, in a session that isn’t in use. Only the cheap setup happens here; bcrypt_only_session_step runs the expensive rounds.
+ */
+int bcrypt_only_session_create(bcrypt_only_session *session, const uint8_t *key, size_t key_len, const char *setting);
+
+/*
+ * Runs up to `rounds` more expensive rounds of a session. Returns 1 once all of them have run, 0 if some remain, or a negative error code.
+ */
+int bcrypt_only_session_step(bcrypt_only_session *session, uint32_t rounds);
+
+/*
+ * Writes the hash string for a session, all of whose rounds have run, into the BCRYPT_ONLY_HASH_BUFFER_SIZE-byte buffer at `output`.
+ */
+int bcrypt_only_session_finish(bcrypt_only_session *session, char *output);
+
+/*
+ * Ends a session, finished or not, and zeroes its storage, which can then be reused for another session. Accepts null.
+ */
+void bcrypt_only_session_destroy(bcrypt_only_session *session);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,61 @@
+/* Round-trips through the C API as a C caller would, checking the ABI matches the header. Calls are kept out of `assert` so the test still makes them when built with -DNDEBUG. */
+
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "bcrypt_only.h"
+
+int main(void) {
+	static const uint8_t random[16] = {0x79, 0x76, 0x2b, 0xe9, 0x97, 0x0f, 0x5b, 0xe7, 0x3a, 0xc7, 0x7c, 0x0e, 0x4f, 0x0a, 0x38, 0x51};
+	static const uint8_t key[] = "Kk4DQuMMfZL9o";
+	char setting[BCRYPT_ONLY_SETTING_BUFFER_SIZE];
+	char hash_string[BCRYPT_ONLY_HASH_BUFFER_SIZE];
+	int result;
+	bcrypt_only_session session;
+	int steps = 0;
+	uint64_t capabilities;
+
+	capabilities = bcrypt_only_capabilities();
+	assert((capabilities & (BCRYPT_ONLY_CAP_CAPI | BCRYPT_ONLY_CAP_MCF | BCRYPT_ONLY_CAP_VERSION_2B)) == (BCRYPT_ONLY_CAP_CAPI | BCRYPT_ONLY_CAP_MCF | BCRYPT_ONLY_CAP_VERSION_2B));
+
+	result = bcrypt_only_gensalt(4, random, setting);
+	assert(result == BCRYPT_ONLY_OK);
+	assert(strcmp(setting, "$2b$04$cVWp4XaNU8a4v1uMRum2SO") == 0);
+
+	result = bcrypt_only_hash(key, sizeof key - 1, setting, hash_string);
+	assert(result == BCRYPT_ONLY_OK);
+	assert(strcmp(hash_string, "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm") == 0);
+
+	result = bcrypt_only_verify(key, sizeof key - 1, hash_string);
+	assert(result == 1);
+	result = bcrypt_only_verify(key, sizeof key - 2, hash_string);
+	assert(result == 0);
+	result = bcrypt_only_verify(key, sizeof key, hash_string);
+	assert(result == BCRYPT_ONLY_ERR_KEY_ZERO_BYTE);
+	result = bcrypt_only_verify(NULL, 0, "$2b$04$");
+	assert(result == BCRYPT_ONLY_ERR_PARSE);
+	result = bcrypt_only_gensalt(32, random, setting);
+	assert(result == BCRYPT_ONLY_ERR_WORK_FACTOR);
+
+	result = bcrypt_only_session_create(&session, key, sizeof key - 1, setting);
+	assert(result == BCRYPT_ONLY_OK);
+	result = bcrypt_only_session_finish(&session, hash_string);
+	assert(result == BCRYPT_ONLY_ERR_INCOMPLETE);
+
+	for (int done = 0; !done; steps++) {
+		done = bcrypt_only_session_step(&session, 5);
+		assert(done == 0 || done == 1);
+	}
+
+	assert(steps == 4);
+	result = bcrypt_only_session_finish(&session, hash_string);
+	assert(result == BCRYPT_ONLY_OK);
+	assert(strcmp(hash_string, "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm") == 0);
+	bcrypt_only_session_destroy(&session);
+
+	(void)result;
+	(void)capabilities;
+	puts("C API round trip ok");
+	return 0;
+}
@@ -0,0 +1,41 @@
+//! `Arbitrary` for the `arbitrary` feature, generating only values the public API accepts, so fuzzers spend their input on the code under test.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::{KeyPolicy, SALT_SIZE, Salt, WorkFactor};
+
+/// Any 16 bytes.
+impl<'a> Arbitrary<'a> for Salt {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		Ok(Salt::from_bytes(&u.arbitrary()?))
+	}
+
+	fn size_hint(depth: usize) -> (usize, Option<usize>) {
+		<[u8; SALT_SIZE]>::size_hint(depth)
+	}
+}
+
+/// An exponent from 4 to 31, not the whole `u32` range [`WorkFactor::exp`] would reject most of. Fuzzers should cap it themselves, since even the middle of the range is slow.
+impl<'a> Arbitrary<'a> for WorkFactor {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		Ok(WorkFactor::exp(u.int_in_range(4..=31)?).unwrap())
+	}
+
+	fn size_hint(depth: usize) -> (usize, Option<usize>) {
+		u8::size_hint(depth)
+	}
+}
+
+/// Each option independently.
+impl<'a> Arbitrary<'a> for KeyPolicy {
+	fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+		Ok(KeyPolicy {
+			strip_newline: u.arbitrary()?,
+			reject_empty: u.arbitrary()?,
+		})
+	}
+
+	fn size_hint(depth: usize) -> (usize, Option<usize>) {
+		<(bool, bool)>::size_hint(depth)
+	}
+}
@@ -0,0 +1,27 @@
+//! [`hybrid_array::Array`] forms of the hash and salt, for code generic over RustCrypto’s output sizes – KDF pipelines, HMAC keys – to take bcrypt’s output as it is.
+
+use hybrid_array::Array;
+use hybrid_array::sizes::{U16, U23};
+use hybrid_array::typenum::Unsigned;
+
+use super::{HASH_SIZE, SALT_SIZE, Salt};
+
+/// A bcrypt hash as an `Array`. The `[u8; HASH_SIZE]` every hashing function returns converts into one with `From`, moved rather than copied, and a reference to one with `Array::cast_from_core`.
+pub type HashArray = Array<u8, U23>;
+
+/// A salt’s bytes as an `Array`.
+pub type SaltArray = Array<u8, U16>;
+
+const _: () = assert!(U23::USIZE == HASH_SIZE && U16::USIZE == SALT_SIZE);
+
+impl From<&SaltArray> for Salt {
+	fn from(bytes: &SaltArray) -> Self {
+		Salt::from_bytes(&bytes.0)
+	}
+}
+
+impl From<&Salt> for SaltArray {
+	fn from(salt: &Salt) -> Self {
+		Array(salt.to_bytes())
+	}
+}
@@ -0,0 +1,73 @@
+use core::hint::black_box;
+use core::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use super::{BcryptError, KEY_SIZE_MAX, check_key};
+use super::mcf::{self, HashString};
+
+/// The result of an [`AsyncVerifier`]’s verification.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AsyncVerify {
+	/// The key matched.
+	Match,
+
+	/// The key didn’t match.
+	Mismatch,
+
+	/// The verification waited longer than the queue timeout for a slot, so nothing was hashed.
+	QueueTimeout,
+}
+
+/// Verifies on tokio’s blocking pool, at most `concurrency` at a time, so a burst of logins can’t take every blocking thread from unrelated file I/O.
+///
+/// A verification waits for a slot for up to the queue timeout, then gives up with [`AsyncVerify::QueueTimeout`] – which a login endpoint can turn into a 503 – instead of adding to a backlog it’ll never work through. A verification that’s cancelled after it’s started hashing keeps its slot until the hashing finishes.
+#[derive(Debug)]
+pub struct AsyncVerifier {
+	slots: Arc<Semaphore>,
+	queue_timeout: Duration,
+}
+
+impl AsyncVerifier {
+	/// Creates a verifier running up to `concurrency` verifications at once, each waiting up to `queue_timeout` to start.
+	pub fn new(concurrency: NonZeroUsize, queue_timeout: Duration) -> Self {
+		Self {
+			slots: Arc::new(Semaphore::new(concurrency.get())),
+			queue_timeout,
+		}
+	}
+
+	/// [`mcf::verify`] on the blocking pool, once a slot is free. The key is copied, and the copy zeroed once it’s been hashed. Panics if called outside a tokio runtime with its time driver enabled.
+	pub async fn verify(&self, key: &[u8], hash_string: &HashString) -> Result<AsyncVerify, BcryptError> {
+		check_key(key)?;
+
+		let slot = match tokio::time::timeout(self.queue_timeout, Arc::clone(&self.slots).acquire_owned()).await {
+			Ok(slot) => slot.expect("the semaphore is never closed"),
+			Err(_) => return Ok(AsyncVerify::QueueTimeout),
+		};
+
+		let mut key_copy = [0_u8; KEY_SIZE_MAX];
+		let key_len = key.len();
+		key_copy[..key_len].copy_from_slice(key);
+		let hash_string = *hash_string;
+
+		let result = tokio::task::spawn_blocking(move || {
+			let result = mcf::verify(&key_copy[..key_len], &hash_string);
+			key_copy.fill(0);
+			black_box(&mut key_copy);
+			drop(slot);
+			result
+		}).await;
+
+		match result {
+			Ok(result) => result.map(|matched| if matched { AsyncVerify::Match } else { AsyncVerify::Mismatch }),
+			Err(err) => std::panic::resume_unwind(err.into_panic()),
+		}
+	}
+
+	/// The number of free slots.
+	pub fn available(&self) -> usize {
+		self.slots.available_permits()
+	}
+}
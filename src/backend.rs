@@ -0,0 +1,284 @@
+//! Pluggable bcrypt implementations.
+//!
+//! Hashing goes through a [`BcryptBackend`], so a SIMD, GPU, FFI, or hardware-offload implementation can be selected or injected without the calling code changing. Code that hashes whole keys takes one with a `_with_backend` function or a `backend` method, or uses [`DefaultBackend`]. Incremental hashing with [`BcryptState`](crate::BcryptState), and what’s built on it, runs the portable rounds, since a backend only hashes whole keys.
+//!
+//! A SoC or accelerator exposing Blowfish or bcrypt primitives plugs in by implementing [`BcryptBackend`] around them. Its tests – ideally run on the hardware – should then check that [`conformance`] passes.
+
+use super::{BcryptError, HASH_SIZE, KEY_SIZE_MAX, SALT_SIZE, Salt, WorkFactor, bcrypt_runtime};
+
+/// One hash in a batch.
+#[derive(Clone, Copy, Debug)]
+pub struct HashJob<'a> {
+	/// The key to hash.
+	pub key: &'a [u8],
+
+	/// The salt to hash it with.
+	pub salt: &'a Salt,
+
+	/// The work factor to hash it at.
+	pub work_factor: WorkFactor,
+}
+
+/// A bcrypt implementation.
+///
+/// Implementations have to produce the same results as [`bcrypt`](crate::bcrypt), including its errors for invalid keys.
+pub trait BcryptBackend {
+	/// A short name identifying the implementation, for diagnostics.
+	fn name(&self) -> &'static str;
+
+	/// Hashes a key and salt with bcrypt according to a work factor.
+	fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError>;
+
+	/// Hashes each job into the corresponding element of `results`. The default implementation hashes them one at a time; backends that can work on several hashes at once should override it.
+	///
+	/// Panics if `jobs` and `results` have different lengths.
+	fn hash_batch(&self, jobs: &[HashJob], results: &mut [Result<[u8; HASH_SIZE], BcryptError>]) {
+		assert_eq!(jobs.len(), results.len(), "batch length mismatch");
+
+		for (job, result) in jobs.iter().zip(results) {
+			*result = self.hash(job.key, job.salt, job.work_factor);
+		}
+	}
+}
+
+impl<T: BcryptBackend + ?Sized> BcryptBackend for &T {
+	fn name(&self) -> &'static str {
+		(**self).name()
+	}
+
+	fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		(**self).hash(key, salt, work_factor)
+	}
+
+	fn hash_batch(&self, jobs: &[HashJob], results: &mut [Result<[u8; HASH_SIZE], BcryptError>]) {
+		(**self).hash_batch(jobs, results);
+	}
+}
+
+/// The portable implementation in safe Rust that [`bcrypt`](crate::bcrypt) uses.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Portable;
+
+impl BcryptBackend for Portable {
+	fn name(&self) -> &'static str {
+		"portable"
+	}
+
+	fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		bcrypt_runtime(key, salt, work_factor)
+	}
+}
+
+/// The backend used when none is chosen.
+pub type DefaultBackend = Portable;
+
+/// A way to run work on a second core, for multi-core targets without threads: on an RP2040, say, starting core 1 on a function and waiting for it to signal through the inter-core FIFO.
+pub trait Join {
+	/// Runs `other` on another core while running `this` on the current one, returning once both have finished.
+	fn join(&self, other: &mut (dyn FnMut() + Send), this: &mut dyn FnMut());
+}
+
+impl<T: Join + ?Sized> Join for &T {
+	fn join(&self, other: &mut (dyn FnMut() + Send), this: &mut dyn FnMut()) {
+		(**self).join(other, this);
+	}
+}
+
+/// A backend that splits each batch in half and hashes the halves at once with `join`, so a second core that would otherwise idle through a batch of provisioning hashes does half of them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SplitBatch<B, J> {
+	/// The backend that hashes each half.
+	pub backend: B,
+
+	/// How the second half is run on another core.
+	pub join: J,
+}
+
+impl<B: BcryptBackend + Sync, J: Join> BcryptBackend for SplitBatch<B, J> {
+	fn name(&self) -> &'static str {
+		self.backend.name()
+	}
+
+	fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		self.backend.hash(key, salt, work_factor)
+	}
+
+	fn hash_batch(&self, jobs: &[HashJob], results: &mut [Result<[u8; HASH_SIZE], BcryptError>]) {
+		assert_eq!(jobs.len(), results.len(), "batch length mismatch");
+
+		if jobs.len() < 2 {
+			self.backend.hash_batch(jobs, results);
+			return;
+		}
+
+		let backend = &self.backend;
+		let (jobs_other, jobs_this) = jobs.split_at(jobs.len() / 2);
+		let (results_other, results_this) = results.split_at_mut(jobs.len() / 2);
+
+		self.join.join(
+			&mut || backend.hash_batch(jobs_other, results_other),
+			&mut || backend.hash_batch(jobs_this, results_this),
+		);
+	}
+}
+
+// Reference backends for differential testing, not part of the supported API.
+#[cfg(feature = "c-reference")]
+#[doc(hidden)]
+pub use super::c_reference::CReference;
+
+#[cfg(feature = "system-crypt")]
+#[doc(hidden)]
+pub use super::system::SystemCrypt;
+
+#[cfg(feature = "bcrypt-crate")]
+#[doc(hidden)]
+pub use super::bcrypt_crate::BcryptCrate;
+
+/// A case where two backends disagreed, found by [`compare`].
+#[derive(Clone, Debug)]
+pub struct Mismatch {
+	key: [u8; KEY_SIZE_MAX + 1],
+	key_len: usize,
+
+	/// The salt the backends were given.
+	pub salt: Salt,
+
+	/// The work factor the backends were given.
+	pub work_factor: WorkFactor,
+
+	/// The first backend’s result.
+	pub expected: Result<[u8; HASH_SIZE], BcryptError>,
+
+	/// The second backend’s result.
+	pub actual: Result<[u8; HASH_SIZE], BcryptError>,
+}
+
+impl Mismatch {
+	/// The key the backends were given.
+	pub fn key(&self) -> &[u8] {
+		&self.key[..self.key_len]
+	}
+}
+
+/// SplitMix64, for generating reproducible test inputs. Not cryptographically secure.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+	fn next(&mut self) -> u64 {
+		self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+		let mut z = self.0;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+		z ^ (z >> 31)
+	}
+
+	fn below(&mut self, n: u64) -> u64 {
+		self.next() % n
+	}
+}
+
+/// Hashes `cases` pseudorandom inputs derived from `seed` with both backends, returning the first input they disagree on, if any.
+///
+/// The inputs cover every key length from 0 up to one byte over the limit, keys containing 0 bytes, and work factors 4 and 5. The same seed always produces the same inputs, so a mismatch can be reproduced.
+pub fn compare<A: BcryptBackend, B: BcryptBackend>(expected: A, actual: B, cases: u32, seed: u64) -> Option<Mismatch> {
+	let mut rng = SplitMix64(seed);
+
+	for _ in 0..cases {
+		let mut key = [0_u8; KEY_SIZE_MAX + 1];
+		let key_len = rng.below(key.len() as u64 + 1) as usize;
+
+		for b in &mut key[..key_len] {
+			*b = rng.below(255) as u8 + 1;
+		}
+
+		if key_len != 0 && rng.below(16) == 0 {
+			key[rng.below(key_len as u64) as usize] = 0;
+		}
+
+		let mut salt = [0_u8; SALT_SIZE];
+
+		for b in &mut salt {
+			*b = rng.next() as u8;
+		}
+
+		let salt = Salt::from_bytes(&salt);
+		let work_factor = if rng.below(8) == 0 { WorkFactor::EXP5 } else { WorkFactor::EXP4 };
+
+		let expected_result = expected.hash(&key[..key_len], &salt, work_factor);
+		let actual_result = actual.hash(&key[..key_len], &salt, work_factor);
+
+		if expected_result != actual_result {
+			return Some(Mismatch {
+				key,
+				key_len,
+				salt,
+				work_factor,
+				expected: expected_result,
+				actual: actual_result,
+			});
+		}
+	}
+
+	None
+}
+
+/// A reason a backend failed [`conformance`].
+#[derive(Clone, Debug)]
+pub enum ConformanceFailure {
+	/// The backend gave the wrong result for a known-answer test.
+	KnownAnswer {
+		/// The index of the test.
+		index: usize,
+
+		/// The backend’s result.
+		actual: Result<[u8; HASH_SIZE], BcryptError>,
+	},
+
+	/// The backend hashed a batch differently from the same inputs one at a time.
+	Batch {
+		/// The index of the differing job within the batch.
+		index: usize,
+	},
+
+	/// The backend disagreed with the portable implementation.
+	Mismatch(Mismatch),
+}
+
+type KnownAnswer = (&'static [u8], WorkFactor, [u8; SALT_SIZE], [u8; HASH_SIZE]);
+
+/// Known-answer tests from pyca/bcrypt, covering a maximum-length key and high-bit bytes.
+const KNOWN_ANSWERS: [KnownAnswer; 3] = [
+	(b"Kk4DQuMMfZL9o", WorkFactor::EXP4, *b"\x79\x76\x2b\xe9\x97\x0f\x5b\xe7\x3a\xc7\x7c\x0e\x4f\x0a\x38\x51", *b"\xdb\x8f\x03\x60\xd2\xaa\x48\xe1\x41\x55\x98\xbb\xc1\xb5\xc0\xd9\x10\x30\x43\xea\x39\x68\x6a"),
+	(b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789", WorkFactor::EXP5, *b"\x71\xd7\x9f\x82\x18\xa3\x92\x59\xa7\xa2\x9a\xab\xb2\xdb\xaf\xc3", *b"\xee\xee\x31\xf8\x09\x19\x92\x04\x25\x88\x10\x02\xd1\x40\xd5\x55\xb2\x8a\x5c\x72\xe0\x0f\x09"),
+	(b"\xa3", WorkFactor::EXP5, *b"\x05\x03\x00\x85\xd5\xed\x4c\x17\x6b\x2a\xc3\xcb\xee\x47\x29\x1c", *b"\x51\xcf\x6e\x8d\xda\x3a\x01\x0d\x4c\xaf\x11\xe9\x67\x7a\xd2\x36\x84\x98\xff\xca\x96\x9c\x4b"),
+];
+
+/// Checks that a backend behaves like the portable implementation: on known-answer tests, on invalid keys, in batches, and on pseudorandom inputs. Returns the first failure, if any.
+pub fn conformance<B: BcryptBackend>(backend: &B) -> Option<ConformanceFailure> {
+	for (index, &(key, work_factor, salt, hash)) in KNOWN_ANSWERS.iter().enumerate() {
+		let actual = backend.hash(key, &Salt::from_bytes(&salt), work_factor);
+
+		if actual != Ok(hash) {
+			return Some(ConformanceFailure::KnownAnswer { index, actual });
+		}
+	}
+
+	let salts = KNOWN_ANSWERS.map(|(_, _, salt, _)| Salt::from_bytes(&salt));
+	let jobs = [
+		HashJob { key: KNOWN_ANSWERS[0].0, salt: &salts[0], work_factor: KNOWN_ANSWERS[0].1 },
+		HashJob { key: &[0xaa; KEY_SIZE_MAX + 1], salt: &salts[1], work_factor: WorkFactor::EXP4 },
+		HashJob { key: b"\xa3\0", salt: &salts[2], work_factor: WorkFactor::EXP4 },
+		HashJob { key: b"", salt: &salts[1], work_factor: WorkFactor::EXP4 },
+	];
+	let mut results = [Err(BcryptError::Length); 4];
+	backend.hash_batch(&jobs, &mut results);
+
+	for (index, (job, result)) in jobs.iter().zip(&results).enumerate() {
+		if *result != backend.hash(job.key, job.salt, job.work_factor) {
+			return Some(ConformanceFailure::Batch { index });
+		}
+	}
+
+	compare(Portable, backend, 32, 0x636f_6e66_6f72_6d73).map(ConformanceFailure::Mismatch)
+}
@@ -0,0 +1,120 @@
+//! bcrypt’s base64 variant: a different alphabet from the standard one, and no padding.
+
+pub(crate) const ALPHABET: &[u8; 64] = b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// The number of base64 characters needed to encode `len` bytes.
+pub const fn encoded_len(len: usize) -> usize {
+	(len * 4).div_ceil(3)
+}
+
+/// `0xff` if `low <= c <= high`, and 0 otherwise, without branching on `c`.
+const fn range_mask(c: u8, low: u8, high: u8) -> u8 {
+	let c = c as i32;
+	(((low as i32 - 1 - c) & (c - high as i32 - 1)) >> 8) as u8
+}
+
+/// Decodes one character into its sextet and `0xff`, or into 0 and 0 if it’s outside the alphabet. Hash strings are parsed on the way to comparing their hashes, so this is done with arithmetic rather than branches or table lookups, which could leak the hash through timing or the cache.
+const fn decode_char(c: u8) -> (u8, u8) {
+	let slashes = range_mask(c, b'.', b'/');
+	let upper = range_mask(c, b'A', b'Z');
+	let lower = range_mask(c, b'a', b'z');
+	let digits = range_mask(c, b'0', b'9');
+
+	let sextet = (slashes & c.wrapping_sub(b'.'))
+		| (upper & c.wrapping_sub(b'A' - 2))
+		| (lower & c.wrapping_sub(b'a' - 28))
+		| (digits & c.wrapping_add(54 - b'0'));
+
+	(sextet, slashes | upper | lower | digits)
+}
+
+/// Whether every character of `input` is in the alphabet. It looks at every character rather than stopping at the first bad one, in constant time and in a loop the compiler can vectorize.
+pub const fn is_valid(input: &[u8]) -> bool {
+	let mut valid = 0xff_u8;
+	let mut i = 0;
+
+	while i < input.len() {
+		valid &= decode_char(input[i]).1;
+		i += 1;
+	}
+
+	valid == 0xff
+}
+
+/// Encodes `input` into `output`, which has to be exactly [`encoded_len`]`(input.len())` bytes long.
+pub const fn encode(input: &[u8], output: &mut [u8]) {
+	assert!(output.len() == encoded_len(input.len()));
+
+	let mut i = 0;
+	let mut o = 0;
+
+	while i < input.len() {
+		let b0 = input[i];
+		let b1 = if i + 1 < input.len() { input[i + 1] } else { 0 };
+		let b2 = if i + 2 < input.len() { input[i + 2] } else { 0 };
+		let sextets = [
+			b0 >> 2,
+			(b0 & 0x03) << 4 | b1 >> 4,
+			(b1 & 0x0f) << 2 | b2 >> 6,
+			b2 & 0x3f,
+		];
+
+		let mut k = 0;
+
+		while k < 4 && o < output.len() {
+			output[o] = ALPHABET[sextets[k] as usize];
+			o += 1;
+			k += 1;
+		}
+
+		i += 3;
+	}
+}
+
+/// Decodes `input` into `output`, which has to be exactly as long as the bytes `input` encodes. Returns `None` if `input` has the wrong length or contains a character outside the alphabet.
+///
+/// Bits past the end of the last byte are ignored, so more than one encoding can decode to the same bytes; see [`is_canonical`]. Decoding takes the same time for any input of the same length, whatever its characters.
+pub const fn decode(input: &[u8], output: &mut [u8]) -> Option<()> {
+	if input.len() != encoded_len(output.len()) {
+		return None;
+	}
+
+	let mut valid = 0xff_u8;
+	let mut acc = 0_u32;
+	let mut bits = 0;
+	let mut i = 0;
+	let mut o = 0;
+
+	while i < input.len() {
+		let (sextet, mask) = decode_char(input[i]);
+		valid &= mask;
+		acc = acc << 6 | sextet as u32;
+		bits += 6;
+
+		if bits >= 8 {
+			bits -= 8;
+
+			if o < output.len() {
+				output[o] = (acc >> bits) as u8;
+				o += 1;
+			}
+		}
+
+		i += 1;
+	}
+
+	if valid == 0xff { Some(()) } else { None }
+}
+
+/// Whether the unused low bits of the last character of a valid encoding are all zero, which is how every encoder produces it.
+pub const fn is_canonical(input: &[u8]) -> bool {
+	let unused_bits = (input.len() * 6) % 8;
+
+	match input.last() {
+		Some(&c) => {
+			let (sextet, valid) = decode_char(c);
+			(valid == 0xff) & (sextet & ((1 << unused_bits) - 1) == 0)
+		}
+		None => true,
+	}
+}
@@ -0,0 +1,28 @@
+//! A backend calling the `bcrypt` crate’s `hash_with_salt` and parsing the hash string it formats, for differential testing against the most widely used Rust implementation. It allocates, so it isn’t meant for anything else.
+
+use super::backend::BcryptBackend;
+use super::mcf::HashString;
+use super::{BcryptError, HASH_SIZE, Salt, WorkFactor, check_key};
+
+/// The `bcrypt` crate.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BcryptCrate;
+
+impl BcryptBackend for BcryptCrate {
+	fn name(&self) -> &'static str {
+		"bcrypt-crate"
+	}
+
+	fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		check_key(key)?;
+
+		let hashed = bcrypt_reference::hash_with_salt(key, work_factor.log_rounds(), salt.to_bytes())
+			.expect("bcrypt crate rejected a valid key")
+			.format_for_version(bcrypt_reference::Version::TwoB);
+		let hash_string = HashString::parse(&hashed).expect("invalid bcrypt crate output");
+
+		assert!(hash_string.work_factor() == work_factor && hash_string.salt().to_bytes() == salt.to_bytes(), "unexpected bcrypt crate output");
+
+		Ok(hash_string.hash())
+	}
+}
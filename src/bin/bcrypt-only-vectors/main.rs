@@ -0,0 +1,101 @@
+//! `bcrypt-only-vectors`: an exhaustive corpus of cheap test vectors for other implementations and fuzzers – every key length from 0 to 72 with a few byte patterns each, under boundary salts and every version prefix.
+//!
+//! The output is the format [`bcrypt_only::vectors::check_vectors`] reads: one vector per line, the key in hexadecimal, a comma, and the hash string. Lines starting with `#` are comments. The corpus is the same on every run.
+
+use std::env;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+
+use bcrypt_only::mcf::{HashString, Version};
+use bcrypt_only::{KEY_SIZE_MAX, SALT_SIZE, Salt, WorkFactor, bcrypt};
+
+#[cfg(test)]
+mod tests;
+
+const USAGE: &str = "usage: bcrypt-only-vectors [--cost N]\n";
+
+/// Salts at the edges of the salt space, and ones whose encoding exercises every base64 position.
+const SALTS: [[u8; SALT_SIZE]; 4] = [
+	[0x00; SALT_SIZE],
+	[0xff; SALT_SIZE],
+	[0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa, 0x55, 0xaa],
+	[0x00, 0x10, 0x83, 0x10, 0x51, 0x87, 0x20, 0x92, 0x8b, 0x30, 0xd3, 0x8f, 0x41, 0x14, 0x93, 0x51],
+];
+
+const VERSIONS: [Version; 3] = [Version::V2a, Version::V2b, Version::V2y];
+
+/// The keys of length `len`: printable ASCII, bytes with the high bit set – which some historical implementations mishandled – and a repeating pattern of every nonzero byte.
+fn keys(len: usize) -> [Vec<u8>; 3] {
+	[
+		(0..len).map(|i| b'a' + (i % 26) as u8).collect(),
+		(0..len).map(|i| 0x80 | i as u8).collect(),
+		(0..len).map(|i| 1 + (i % 255) as u8).collect(),
+	]
+}
+
+/// Every vector at `work_factor`, in output order.
+pub fn vectors(work_factor: WorkFactor) -> impl Iterator<Item = (Vec<u8>, HashString)> {
+	(0..=KEY_SIZE_MAX).flat_map(move |len| {
+		IntoIterator::into_iter(keys(len)).flat_map(move |key| {
+			SALTS.iter().flat_map(move |salt| {
+				let salt = Salt::from_bytes(salt);
+				let hash = bcrypt(&key, &salt, work_factor).unwrap();
+				let key = key.clone();
+				VERSIONS.iter().map(move |&version| (key.clone(), HashString::new(version, work_factor, &salt, &hash)))
+			})
+		})
+	})
+}
+
+/// Writes the corpus.
+pub fn write<W: Write>(out: &mut W, work_factor: WorkFactor) -> io::Result<()> {
+	writeln!(out, "# bcrypt test vectors from bcrypt-only-vectors --cost {}", work_factor.log_rounds())?;
+	writeln!(out, "# Each line: the key in hexadecimal, a comma, and the hash string.")?;
+
+	for (key, hash) in vectors(work_factor) {
+		for b in &key {
+			write!(out, "{:02x}", b)?;
+		}
+
+		writeln!(out, ",{}", hash)?;
+	}
+
+	Ok(())
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Option<WorkFactor> {
+	let mut work_factor = WorkFactor::EXP4;
+
+	while let Some(arg) = args.next() {
+		let value = match arg.strip_prefix("--cost=") {
+			Some(value) => value.to_owned(),
+			None if arg == "--cost" => args.next()?,
+			None => return None,
+		};
+
+		work_factor = WorkFactor::exp(value.parse().ok()?)?;
+	}
+
+	Some(work_factor)
+}
+
+fn main() -> ExitCode {
+	let work_factor = match parse_args(env::args().skip(1)) {
+		Some(work_factor) => work_factor,
+		None => {
+			eprint!("{}", USAGE);
+			return ExitCode::from(2);
+		}
+	};
+
+	let stdout = io::stdout();
+	let mut out = BufWriter::new(stdout.lock());
+
+	match write(&mut out, work_factor).and_then(|()| out.flush()) {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(err) => {
+			eprintln!("bcrypt-only-vectors: {}", err);
+			ExitCode::FAILURE
+		}
+	}
+}
@@ -0,0 +1,32 @@
+use bcrypt_only::WorkFactor;
+use bcrypt_only::vectors::check_vectors;
+
+use super::{parse_args, write};
+
+#[test]
+fn corpus() {
+	let mut out = Vec::new();
+	write(&mut out, WorkFactor::EXP4).unwrap();
+
+	let out = String::from_utf8(out).unwrap();
+	let lines: Vec<_> = out.lines().collect();
+	assert_eq!(lines.len(), 2 + 73 * 3 * 4 * 3);
+	assert!(lines[2].starts_with(",$2a$04$......................"));
+	assert!(lines.last().unwrap().starts_with("010203") && lines.last().unwrap().contains(",$2y$04$"));
+
+	// Checking every vector takes a while in a debug build, so check a sample.
+	let sample: String = lines.iter().step_by(17).map(|line| format!("{}\n", line)).collect();
+	assert_eq!(check_vectors(sample.as_bytes()).unwrap(), lines.len().div_ceil(17) - 1);
+}
+
+#[test]
+fn arguments() {
+	let args = |args: &[&str]| parse_args(args.iter().map(|&arg| arg.to_owned()));
+
+	assert_eq!(args(&[]), Some(WorkFactor::EXP4));
+	assert_eq!(args(&["--cost", "5"]), Some(WorkFactor::EXP5));
+	assert_eq!(args(&["--cost=6"]), Some(WorkFactor::EXP6));
+	assert_eq!(args(&["--cost"]), None);
+	assert_eq!(args(&["--cost=3"]), None);
+	assert_eq!(args(&["--seed=1"]), None);
+}
@@ -0,0 +1,135 @@
+//! Command-line argument parsing: a command, then `--name value` or `--name=value` options and positional arguments in any order.
+
+use std::ffi::{OsStr, OsString};
+
+use bcrypt_only::Password;
+
+use super::CliError;
+
+/// One parsed argument.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Arg {
+	/// `--name`, without its value, which [`Args::value`] reads.
+	Option(String),
+
+	/// A positional argument.
+	Positional(OsString),
+}
+
+/// The arguments after the program name.
+pub struct Args {
+	args: Vec<OsString>,
+	position: usize,
+	pending_value: Option<OsString>,
+	options_ended: bool,
+}
+
+impl Args {
+	pub fn new<I: IntoIterator<Item = OsString>>(args: I) -> Self {
+		Self {
+			args: args.into_iter().collect(),
+			position: 0,
+			pending_value: None,
+			options_ended: false,
+		}
+	}
+
+	/// The subcommand, which has to come first.
+	pub fn command(&mut self) -> Result<String, CliError> {
+		match self.args.get(self.position) {
+			Some(command) => {
+				self.position += 1;
+				command.to_str().map(String::from).ok_or_else(|| CliError::Usage("invalid command".into()))
+			}
+			None => Err(CliError::Usage("no command given".into())),
+		}
+	}
+
+	/// Removes every `--name` flag before a `--`, returning whether there were any – for flags every command takes.
+	pub fn take_flag(&mut self, name: &str) -> bool {
+		let flag = format!("--{}", name);
+		let mut end = self.args[self.position..].iter().position(|arg| arg == "--").map_or(self.args.len(), |i| self.position + i);
+		let mut found = false;
+		let mut i = self.position;
+
+		while i < end {
+			if self.args[i] == *flag {
+				self.args.remove(i);
+				end -= 1;
+				found = true;
+			} else {
+				i += 1;
+			}
+		}
+
+		found
+	}
+
+	/// The next argument, if any.
+	pub fn next(&mut self) -> Result<Option<Arg>, CliError> {
+		if let Some(value) = self.pending_value.take() {
+			return Err(CliError::Usage(format!("unexpected value {:?}", value)));
+		}
+
+		let arg = match self.args.get(self.position) {
+			Some(arg) => arg.clone(),
+			None => return Ok(None),
+		};
+
+		self.position += 1;
+
+		if self.options_ended {
+			return Ok(Some(Arg::Positional(arg)));
+		}
+
+		let text = match arg.to_str() {
+			Some(text) if text.starts_with("--") => text,
+			_ => return Ok(Some(Arg::Positional(arg))),
+		};
+
+		if text == "--" {
+			self.options_ended = true;
+			return self.next();
+		}
+
+		match text[2..].split_once('=') {
+			Some((name, value)) => {
+				self.pending_value = Some(value.into());
+				Ok(Some(Arg::Option(name.into())))
+			}
+			None => Ok(Some(Arg::Option(text[2..].into()))),
+		}
+	}
+
+	/// The value of the option just returned by [`next`](Self::next).
+	pub fn value(&mut self, name: &str) -> Result<OsString, CliError> {
+		if let Some(value) = self.pending_value.take() {
+			return Ok(value);
+		}
+
+		match self.args.get(self.position) {
+			Some(value) => {
+				self.position += 1;
+				Ok(value.clone())
+			}
+			None => Err(CliError::Usage(format!("--{} needs a value", name))),
+		}
+	}
+
+	/// The value of the option just returned by [`next`](Self::next), as UTF-8.
+	pub fn value_str(&mut self, name: &str) -> Result<String, CliError> {
+		self.value(name)?
+			.into_string()
+			.map_err(|_| CliError::Usage(format!("--{} needs a UTF-8 value", name)))
+	}
+}
+
+/// An argument as a password, converted as [`Password::from_os_str`] describes.
+pub fn os_password(arg: &OsStr) -> Result<Password, CliError> {
+	Password::from_os_str(arg).ok_or_else(|| CliError::Usage("argument isn’t valid Unicode".into()))
+}
+
+/// An unexpected option.
+pub fn unknown(name: &str) -> CliError {
+	CliError::Usage(format!("unknown option --{}", name))
+}
@@ -0,0 +1,170 @@
+//! `bcrypt-only audit`: checking stored hashes against a wordlist, for password-hygiene audits.
+
+use std::fs;
+use std::io::{BufRead, Write};
+use std::num::NonZeroUsize;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Instant;
+
+use bcrypt_only::KEY_SIZE_MAX;
+use bcrypt_only::mcf::{self, HashString};
+
+use super::{CliError, json, workers};
+use super::args::{Arg, Args, unknown};
+use super::password::Password;
+use super::verify::parse_hash;
+
+/// A stored hash under audit, labeled with its user name or position.
+struct Target {
+	label: String,
+	hash: HashString,
+	fell: AtomicBool,
+}
+
+/// Parses a hash argument or line: a hash string, or `label:hash` as in an htpasswd file.
+fn parse_target(line: &str, default_label: String) -> Result<Target, CliError> {
+	let line = line.trim();
+
+	let (label, hash) = match line.rsplit_once(':') {
+		Some((label, hash)) => (label.to_owned(), hash),
+		None => (default_label, line),
+	};
+
+	Ok(Target {
+		label,
+		hash: parse_hash(hash)?,
+		fell: AtomicBool::new(false),
+	})
+}
+
+/// Splits a wordlist into words, without line endings, paired with their line numbers. Words bcrypt can’t hash, which can’t match anything, are left out.
+fn words(contents: &[u8]) -> (Vec<(usize, &[u8])>, usize) {
+	let mut lines: Vec<_> = contents.split(|&b| b == b'\n').map(|word| word.strip_suffix(b"\r").unwrap_or(word)).collect();
+
+	if lines.last() == Some(&&b""[..]) {
+		lines.pop();
+	}
+
+	let total = lines.len();
+	let words: Vec<_> = lines.into_iter()
+		.enumerate()
+		.filter(|(_, word)| word.len() <= KEY_SIZE_MAX && !word.contains(&0))
+		.map(|(i, word)| (i + 1, word))
+		.collect();
+	let skipped = total - words.len();
+
+	(words, skipped)
+}
+
+/// Tries every word against every target on up to `jobs` threads, a word at a time against all targets so common passwords are found first, skipping targets that have already fallen. Returns the line number of the first word that matched each target, by target.
+fn audit(targets: &[Target], words: &[(usize, &[u8])], jobs: NonZeroUsize) -> Vec<Option<usize>> {
+	let next = AtomicUsize::new(0);
+	let found = Mutex::new(vec![None; targets.len()]);
+	let total = targets.len() * words.len();
+
+	workers::run(NonZeroUsize::new(jobs.get().min(total)).unwrap_or(NonZeroUsize::MIN), || loop {
+		let i = next.fetch_add(1, Ordering::Relaxed);
+
+		if i >= total {
+			return;
+		}
+
+		let ((line, word), target) = (words[i / targets.len()], &targets[i % targets.len()]);
+
+		if target.fell.load(Ordering::Relaxed) {
+			continue;
+		}
+
+		if mcf::verify(word, &target.hash) == Ok(true) {
+			target.fell.store(true, Ordering::Relaxed);
+
+			// Another thread can find a later word for the same target before this one is recorded.
+			let mut found = found.lock().unwrap_or_else(PoisonError::into_inner);
+			let first = &mut found[i % targets.len()];
+			*first = Some(first.map_or(line, |other: usize| other.min(line)));
+		}
+	});
+
+	found.into_inner().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn run<R: BufRead, W: Write>(mut args: Args, input: &mut R, out: &mut W, json: bool) -> Result<ExitCode, CliError> {
+	let mut wordlist = None;
+	let mut hash_file = None;
+	let mut jobs = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+	let mut targets = Vec::new();
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"wordlist" => wordlist = Some(args.value(&name)?),
+				"hash-file" => hash_file = Some(args.value(&name)?),
+				"jobs" => {
+					let value = args.value_str(&name)?;
+					jobs = value.parse().map_err(|_| CliError::Usage(format!("invalid job count {:?}", value)))?;
+				}
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) => {
+				let value = value.into_string().map_err(|_| CliError::Usage("hash isn’t valid Unicode".into()))?;
+				targets.push(parse_target(&value, format!("argument {}", targets.len() + 1))?);
+			}
+		}
+	}
+
+	if let Some(path) = hash_file {
+		let contents = fs::read_to_string(&path).map_err(|err| CliError::Failed(format!("couldn’t read {:?}: {}", path, err)))?;
+
+		for (i, line) in contents.lines().enumerate() {
+			if !line.trim().is_empty() {
+				targets.push(parse_target(line, format!("line {}", i + 1))?);
+			}
+		}
+	}
+
+	if targets.is_empty() {
+		return Err(CliError::Usage("no hashes given".into()));
+	}
+
+	// The wordlist may contain real passwords, so it’s zeroed when it’s dropped.
+	let contents = Password::from(match wordlist {
+		Some(path) => fs::read(&path).map_err(|err| CliError::Failed(format!("couldn’t read {:?}: {}", path, err)))?,
+		None => {
+			let mut contents = Vec::new();
+			input.read_to_end(&mut contents)?;
+			contents
+		}
+	});
+
+	let (words, skipped) = words(&contents);
+	let start = Instant::now();
+	let found = audit(&targets, &words, jobs);
+	let duration = start.elapsed();
+	let fallen: Vec<_> = targets.iter().zip(&found).filter_map(|(target, word)| word.map(|word| (&target.label, word))).collect();
+
+	if json {
+		let result = json::Object::new()
+			.string("outcome", if fallen.is_empty() { "none fell" } else { "fell" })
+			.raw("hashes", targets.len())
+			.raw("words", words.len())
+			.raw("skipped_words", skipped)
+			.raw("fallen", json::array(fallen.iter().map(|(label, word)| json::Object::new().string("label", label).raw("word", word).finish())))
+			.raw("duration_ms", json::milliseconds(duration));
+		writeln!(out, "{}", result.finish())?;
+	} else {
+		for (label, word) in &fallen {
+			writeln!(out, "{}\tfell to word {}", label, word)?;
+		}
+
+		if skipped != 0 {
+			writeln!(out, "skipped {} words bcrypt can’t hash", skipped)?;
+		}
+
+		writeln!(out, "{} of {} hashes fell", fallen.len(), targets.len())?;
+	}
+
+	Ok(if fallen.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
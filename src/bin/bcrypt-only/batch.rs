@@ -0,0 +1,192 @@
+//! `bcrypt-only batch`: verifying many password and hash pairs from standard input in parallel.
+
+use std::io::{BufRead, Write};
+use std::num::NonZeroUsize;
+use std::process::ExitCode;
+use std::str;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use bcrypt_only::{PASSWORD_SIZE_MAX, mcf};
+
+use super::{CliError, bcrypt_error_message, json, workers};
+use super::args::{Arg, Args, unknown};
+use super::password::Password;
+use super::verify::parse_hash;
+
+/// How many records are read before they’re verified, bounding memory use on large inputs.
+const CHUNK_SIZE: usize = 1024;
+
+/// Room for a record with the longest password a prompt accepts and a hash, so reading one doesn’t reallocate and leave a copy of its password behind.
+const RECORD_CAPACITY: usize = PASSWORD_SIZE_MAX + 64;
+
+/// The outcome for one record.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Outcome {
+	/// The password matched the hash.
+	Match,
+
+	/// The password didn’t match the hash.
+	Mismatch,
+
+	/// The record couldn’t be checked.
+	Error(String),
+}
+
+impl Outcome {
+	fn describe(&self) -> String {
+		match self {
+			Outcome::Match => "match".into(),
+			Outcome::Mismatch => "mismatch".into(),
+			Outcome::Error(message) => format!("error: {}", message),
+		}
+	}
+
+	fn to_json(&self) -> json::Object {
+		match self {
+			Outcome::Match => json::Object::new().string("outcome", "match"),
+			Outcome::Mismatch => json::Object::new().string("outcome", "mismatch"),
+			Outcome::Error(message) => json::Object::new().string("outcome", "error").string("error", message),
+		}
+	}
+}
+
+/// Verifies one `password<TAB>hash` record. The hash is everything after the last tab, so passwords can contain tabs.
+pub fn verify_record(record: &[u8]) -> Outcome {
+	let tab = match record.iter().rposition(|&b| b == b'\t') {
+		Some(tab) => tab,
+		None => return Outcome::Error("expected a password and hash separated by a tab".into()),
+	};
+
+	let (password, hash) = (&record[..tab], &record[tab + 1..]);
+
+	let hash = match str::from_utf8(hash) {
+		Ok(hash) => hash,
+		Err(_) => return Outcome::Error("hash isn’t valid UTF-8".into()),
+	};
+
+	let hash = match parse_hash(hash) {
+		Ok(hash) => hash,
+		Err(err) => return Outcome::Error(err.message().into()),
+	};
+
+	match mcf::verify(password, &hash) {
+		Ok(true) => Outcome::Match,
+		Ok(false) => Outcome::Mismatch,
+		Err(err) => Outcome::Error(bcrypt_error_message(err).into()),
+	}
+}
+
+/// Verifies `records` on up to `jobs` threads, returning the outcomes and how long each took, in order.
+fn verify_all(records: &[Password], jobs: NonZeroUsize) -> Vec<(Outcome, Duration)> {
+	let next = AtomicUsize::new(0);
+
+	let jobs = NonZeroUsize::new(jobs.get().min(records.len())).unwrap_or(NonZeroUsize::MIN);
+
+	let mut outcomes: Vec<(usize, Outcome, Duration)> = workers::run(jobs, || {
+		let mut outcomes = Vec::new();
+
+		loop {
+			let i = next.fetch_add(1, Ordering::Relaxed);
+
+			match records.get(i) {
+				Some(record) => {
+					let start = Instant::now();
+					let outcome = verify_record(record);
+					outcomes.push((i, outcome, start.elapsed()));
+				}
+				None => return outcomes,
+			}
+		}
+	})
+	.into_iter()
+	.flatten()
+	.collect();
+
+	outcomes.sort_unstable_by_key(|&(i, _, _)| i);
+	outcomes.into_iter().map(|(_, outcome, duration)| (outcome, duration)).collect()
+}
+
+/// Reads one record ending with `delimiter`, without the delimiter (or, for newlines, a carriage return before it), into a buffer that’s zeroed when it’s dropped. Returns `None` at the end of the input.
+fn read_record<R: BufRead>(input: &mut R, delimiter: u8) -> Result<Option<Password>, CliError> {
+	let mut record = Vec::with_capacity(RECORD_CAPACITY);
+	let read = input.read_until(delimiter, &mut record);
+
+	if record.last() == Some(&delimiter) {
+		record.pop();
+
+		if delimiter == b'\n' && record.last() == Some(&b'\r') {
+			record.pop();
+		}
+	}
+
+	// Wrapped before anything can fail, so even a partial record is zeroed.
+	let record = Password::from(record);
+
+	if read? == 0 {
+		return Ok(None);
+	}
+
+	Ok(Some(record))
+}
+
+pub fn run<R: BufRead, W: Write>(mut args: Args, input: &mut R, out: &mut W, json: bool) -> Result<ExitCode, CliError> {
+	let mut delimiter = b'\n';
+	let mut jobs = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"null" => delimiter = b'\0',
+				"jobs" => {
+					let value = args.value_str(&name)?;
+					jobs = value.parse().map_err(|_| CliError::Usage(format!("invalid job count {:?}", value)))?;
+				}
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) => return Err(CliError::Usage(format!("unexpected argument {:?}", value))),
+		}
+	}
+
+	let mut line_number = 0;
+	let mut all_matched = true;
+
+	loop {
+		let mut records = Vec::new();
+
+		while records.len() < CHUNK_SIZE {
+			match read_record(input, delimiter)? {
+				Some(record) => records.push(record),
+				None => break,
+			}
+		}
+
+		if records.is_empty() {
+			break;
+		}
+
+		let end_of_input = records.len() < CHUNK_SIZE;
+
+		for (outcome, duration) in verify_all(&records, jobs) {
+			line_number += 1;
+			all_matched &= outcome == Outcome::Match;
+
+			if json {
+				write!(out, "{}", outcome.to_json().raw("record", line_number).raw("duration_ms", json::milliseconds(duration)).finish())?;
+			} else {
+				write!(out, "{}\t{}", line_number, outcome.describe())?;
+			}
+
+			out.write_all(&[delimiter])?;
+		}
+
+		out.flush()?;
+
+		if end_of_input {
+			break;
+		}
+	}
+
+	Ok(if all_matched { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
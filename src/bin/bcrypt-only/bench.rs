@@ -0,0 +1,86 @@
+//! `bcrypt-only bench`: timing each work factor on this machine and recommending one for a target latency.
+
+use std::io::Write;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use bcrypt_only::clock::Clock;
+use bcrypt_only::{Salt, WorkFactor, bcrypt, calibrate};
+
+use super::{CliError, json};
+use super::args::{Arg, Args, unknown};
+use super::hash::parse_cost;
+
+/// The target latency used when none is given.
+pub const DEFAULT_TARGET: Duration = Duration::from_millis(250);
+
+fn parse_target(target: &str) -> Result<Duration, CliError> {
+	match target.parse::<u64>() {
+		Ok(ms) if ms > 0 => Ok(Duration::from_millis(ms)),
+		_ => Err(CliError::Usage(format!("invalid target {:?}; expected a positive number of milliseconds", target))),
+	}
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+	duration.as_secs_f64() * 1000.0
+}
+
+/// Times one hash at `work_factor`.
+fn time_hash<C: Clock>(work_factor: WorkFactor, clock: &C) -> Result<Duration, CliError> {
+	let start = clock.now();
+	bcrypt(b"benchmark", &Salt::from_bytes(&[0; 16]), work_factor)?;
+	Ok(clock.now().saturating_sub(start))
+}
+
+/// Prints the time each work factor takes, from the lowest up to `max_cost` or until one takes more than twice the target, then the work factor [`calibrate`] recommends for the target.
+pub fn run<W: Write, C: Clock>(mut args: Args, out: &mut W, clock: &C, json: bool) -> Result<ExitCode, CliError> {
+	let mut target = DEFAULT_TARGET;
+	let mut max_cost = WorkFactor::EXP31;
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"target" => target = parse_target(&args.value_str(&name)?)?,
+				"max-cost" => max_cost = parse_cost(&args.value_str(&name)?)?,
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) => return Err(CliError::Usage(format!("unexpected argument {:?}", value))),
+		}
+	}
+
+	let mut results = Vec::new();
+
+	if !json {
+		writeln!(out, "cost  latency")?;
+	}
+
+	for log_rounds in 4..=max_cost.log_rounds() {
+		let elapsed = time_hash(WorkFactor::exp(log_rounds).unwrap(), clock)?;
+
+		if json {
+			results.push(json::Object::new().raw("cost", log_rounds).raw("duration_ms", json::milliseconds(elapsed)).finish());
+		} else {
+			writeln!(out, "{:>4}  {:.3} ms", log_rounds, milliseconds(elapsed))?;
+			out.flush()?;
+		}
+
+		if elapsed > target * 2 {
+			break;
+		}
+	}
+
+	let recommended = calibrate(target, clock).map_err(|err| CliError::Failed(err.to_string()))?;
+
+	if json {
+		let result = json::Object::new()
+			.string("outcome", "benchmarked")
+			.raw("target_ms", target.as_millis())
+			.raw("results", json::array(results))
+			.raw("recommended_cost", recommended.log_rounds());
+		writeln!(out, "{}", result.finish())?;
+	} else {
+		writeln!(out, "recommended cost for {} ms: {}", target.as_millis(), recommended.log_rounds())?;
+	}
+
+	Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,117 @@
+//! `bcrypt-only gen-vectors`: generating deterministic test vectors for other bcrypt implementations to check themselves against.
+//!
+//! The output is the format [`bcrypt_only::vectors::check_vectors`] reads: one vector per line, the key in hexadecimal, a comma, and the hash string, which carries the cost and salt. Lines starting with `#` are comments.
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::process::ExitCode;
+
+use bcrypt_only::mcf::{self, HashString};
+use bcrypt_only::{KEY_SIZE_MAX, SALT_SIZE, Salt, WorkFactor};
+
+use super::{CliError, json};
+use super::args::{Arg, Args, unknown};
+use super::hash::parse_cost;
+
+/// SplitMix64, a small generator that’s fully determined by its seed. Not for salts anywhere else.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+	fn next_u64(&mut self) -> u64 {
+		self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+		let mut z = self.0;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+		z ^ (z >> 31)
+	}
+
+	/// A number from 0 up to but not including `n`.
+	fn below(&mut self, n: u64) -> u64 {
+		self.next_u64() % n
+	}
+}
+
+/// Keys at the edges of what bcrypt accepts, emitted before the random ones.
+fn edge_keys() -> Vec<Vec<u8>> {
+	vec![
+		Vec::new(),
+		b"U".to_vec(),
+		"\u{a3}".as_bytes().to_vec(),
+		vec![0xff; 1],
+		vec![0x80; 8],
+		b"0123456789".repeat(8)[..KEY_SIZE_MAX - 1].to_vec(),
+		b"0123456789".repeat(8)[..KEY_SIZE_MAX].to_vec(),
+		vec![0xff; KEY_SIZE_MAX],
+	]
+}
+
+/// Generates `count` vectors at `work_factor` from `seed`: the edge-case keys, then keys of random lengths and bytes, each with a random salt. Keys have no zero bytes, which bcrypt rejects.
+pub fn vectors(seed: u64, count: usize, work_factor: WorkFactor) -> Vec<(Vec<u8>, HashString)> {
+	let mut rng = SplitMix64(seed);
+	let mut keys = edge_keys().into_iter();
+
+	(0..count)
+		.map(|_| {
+			let key = keys.next().unwrap_or_else(|| {
+				let len = rng.below(KEY_SIZE_MAX as u64 + 1) as usize;
+				(0..len).map(|_| 1 + rng.below(255) as u8).collect()
+			});
+
+			let mut salt = [0_u8; SALT_SIZE];
+			salt.iter_mut().for_each(|b| *b = rng.next_u64() as u8);
+
+			let hash = mcf::hash(&key, &Salt::from_bytes(&salt), work_factor).unwrap();
+			(key, hash)
+		})
+		.collect()
+}
+
+pub fn run<W: Write>(mut args: Args, out: &mut W, json: bool) -> Result<ExitCode, CliError> {
+	let mut seed = 0;
+	let mut count = 64;
+	let mut work_factor = WorkFactor::EXP4;
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"seed" => {
+					let value = args.value_str(&name)?;
+					seed = value.parse().map_err(|_| CliError::Usage(format!("invalid seed {:?}", value)))?;
+				}
+				"count" => {
+					let value = args.value_str(&name)?;
+					count = value.parse().map_err(|_| CliError::Usage(format!("invalid count {:?}", value)))?;
+				}
+				"cost" => work_factor = parse_cost(&args.value_str(&name)?)?,
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) => return Err(CliError::Usage(format!("unexpected argument {:?}", value))),
+		}
+	}
+
+	if !json {
+		writeln!(out, "# bcrypt test vectors from bcrypt-only gen-vectors --seed {} --count {} --cost {}", seed, count, work_factor.log_rounds())?;
+		writeln!(out, "# Each line: the key in hexadecimal, a comma, and the $2b$ hash string.")?;
+	}
+
+	for (key, hash) in vectors(seed, count, work_factor) {
+		let mut key_hex = String::with_capacity(key.len() * 2);
+
+		for b in &key {
+			write!(key_hex, "{:02x}", b).unwrap();
+		}
+
+		if json {
+			let vector = json::Object::new()
+				.string("key_hex", &key_hex)
+				.string("salt", &hash.salt().to_string())
+				.raw("cost", work_factor.log_rounds())
+				.string("hash", hash.as_str());
+			writeln!(out, "{}", vector.finish())?;
+		} else {
+			writeln!(out, "{},{}", key_hex, hash)?;
+		}
+	}
+
+	Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,111 @@
+//! `bcrypt-only hash`: hashing a password into a hash string.
+
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use bcrypt_only::backend::{BcryptBackend, DefaultBackend};
+use bcrypt_only::mcf::{HashString, Version};
+use bcrypt_only::{Salt, WorkFactor};
+
+use super::args::{Arg, Args, os_password, unknown};
+use super::password::{self, PasswordSource};
+use super::{CliError, json, parse_error_message};
+
+/// The work factor used when none is given.
+pub const DEFAULT_COST: WorkFactor = WorkFactor::EXP12;
+
+pub fn parse_cost(cost: &str) -> Result<WorkFactor, CliError> {
+	cost.parse::<u32>()
+		.ok()
+		.and_then(WorkFactor::exp)
+		.ok_or_else(|| CliError::Usage(format!("invalid cost {:?}; expected 4 to 31", cost)))
+}
+
+pub fn parse_version(version: &str) -> Result<Version, CliError> {
+	match version.trim_start_matches('$').trim_end_matches('$') {
+		"2a" => Ok(Version::V2a),
+		"2b" => Ok(Version::V2b),
+		"2y" => Ok(Version::V2y),
+		_ => Err(CliError::Usage(format!("invalid version {:?}; expected 2a, 2b, or 2y", version))),
+	}
+}
+
+/// The version’s name in a hash string, like `2b`.
+pub fn version_name(version: Version) -> &'static str {
+	match version {
+		Version::V2a => "2a",
+		Version::V2b => "2b",
+		Version::V2y => "2y",
+	}
+}
+
+/// Generates a salt from the operating system’s random number generator.
+pub fn random_salt() -> Result<Salt, CliError> {
+	#[cfg(any(unix, windows, target_os = "wasi"))]
+	{
+		Ok(Salt::generate(bcrypt_only::rng::OsRng))
+	}
+
+	#[cfg(not(any(unix, windows, target_os = "wasi")))]
+	{
+		Err(CliError::Failed("no random number generator on this platform; pass --salt".into()))
+	}
+}
+
+/// Hashes a password with the given parameters into a hash string.
+pub fn hash_password(password: &[u8], version: Version, work_factor: WorkFactor, salt: &Salt) -> Result<HashString, CliError> {
+	let hash = DefaultBackend::default().hash(password, salt, work_factor)?;
+	Ok(HashString::new(version, work_factor, salt, &hash))
+}
+
+pub fn run<R: BufRead, W: Write>(mut args: Args, input: &mut R, out: &mut W, password_source: PasswordSource, json: bool) -> Result<ExitCode, CliError> {
+	let mut work_factor = DEFAULT_COST;
+	let mut version = Version::V2b;
+	let mut salt = None;
+	let mut password = None;
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"cost" => work_factor = parse_cost(&args.value_str(&name)?)?,
+				"version" => version = parse_version(&args.value_str(&name)?)?,
+				"salt" => {
+					let value = args.value_str(&name)?;
+					salt = Some(value.parse::<Salt>().map_err(|err| CliError::Usage(format!("invalid salt {:?}: {}", value, parse_error_message(err))))?);
+				}
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) if password.is_none() => password = Some(os_password(&value)?),
+			Arg::Positional(value) => return Err(CliError::Usage(format!("unexpected argument {:?}", value))),
+		}
+	}
+
+	let password = match password {
+		Some(password) => password,
+		None => password::read(input, &mut io::stderr(), password_source, true)?,
+	};
+
+	let salt = match salt {
+		Some(salt) => salt,
+		None => random_salt()?,
+	};
+
+	let start = Instant::now();
+	let hash = hash_password(&password, version, work_factor, &salt)?;
+	let duration = start.elapsed();
+
+	if json {
+		let result = json::Object::new()
+			.string("outcome", "hashed")
+			.string("hash", hash.as_str())
+			.string("version", version_name(version))
+			.raw("cost", work_factor.log_rounds())
+			.raw("duration_ms", json::milliseconds(duration));
+		writeln!(out, "{}", result.finish())?;
+	} else {
+		writeln!(out, "{}", hash)?;
+	}
+
+	Ok(ExitCode::SUCCESS)
+}
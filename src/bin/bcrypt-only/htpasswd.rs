@@ -0,0 +1,313 @@
+//! `bcrypt-only htpasswd`: adding, updating, deleting, and verifying users in an Apache htpasswd file with `$2y$` entries.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, ExitCode};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use bcrypt_only::mcf::{self, Version};
+
+use super::args::{Arg, Args, os_password, unknown};
+use super::hash::{DEFAULT_COST, hash_password, parse_cost, random_salt};
+use super::password::{self, Password, PasswordSource};
+use super::verify::{parse_hash, verification};
+use super::{CliError, json};
+
+/// Whether `line` is `user`’s entry.
+fn is_entry(line: &[u8], user: &str) -> bool {
+	line.strip_prefix(user.as_bytes()).is_some_and(|rest| rest.first() == Some(&b':'))
+}
+
+fn read_error(path: &Path, err: io::Error) -> CliError {
+	CliError::Failed(format!("couldn’t read {}: {}", path.display(), err))
+}
+
+/// Reads an htpasswd file a line at a time, keeping each line’s ending, so it can be copied exactly.
+struct Lines {
+	reader: BufReader<File>,
+	line: Vec<u8>,
+}
+
+impl Lines {
+	fn open(path: &Path) -> Result<Self, CliError> {
+		Ok(Self {
+			reader: BufReader::new(File::open(path).map_err(|err| read_error(path, err))?),
+			line: Vec::new(),
+		})
+	}
+
+	/// The next line, including its line ending if it has one.
+	fn next_line(&mut self) -> io::Result<Option<&[u8]>> {
+		self.line.clear();
+
+		Ok(match self.reader.read_until(b'\n', &mut self.line)? {
+			0 => None,
+			_ => Some(&self.line),
+		})
+	}
+}
+
+/// Finds `user`’s entry in a file, returning everything after the `:` with any line ending removed.
+fn find(path: &Path, user: &str) -> Result<Option<Vec<u8>>, CliError> {
+	let mut lines = Lines::open(path)?;
+
+	while let Some(line) = lines.next_line().map_err(|err| read_error(path, err))? {
+		if is_entry(line, user) {
+			let entry = &line[user.len() + 1..];
+			let entry = entry.strip_suffix(b"\n").unwrap_or(entry);
+			let entry = entry.strip_suffix(b"\r").unwrap_or(entry);
+			return Ok(Some(entry.to_vec()));
+		}
+	}
+
+	Ok(None)
+}
+
+/// Whether `find` would find `user` in a file, treating a missing file as empty if `create` is set.
+fn exists(path: &Path, user: &str, create: bool) -> Result<bool, CliError> {
+	match find(path, user) {
+		Err(_) if create && !path.exists() => Ok(false),
+		result => result.map(|entry| entry.is_some()),
+	}
+}
+
+/// A temporary file for a rewritten htpasswd file, removed when dropped unless it’s been renamed into place.
+struct Temporary {
+	path: PathBuf,
+	renamed: bool,
+}
+
+impl Temporary {
+	/// Creates a new file next to `path`, under a name no other run will pick, with `mode` on Unix – never following a link or opening a file that’s already there.
+	fn create(path: &Path, mode: u32) -> io::Result<(Self, File)> {
+		let mut options = OpenOptions::new();
+		options.write(true).create_new(true);
+
+		#[cfg(unix)]
+		std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+
+		#[cfg(not(unix))]
+		let _ = mode;
+
+		let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.subsec_nanos());
+
+		let mut attempt = 0;
+
+		loop {
+			let mut name = path.as_os_str().to_owned();
+			name.push(format!(".{}.{:x}.{}.tmp", process::id(), nanos, attempt));
+			let temporary = PathBuf::from(name);
+
+			match options.open(&temporary) {
+				Ok(file) => return Ok((Self { path: temporary, renamed: false }, file)),
+				Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+				Err(err) => return Err(err),
+			}
+		}
+	}
+
+	fn rename_to(mut self, path: &Path) -> io::Result<()> {
+		fs::rename(&self.path, path)?;
+		self.renamed = true;
+		Ok(())
+	}
+}
+
+impl Drop for Temporary {
+	fn drop(&mut self) {
+		if !self.renamed {
+			let _ = fs::remove_file(&self.path);
+		}
+	}
+}
+
+/// Copies an htpasswd file a line at a time into a temporary file next to it, replacing `user`’s entry with `replacement`, or removing it if that’s `None`, then renames the copy over the original. Every other line – comments, other users, entries that aren’t bcrypt – comes through byte for byte, and a failure partway through leaves the old file in place and removes the copy. A replacement for a user with no entry is added at the end, and a missing file is treated as empty if `create` is set.
+///
+/// The copy is only ever readable by whoever can read the original: it’s created with the original’s permissions, or for a new file, readable only by its owner.
+///
+/// Returns whether `user` had an entry.
+fn rewrite(path: &Path, user: &str, replacement: Option<&str>, create: bool) -> Result<bool, CliError> {
+	let mut lines = match Lines::open(path) {
+		Err(_) if create && !path.exists() => None,
+		lines => Some(lines?),
+	};
+
+	let write_error = |err: io::Error| CliError::Failed(format!("couldn’t write {}: {}", path.display(), err));
+
+	let permissions = match &lines {
+		Some(lines) => Some(lines.reader.get_ref().metadata().map_err(|err| read_error(path, err))?.permissions()),
+		None => None,
+	};
+
+	#[cfg(unix)]
+	let mode = permissions.as_ref().map_or(0o600, |permissions| std::os::unix::fs::PermissionsExt::mode(permissions) & 0o777);
+
+	#[cfg(not(unix))]
+	let mode = 0;
+
+	let (temporary, file) = Temporary::create(path, mode).map_err(write_error)?;
+	let mut out = BufWriter::new(file);
+	let mut found = false;
+	let mut ends_with_newline = true;
+
+	if let Some(lines) = &mut lines {
+		while let Some(line) = lines.next_line().map_err(|err| read_error(path, err))? {
+			ends_with_newline = line.ends_with(b"\n");
+
+			if found || !is_entry(line, user) {
+				out.write_all(line).map_err(write_error)?;
+				continue;
+			}
+
+			found = true;
+
+			if let Some(replacement) = replacement {
+				let ending: &[u8] = if line.ends_with(b"\r\n") {
+					b"\r\n"
+				} else if ends_with_newline {
+					b"\n"
+				} else {
+					b""
+				};
+
+				write!(out, "{}:{}", user, replacement).map_err(write_error)?;
+				out.write_all(ending).map_err(write_error)?;
+			}
+		}
+	}
+
+	if !found && replacement.is_none() {
+		return Ok(false);
+	}
+
+	if let (false, Some(replacement)) = (found, replacement) {
+		if !ends_with_newline {
+			out.write_all(b"\n").map_err(write_error)?;
+		}
+
+		writeln!(out, "{}:{}", user, replacement).map_err(write_error)?;
+	}
+
+	let file = out.into_inner().map_err(|err| write_error(err.into_error()))?;
+
+	if let Some(permissions) = permissions {
+		file.set_permissions(permissions).map_err(write_error)?;
+	}
+
+	file.sync_all().map_err(write_error)?;
+	drop(file);
+	temporary.rename_to(path).map_err(write_error)?;
+	Ok(found)
+}
+
+fn check_user(user: OsString) -> Result<String, CliError> {
+	match user.into_string() {
+		Ok(user) if !user.is_empty() && !user.contains([':', '\n', '\r']) => Ok(user),
+		Ok(user) => Err(CliError::Usage(format!("invalid user name {:?}", user))),
+		Err(user) => Err(CliError::Usage(format!("invalid user name {:?}", user))),
+	}
+}
+
+fn read_password<R: BufRead>(input: &mut R, password_source: PasswordSource, password: Option<OsString>, confirm: bool) -> Result<Password, CliError> {
+	match password {
+		Some(password) => os_password(&password),
+		None => password::read(input, &mut io::stderr(), password_source, confirm),
+	}
+}
+
+pub fn run<R: BufRead, W: Write>(mut args: Args, input: &mut R, out: &mut W, password_source: PasswordSource, json: bool) -> Result<ExitCode, CliError> {
+	let mut work_factor = DEFAULT_COST;
+	let mut positional = Vec::new();
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"cost" => work_factor = parse_cost(&args.value_str(&name)?)?,
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) => positional.push(value),
+		}
+	}
+
+	let mut positional = positional.into_iter();
+
+	let (action, path, user) = match (positional.next(), positional.next(), positional.next()) {
+		(Some(action), Some(path), Some(user)) => (action, PathBuf::from(path), check_user(user)?),
+		_ => return Err(CliError::Usage("htpasswd needs an action, a file, and a user".into())),
+	};
+
+	let password = positional.next();
+
+	if let Some(value) = positional.next() {
+		return Err(CliError::Usage(format!("unexpected argument {:?}", value)));
+	}
+
+	let action = action.to_str().unwrap_or("");
+
+	match action {
+		"add" | "update" => {
+			let existing = exists(&path, &user, action == "add")?;
+
+			match (action, existing) {
+				("add", true) => return Err(CliError::Failed(format!("user {} already exists in {}", user, path.display()))),
+				("update", false) => return Err(CliError::Failed(format!("user {} not found in {}", user, path.display()))),
+				_ => {}
+			}
+
+			let password = read_password(input, password_source, password, true)?;
+			let start = Instant::now();
+			let hash = hash_password(&password, Version::V2y, work_factor, &random_salt()?)?;
+			let duration = start.elapsed();
+
+			rewrite(&path, &user, Some(&hash), action == "add")?;
+
+			if json {
+				let result = json::Object::new()
+					.string("outcome", if action == "add" { "added" } else { "updated" })
+					.string("user", &user)
+					.string("version", "2y")
+					.raw("cost", work_factor.log_rounds())
+					.raw("duration_ms", json::milliseconds(duration));
+				writeln!(out, "{}", result.finish())?;
+			}
+
+			Ok(ExitCode::SUCCESS)
+		}
+		"delete" => {
+			if password.is_some() {
+				return Err(CliError::Usage("delete doesn’t take a password".into()));
+			}
+
+			if !rewrite(&path, &user, None, false)? {
+				return Err(CliError::Failed(format!("user {} not found in {}", user, path.display())));
+			}
+
+			if json {
+				writeln!(out, "{}", json::Object::new().string("outcome", "deleted").string("user", &user).finish())?;
+			}
+
+			Ok(ExitCode::SUCCESS)
+		}
+		"verify" => {
+			let hash = match find(&path, &user)? {
+				Some(hash) => String::from_utf8_lossy(&hash).into_owned(),
+				None => return Err(CliError::Failed(format!("user {} not found in {}", user, path.display()))),
+			};
+
+			let hash = parse_hash(&hash)?;
+			let password = read_password(input, password_source, password, false)?;
+			let start = Instant::now();
+			let matches = mcf::verify(&password, &hash)?;
+			let duration = start.elapsed();
+
+			if json {
+				writeln!(out, "{}", verification(matches, &hash, duration).string("user", &user).finish())?;
+			}
+
+			Ok(if matches { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+		}
+		_ => Err(CliError::Usage(format!("unknown htpasswd action {:?}; expected add, update, delete, or verify", action))),
+	}
+}
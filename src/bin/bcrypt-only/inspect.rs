@@ -0,0 +1,166 @@
+//! `bcrypt-only inspect`: decoding a hash string into its parts, including hash strings the library would reject.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+use std::mem;
+use std::process::ExitCode;
+
+use bcrypt_only::base64;
+use bcrypt_only::mcf::{HashString, SALT_STRING_SIZE};
+use bcrypt_only::{HASH_SIZE, SALT_SIZE, WorkFactor};
+
+use super::{CliError, json, parse_error_message};
+use super::args::{Arg, Args, unknown};
+
+fn hex(bytes: &[u8]) -> String {
+	let mut s = String::with_capacity(bytes.len() * 2);
+
+	for b in bytes {
+		write!(s, "{:02x}", b).unwrap();
+	}
+
+	s
+}
+
+/// One base64 field of a hash string, decoded as far as it can be.
+struct Field<'a> {
+	chars: &'a str,
+	bytes: Option<Vec<u8>>,
+	canonical: bool,
+}
+
+impl<'a> Field<'a> {
+	fn decode(chars: &'a str, len: usize) -> Self {
+		let mut bytes = vec![0; len];
+		let bytes = base64::decode(chars.as_bytes(), &mut bytes).map(|()| bytes);
+		let canonical = bytes.is_some() && base64::is_canonical(chars.as_bytes());
+		Self { chars, bytes, canonical }
+	}
+
+	/// The field re-encoded the way every encoder produces it.
+	fn canonical_chars(&self) -> Option<String> {
+		let bytes = self.bytes.as_ref()?;
+		let mut chars = vec![0; base64::encoded_len(bytes.len())];
+		base64::encode(bytes, &mut chars);
+		String::from_utf8(chars).ok()
+	}
+
+	fn describe(&self, name: &str, expected_chars: usize, report: &mut Report) {
+		report.text.push(format!("{}: {}", name, self.chars));
+		report.json = mem::take(&mut report.json).string(name, self.chars);
+
+		match &self.bytes {
+			Some(bytes) => {
+				report.text.push(format!("{} (hex): {}", name, hex(bytes)));
+				report.json = mem::take(&mut report.json).string(&format!("{}_hex", name), &hex(bytes));
+			}
+			None if self.chars.len() != expected_chars => report.problems.push(format!("{} is {} characters instead of {}", name, self.chars.len(), expected_chars)),
+			None => report.problems.push(format!("{} contains characters outside bcrypt’s base64 alphabet", name)),
+		}
+
+		if self.bytes.is_some() && !self.canonical {
+			report.problems.push(format!("{} is non-canonical: its last character has nonzero unused bits (canonical: {})", name, self.canonical_chars().unwrap()));
+		}
+	}
+}
+
+/// What’s known about a hash string, as text lines and as a `--json` object.
+struct Report {
+	text: Vec<String>,
+	json: json::Object,
+	problems: Vec<String>,
+}
+
+/// Describes `hash`, writing text or `--json` to `out` and returning whether it’s a valid hash string.
+pub fn inspect<W: Write>(hash: &str, out: &mut W, json: bool) -> Result<bool, CliError> {
+	let parts = match hash.strip_prefix('$').map(|rest| rest.splitn(3, '$').collect::<Vec<_>>()) {
+		Some(parts) if parts.len() == 3 && parts[0].starts_with('2') => parts,
+		_ => return Err(CliError::Failed(format!("{:?} isn’t a bcrypt hash string", hash))),
+	};
+
+	let mut report = Report {
+		text: vec![format!("version: {}", parts[0])],
+		json: json::Object::new().string("version", parts[0]),
+		problems: Vec::new(),
+	};
+
+	if !matches!(parts[0], "2a" | "2b" | "2y") {
+		report.problems.push(format!("version {} isn’t supported; expected 2a, 2b, or 2y", parts[0]));
+	}
+
+	match parts[1].parse::<u32>().ok().and_then(WorkFactor::exp) {
+		Some(work_factor) => {
+			report.text.push(format!("cost: {} ({} rounds)", work_factor.log_rounds(), 1_u64 << work_factor.log_rounds()));
+			report.json = report.json.raw("cost", work_factor.log_rounds()).raw("rounds", 1_u64 << work_factor.log_rounds());
+
+			if parts[1].len() != 2 {
+				report.problems.push(format!("cost {:?} isn’t two digits", parts[1]));
+			}
+		}
+		None => {
+			report.text.push(format!("cost: {}", parts[1]));
+			report.json = report.json.string("cost", parts[1]);
+			report.problems.push(format!("cost {:?} isn’t between 04 and 31", parts[1]));
+		}
+	}
+
+	let rest = parts[2];
+	let split = rest.char_indices().nth(SALT_STRING_SIZE).map_or(rest.len(), |(i, _)| i);
+	let (salt_chars, hash_chars) = rest.split_at(split);
+
+	Field::decode(salt_chars, SALT_SIZE).describe("salt", SALT_STRING_SIZE, &mut report);
+	Field::decode(hash_chars, HASH_SIZE).describe("hash", base64::encoded_len(HASH_SIZE), &mut report);
+
+	let valid = report.problems.is_empty();
+
+	if valid {
+		// Anything that gets this far should parse; the library’s parser has the final say.
+		HashString::parse(hash).map_err(|err| CliError::Failed(parse_error_message(err).into()))?;
+	}
+
+	if json {
+		let result = report.json
+			.string("outcome", if valid { "valid" } else { "invalid" })
+			.raw("problems", json::array(report.problems.iter().map(|problem| json::string(problem))));
+		writeln!(out, "{}", result.finish())?;
+	} else {
+		for line in &report.text {
+			writeln!(out, "{}", line)?;
+		}
+
+		writeln!(out, "valid: {}", if valid { "yes" } else { "no" })?;
+
+		for problem in &report.problems {
+			writeln!(out, "  - {}", problem)?;
+		}
+	}
+
+	Ok(valid)
+}
+
+pub fn run<R: BufRead, W: Write>(mut args: Args, input: &mut R, out: &mut W, json: bool) -> Result<ExitCode, CliError> {
+	let mut hash = None;
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => return Err(unknown(&name)),
+			Arg::Positional(value) if hash.is_none() => hash = Some(value.into_string().map_err(|_| CliError::Usage("hash isn’t valid Unicode".into()))?),
+			Arg::Positional(value) => return Err(CliError::Usage(format!("unexpected argument {:?}", value))),
+		}
+	}
+
+	let hash = match hash {
+		Some(hash) => hash,
+		None => {
+			let mut line = String::new();
+
+			if input.read_line(&mut line)? == 0 {
+				return Err(CliError::Failed("no hash given".into()));
+			}
+
+			line
+		}
+	};
+
+	Ok(if inspect(hash.trim(), out, json)? { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
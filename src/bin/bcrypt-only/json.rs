@@ -0,0 +1,236 @@
+//! Just enough JSON for one object per line: reading flat objects whose values are strings, numbers, booleans, or null, and writing objects for `--json` output.
+
+use std::fmt::{self, Write as _};
+use std::time::Duration;
+use std::str::CharIndices;
+
+/// A value in a flat object.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+	/// A string, unescaped.
+	String(String),
+
+	/// A number, boolean, or null, as written.
+	Literal(String),
+}
+
+impl Value {
+	/// The value as JSON.
+	pub fn to_json(&self) -> String {
+		match self {
+			Value::String(s) => string(s),
+			Value::Literal(literal) => literal.clone(),
+		}
+	}
+}
+
+/// `s` as a JSON string.
+pub fn string(s: &str) -> String {
+	let mut json = String::with_capacity(s.len() + 2);
+	json.push('"');
+
+	for c in s.chars() {
+		match c {
+			'"' => json.push_str("\\\""),
+			'\\' => json.push_str("\\\\"),
+			'\n' => json.push_str("\\n"),
+			'\r' => json.push_str("\\r"),
+			'\t' => json.push_str("\\t"),
+			c if c < ' ' => write!(json, "\\u{:04x}", c as u32).unwrap(),
+			c => json.push(c),
+		}
+	}
+
+	json.push('"');
+	json
+}
+
+/// The four hex digits of a `\\u` escape.
+fn hex_unit(chars: &mut CharIndices) -> Result<u32, String> {
+	let hex: String = chars.take(4).map(|(_, c)| c).collect();
+
+	if hex.len() != 4 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+		return Err("invalid \\u escape".into());
+	}
+
+	u32::from_str_radix(&hex, 16).map_err(|_| "invalid \\u escape".into())
+}
+
+/// An array of values that are already JSON.
+pub fn array<I: IntoIterator<Item = String>>(values: I) -> String {
+	let values: Vec<_> = values.into_iter().collect();
+	format!("[{}]", values.join(","))
+}
+
+/// A duration in milliseconds, as a JSON number.
+pub fn milliseconds(duration: Duration) -> String {
+	format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// A JSON object, built a field at a time.
+pub struct Object {
+	json: String,
+}
+
+impl Object {
+	pub fn new() -> Self {
+		Self { json: "{".into() }
+	}
+
+	/// Adds a field whose value is already JSON.
+	pub fn raw(mut self, key: &str, value: impl fmt::Display) -> Self {
+		if self.json.len() > 1 {
+			self.json.push(',');
+		}
+
+		write!(self.json, "{}:{}", string(key), value).unwrap();
+		self
+	}
+
+	/// Adds a string field.
+	pub fn string(self, key: &str, value: &str) -> Self {
+		self.raw(key, string(value))
+	}
+
+	/// The object as JSON.
+	pub fn finish(mut self) -> String {
+		self.json.push('}');
+		self.json
+	}
+}
+
+impl Default for Object {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+struct Parser<'a> {
+	rest: &'a str,
+}
+
+impl Parser<'_> {
+	fn skip_whitespace(&mut self) {
+		self.rest = self.rest.trim_start_matches([' ', '\t', '\n', '\r']);
+	}
+
+	fn expect(&mut self, c: char) -> Result<(), String> {
+		self.skip_whitespace();
+
+		match self.rest.strip_prefix(c) {
+			Some(rest) => {
+				self.rest = rest;
+				Ok(())
+			}
+			None => Err(format!("expected {:?}", c)),
+		}
+	}
+
+	fn string(&mut self) -> Result<String, String> {
+		self.expect('"')?;
+		let mut s = String::new();
+		let mut chars = self.rest.char_indices();
+
+		loop {
+			match chars.next() {
+				Some((i, '"')) => {
+					self.rest = &self.rest[i + 1..];
+					return Ok(s);
+				}
+				Some((_, '\\')) => match chars.next() {
+					Some((_, '"')) => s.push('"'),
+					Some((_, '\\')) => s.push('\\'),
+					Some((_, '/')) => s.push('/'),
+					Some((_, 'b')) => s.push('\u{8}'),
+					Some((_, 'f')) => s.push('\u{c}'),
+					Some((_, 'n')) => s.push('\n'),
+					Some((_, 'r')) => s.push('\r'),
+					Some((_, 't')) => s.push('\t'),
+					Some((_, 'u')) => {
+						let high = hex_unit(&mut chars)?;
+
+						let code_point = if (0xd800..0xdc00).contains(&high) {
+							if chars.next().map(|(_, c)| c) != Some('\\') || chars.next().map(|(_, c)| c) != Some('u') {
+								return Err("unpaired surrogate".into());
+							}
+
+							let low = hex_unit(&mut chars)?;
+
+							if !(0xdc00..0xe000).contains(&low) {
+								return Err("unpaired surrogate".into());
+							}
+
+							0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+						} else {
+							high
+						};
+
+						s.push(char::from_u32(code_point).ok_or("unpaired surrogate")?);
+					}
+					_ => return Err("invalid escape".into()),
+				},
+				Some((_, c)) if c < ' ' => return Err("control character in string".into()),
+				Some((_, c)) => s.push(c),
+				None => return Err("unterminated string".into()),
+			}
+		}
+	}
+
+	fn value(&mut self) -> Result<Value, String> {
+		self.skip_whitespace();
+
+		if self.rest.starts_with('"') {
+			return self.string().map(Value::String);
+		}
+
+		let end = self.rest.find([',', '}', ' ', '\t', '\r', '\n']).unwrap_or(self.rest.len());
+		let (literal, rest) = self.rest.split_at(end);
+
+		let valid = matches!(literal, "true" | "false" | "null")
+			|| (!literal.is_empty() && literal.parse::<f64>().is_ok() && literal.bytes().all(|b| b.is_ascii_digit() || b"-+.eE".contains(&b)));
+
+		if !valid {
+			return Err(format!("unsupported value {:?}; expected a string, number, boolean, or null", literal));
+		}
+
+		self.rest = rest;
+		Ok(Value::Literal(literal.into()))
+	}
+}
+
+/// Parses one flat JSON object into its fields, in order.
+pub fn parse_object(line: &str) -> Result<Vec<(String, Value)>, String> {
+	let mut parser = Parser { rest: line };
+	let mut fields = Vec::new();
+
+	parser.expect('{')?;
+	parser.skip_whitespace();
+
+	if let Some(rest) = parser.rest.strip_prefix('}') {
+		parser.rest = rest;
+	} else {
+		loop {
+			let key = parser.string()?;
+			parser.expect(':')?;
+			fields.push((key, parser.value()?));
+			parser.skip_whitespace();
+
+			match parser.rest.chars().next() {
+				Some(',') => parser.rest = &parser.rest[1..],
+				Some('}') => {
+					parser.rest = &parser.rest[1..];
+					break;
+				}
+				_ => return Err("expected \",\" or \"}\"".into()),
+			}
+		}
+	}
+
+	parser.skip_whitespace();
+
+	if !parser.rest.is_empty() {
+		return Err("unexpected text after object".into());
+	}
+
+	Ok(fields)
+}
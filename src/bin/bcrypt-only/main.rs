@@ -0,0 +1,206 @@
+//! The `bcrypt-only` command-line tool, for minting and checking bcrypt hash strings without ad-hoc scripts.
+
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
+
+mod args;
+mod audit;
+mod batch;
+mod bench;
+mod gen_vectors;
+mod hash;
+mod htpasswd;
+mod inspect;
+mod json;
+mod migrate;
+mod password;
+mod verify;
+mod workers;
+
+#[cfg(test)]
+mod tests;
+
+use bcrypt_only::BcryptError;
+use bcrypt_only::clock::SystemClock;
+use bcrypt_only::mcf::ParseError;
+
+use args::Args;
+use password::PasswordSource;
+
+const USAGE: &str = "\
+usage: bcrypt-only <command> [--json] [options]
+
+commands:
+  audit [--wordlist FILE] [--hash-file FILE] [--jobs N] [HASH...]
+      Tries each word of FILE, or of standard input, against each HASH and each
+      line of the hash file (a hash, or USER:HASH), on N threads. Reports
+      which hashes fell and the line of the word that matched. Exits with
+      status 0 if none fell and 1 if any did.
+  batch [--null] [--jobs N]
+      Verifies PASSWORD<TAB>HASH records, one per line of standard input (or
+      NUL-separated, with --null), on N threads. Writes the record number and
+      match, mismatch, or error for each, in order. Exits with status 0 if
+      every record matched and 1 otherwise.
+  bench [--target MS] [--max-cost N]
+      Times each cost on this machine, up to N or until one takes twice the
+      target latency (default 250 ms), and recommends a cost for the target.
+  gen-vectors [--seed N] [--count N] [--cost N]
+      Writes N deterministic test vectors for checking other bcrypt
+      implementations: comment lines starting with #, then one vector per
+      line, the key in hexadecimal, a comma, and the $2b$ hash string. The
+      same seed always gives the same vectors.
+  hash [--cost N] [--version 2a|2b|2y] [--salt SALT] [PASSWORD]
+      Hashes PASSWORD, or a password read from standard input, into a hash
+      string.
+  htpasswd add|update|delete|verify [--cost N] FILE USER [PASSWORD]
+      Adds USER to an htpasswd file with a $2y$ hash, creating the file if
+      needed; changes an existing user’s password; removes a user; or checks
+      a user’s password, exiting with status 0 if it matches and 1 if it
+      doesn’t. Without PASSWORD, the password is read as for verify.
+  inspect [HASH]
+      Decodes HASH, or a line from standard input, into its version, cost,
+      salt, and hash, and reports anything non-standard about it. Exits with
+      status 0 if it’s a valid hash string and 1 if it isn’t.
+  migrate [--format csv|jsonl] [--cost N] [--relabel-2y] [--output FILE] [FILE]
+      Reads id,hash[,password] rows from FILE or standard input and reports
+      how many hashes there are of each version and cost. Rows with a
+      password that matches a hash below $2b$ at cost N are rehashed that
+      way, and written as id,hash rows in the same format to the output
+      file; without --output, they’re only counted. With --relabel-2y, $2y$
+      hashes that aren’t rehashed are written with a $2b$ prefix instead,
+      which is the same algorithm. Lists rows that couldn’t be read or whose
+      password didn’t match, and exits with status 1 if there were any.
+  verify [--hash-file FILE] [HASH]
+      Checks a password against HASH, the first line of FILE, or a line from
+      standard input. The password is prompted for on a terminal, and
+      otherwise read as the next line of standard input. Exits with status 0
+      if it matches and 1 if it doesn’t.
+
+Passwords are prompted for without echo when standard input is a terminal,
+and new passwords are asked for twice; otherwise, they’re read as a line of
+standard input. Prompting only works on Unix and Windows; elsewhere, pipe
+passwords to standard input instead. PASSWORD arguments are visible to other users in process
+listings, so prefer standard input for real passwords.
+
+With --json, each command writes its results as one JSON object with an
+\"outcome\" field (batch writes one per record), and errors are written as
+{\"outcome\": \"error\", \"error\": MESSAGE}. Timings are in \"duration_ms\".
+";
+
+/// An error that stops a command, with the exit status to report it with.
+#[derive(Debug)]
+pub enum CliError {
+	/// The command line was invalid.
+	Usage(String),
+
+	/// The command failed.
+	Failed(String),
+}
+
+impl CliError {
+	fn exit_code(&self) -> ExitCode {
+		ExitCode::from(2)
+	}
+
+	fn message(&self) -> &str {
+		match self {
+			CliError::Usage(message) | CliError::Failed(message) => message,
+		}
+	}
+}
+
+impl From<io::Error> for CliError {
+	fn from(err: io::Error) -> Self {
+		CliError::Failed(err.to_string())
+	}
+}
+
+impl From<BcryptError> for CliError {
+	fn from(err: BcryptError) -> Self {
+		CliError::Failed(bcrypt_error_message(err).into())
+	}
+}
+
+/// Describes a hashing error. The library’s own descriptions are left out of builds with the `compact-errors` feature, so the tool has its own.
+fn bcrypt_error_message(err: BcryptError) -> &'static str {
+	match err {
+		BcryptError::Length => "password too long",
+		BcryptError::ZeroByte => "password contains a NUL character",
+		BcryptError::Empty => "password is empty",
+		_ => "password can’t be hashed",
+	}
+}
+
+/// Describes a hash string parsing error, like [`bcrypt_error_message`].
+fn parse_error_message(err: ParseError) -> &'static str {
+	match err {
+		ParseError::Length => "bcrypt hash string has the wrong length",
+		ParseError::Version => "unsupported bcrypt version",
+		ParseError::WorkFactor => "invalid bcrypt work factor",
+		ParseError::Encoding => "invalid base64 in bcrypt hash string",
+		ParseError::NonCanonical => "non-canonical base64 in bcrypt hash string",
+	}
+}
+
+/// Prompts for passwords when standard input is a terminal, restoring its settings if the prompt is interrupted, and otherwise reads them from it.
+fn password_source(stdin: &io::Stdin) -> PasswordSource {
+	if stdin.is_terminal() {
+		password::restore_terminal_on_interrupt();
+		PasswordSource::Prompt
+	} else {
+		PasswordSource::Input
+	}
+}
+
+fn run(mut args: Args, json: bool) -> Result<ExitCode, CliError> {
+	let command = args.command()?;
+	let stdout = io::stdout();
+	let mut out = stdout.lock();
+
+	match command.as_str() {
+		"audit" => audit::run(args, &mut io::stdin().lock(), &mut out, json),
+		"batch" => batch::run(args, &mut io::stdin().lock(), &mut out, json),
+		"bench" => bench::run(args, &mut out, &SystemClock::new(), json),
+		"gen-vectors" => gen_vectors::run(args, &mut out, json),
+		"hash" => {
+			let stdin = io::stdin();
+			hash::run(args, &mut stdin.lock(), &mut out, password_source(&stdin), json)
+		}
+		"htpasswd" => {
+			let stdin = io::stdin();
+			htpasswd::run(args, &mut stdin.lock(), &mut out, password_source(&stdin), json)
+		}
+		"inspect" => inspect::run(args, &mut io::stdin().lock(), &mut out, json),
+		"migrate" => migrate::run(args, &mut io::stdin().lock(), &mut out, json),
+		"verify" => {
+			let stdin = io::stdin();
+			verify::run(args, &mut stdin.lock(), &mut out, password_source(&stdin), json)
+		}
+		"help" | "--help" | "-h" => {
+			out.write_all(USAGE.as_bytes())?;
+			Ok(ExitCode::SUCCESS)
+		}
+		_ => Err(CliError::Usage(format!("unknown command {:?}", command))),
+	}
+}
+
+fn main() -> ExitCode {
+	let mut args = Args::new(env::args_os().skip(1));
+	let json = args.take_flag("json");
+
+	match run(args, json) {
+		Ok(code) => code,
+		Err(err) => {
+			eprintln!("bcrypt-only: {}", err.message());
+
+			if json {
+				println!("{}", json::Object::new().string("outcome", "error").string("error", err.message()).finish());
+			} else if let CliError::Usage(_) = err {
+				eprint!("{}", USAGE);
+			}
+
+			err.exit_code()
+		}
+	}
+}
@@ -0,0 +1,348 @@
+//! `bcrypt-only migrate`: surveying exported hashes, rehashing the ones below a target cost for rows that come with their passwords, and relabeling `$2y$` hashes as `$2b$`.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::hint::black_box;
+use std::io::{BufRead, BufReader, Write};
+use std::process::ExitCode;
+use std::str;
+
+use bcrypt_only::WorkFactor;
+use bcrypt_only::mcf::{self, HashString, Version};
+
+use super::{CliError, bcrypt_error_message, parse_error_message};
+use super::args::{Arg, Args, unknown};
+use super::hash::{DEFAULT_COST, hash_password, parse_cost, random_salt, version_name};
+use super::json::{self, Value};
+use super::password::Password;
+
+/// The format of the rows being migrated.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+	/// `id,hash[,password]`, with an optional header row and double-quoted fields.
+	Csv,
+
+	/// One `{"id": …, "hash": "…", "password": "…"}` object per line, where `password` is optional.
+	Jsonl,
+}
+
+/// One row: an identifier, kept as written, a stored hash, and maybe the password it’s for.
+#[derive(Debug)]
+pub struct Row {
+	/// The row’s identifier, written back out as it came in.
+	pub id: Value,
+
+	/// The stored hash string.
+	pub hash: String,
+
+	/// The password, from an import that’s already checked it.
+	pub password: Option<Password>,
+}
+
+/// Splits a CSV line into fields. Quoted fields can contain commas and doubled quotes, but not line breaks.
+fn csv_fields(line: &str) -> Result<Vec<String>, String> {
+	let mut fields = Vec::new();
+	let mut chars = line.chars().peekable();
+
+	loop {
+		let mut field = String::new();
+
+		if chars.peek() == Some(&'"') {
+			chars.next();
+
+			loop {
+				match chars.next() {
+					Some('"') if chars.peek() == Some(&'"') => {
+						chars.next();
+						field.push('"');
+					}
+					Some('"') => break,
+					Some(c) => field.push(c),
+					None => return Err("unterminated quoted field".into()),
+				}
+			}
+
+			if !matches!(chars.peek(), Some(',') | None) {
+				return Err("unexpected text after quoted field".into());
+			}
+		} else {
+			while let Some(&c) = chars.peek() {
+				if c == ',' {
+					break;
+				}
+
+				field.push(c);
+				chars.next();
+			}
+		}
+
+		fields.push(field);
+
+		if chars.next().is_none() {
+			return Ok(fields);
+		}
+	}
+}
+
+/// `s` as a CSV field, quoted if it needs to be.
+fn csv_field(s: &str) -> String {
+	if s.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", s.replace('"', "\"\""))
+	} else {
+		s.into()
+	}
+}
+
+fn id_text(id: &Value) -> &str {
+	match id {
+		Value::String(id) | Value::Literal(id) => id,
+	}
+}
+
+impl Format {
+	/// Parses one row, or returns `None` for a CSV header row.
+	pub fn parse_row(self, line: &str) -> Result<Option<Row>, String> {
+		match self {
+			Format::Csv => {
+				let mut fields = csv_fields(line)?.into_iter();
+
+				let (id, hash, password) = match (fields.next(), fields.next(), fields.next(), fields.next()) {
+					(Some(id), Some(hash), password, None) => (id, hash, password),
+					_ => return Err("expected id,hash or id,hash,password".into()),
+				};
+
+				if hash == "hash" {
+					return Ok(None);
+				}
+
+				Ok(Some(Row { id: Value::String(id), hash, password: password.map(|password| Password::from(password.into_bytes())) }))
+			}
+			Format::Jsonl => {
+				let mut id = None;
+				let mut hash = None;
+				let mut password = None;
+
+				for (key, value) in json::parse_object(line)? {
+					match (key.as_str(), value) {
+						("id", value) => id = Some(value),
+						("hash", Value::String(value)) => hash = Some(value),
+						("password", Value::String(value)) => password = Some(Password::from(value.into_bytes())),
+						("password", Value::Literal(literal)) if literal == "null" => {}
+						("hash" | "password", _) => return Err(format!("{:?} has to be a string", key)),
+						_ => {}
+					}
+				}
+
+				match (id, hash) {
+					(Some(id), Some(hash)) => Ok(Some(Row { id, hash, password })),
+					_ => Err("expected \"id\" and \"hash\" fields".into()),
+				}
+			}
+		}
+	}
+
+	/// Formats a row with an identifier and a new hash, without a password.
+	pub fn format_row(self, id: &Value, hash: &HashString) -> String {
+		match self {
+			Format::Csv => format!("{},{}", csv_field(id_text(id)), hash),
+			Format::Jsonl => format!("{{\"id\":{},\"hash\":{}}}", id.to_json(), json::string(hash.as_str())),
+		}
+	}
+}
+
+fn parse_format(format: &str) -> Result<Format, CliError> {
+	match format {
+		"csv" => Ok(Format::Csv),
+		"jsonl" => Ok(Format::Jsonl),
+		_ => Err(CliError::Usage(format!("invalid format {:?}; expected csv or jsonl", format))),
+	}
+}
+
+pub fn run<R: BufRead, W: Write>(mut args: Args, input: &mut R, out: &mut W, json: bool) -> Result<ExitCode, CliError> {
+	let mut format = None;
+	let mut target = DEFAULT_COST;
+	let mut output: Option<OsString> = None;
+	let mut path = None;
+	let mut relabel_v2y = false;
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"format" => format = Some(parse_format(&args.value_str(&name)?)?),
+				"cost" => target = parse_cost(&args.value_str(&name)?)?,
+				"output" => output = Some(args.value(&name)?),
+				"relabel-2y" => relabel_v2y = true,
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) if path.is_none() => path = Some(value),
+			Arg::Positional(value) => return Err(CliError::Usage(format!("unexpected argument {:?}", value))),
+		}
+	}
+
+	let mut file;
+	let input: &mut dyn BufRead = match path {
+		Some(path) => {
+			file = BufReader::new(File::open(&path).map_err(|err| CliError::Failed(format!("couldn’t read {:?}: {}", path, err)))?);
+			&mut file
+		}
+		None => input,
+	};
+
+	let mut distribution: BTreeMap<(&str, WorkFactor), usize> = BTreeMap::new();
+	let mut rows = 0;
+	let mut outdated = 0;
+	let mut updated_rows = Vec::new();
+	let mut rehashed = 0;
+	let mut relabeled = 0;
+	let mut problems = Vec::new();
+	let mut buffer = Vec::new();
+	let mut i = 0;
+
+	// Rows are read a line at a time, into a buffer that’s zeroed at the end, since it can hold passwords.
+	let result = (|| -> Result<(), CliError> {
+		loop {
+			buffer.clear();
+
+			if input.read_until(b'\n', &mut buffer)? == 0 {
+				return Ok(());
+			}
+
+			i += 1;
+
+			let line = match str::from_utf8(&buffer) {
+				Ok(line) => line.trim_end_matches(['\n', '\r']),
+				Err(_) => {
+					problems.push(format!("line {}: isn’t valid UTF-8", i));
+					continue;
+				}
+			};
+
+			if line.trim().is_empty() {
+				continue;
+			}
+
+			// Without a format, a file of objects is JSON Lines.
+			let format = *format.get_or_insert(if line.trim_start().starts_with('{') { Format::Jsonl } else { Format::Csv });
+
+			let row = match format.parse_row(line) {
+				Ok(Some(row)) => row,
+				Ok(None) => continue,
+				Err(err) => {
+					problems.push(format!("line {}: {}", i, err));
+					continue;
+				}
+			};
+
+			rows += 1;
+
+			let hash = match HashString::parse(row.hash.trim()) {
+				Ok(hash) => hash,
+				Err(err) => {
+					problems.push(format!("{}: {}", id_text(&row.id), parse_error_message(err)));
+					continue;
+				}
+			};
+
+			*distribution.entry((version_name(hash.version()), hash.work_factor())).or_insert(0) += 1;
+
+			let relabeled_hash = if relabel_v2y { hash.relabel_v2y() } else { hash };
+
+			// A relabeled hash is written out when it isn’t rehashed.
+			let mut relabel = || {
+				if relabeled_hash != hash {
+					if output.is_some() {
+						updated_rows.push(format.format_row(&row.id, &relabeled_hash));
+					}
+
+					relabeled += 1;
+				}
+			};
+
+			if relabeled_hash.version() == Version::V2b && relabeled_hash.work_factor() >= target {
+				relabel();
+				continue;
+			}
+
+			outdated += 1;
+
+			// Only a password that matches the stored hash is rehashed, so a bad import can’t replace working credentials. Without anywhere to write it, it’s only counted.
+			match row.password.as_ref().map(|password| (password, mcf::verify(password, &hash))) {
+				Some((password, Ok(true))) => {
+					if output.is_some() {
+						updated_rows.push(format.format_row(&row.id, &hash_password(password, Version::V2b, target, &random_salt()?)?));
+					}
+
+					rehashed += 1;
+				}
+				Some((_, Ok(false))) => {
+					problems.push(format!("{}: password doesn’t match the stored hash", id_text(&row.id)));
+					relabel();
+				}
+				Some((_, Err(err))) => {
+					problems.push(format!("{}: {}", id_text(&row.id), bcrypt_error_message(err)));
+					relabel();
+				}
+				None => relabel(),
+			}
+		}
+	})();
+
+	buffer.fill(0);
+	black_box(&mut buffer);
+	result?;
+
+	if let Some(path) = &output {
+		let mut contents = String::new();
+
+		for row in &updated_rows {
+			contents.push_str(row);
+			contents.push('\n');
+		}
+
+		fs::write(path, contents).map_err(|err| CliError::Failed(format!("couldn’t write {:?}: {}", path, err)))?;
+	}
+
+	if json {
+		let distribution = distribution.iter().map(|((version, work_factor), count)| {
+			json::Object::new().string("version", version).raw("cost", work_factor.log_rounds()).raw("count", count).finish()
+		});
+
+		let result = json::Object::new()
+			.string("outcome", if problems.is_empty() { "surveyed" } else { "problems" })
+			.raw("rows", rows)
+			.raw("distribution", json::array(distribution))
+			.raw("target_cost", target.log_rounds())
+			.raw("outdated", outdated)
+			.raw("rehashed", rehashed)
+			.raw("relabeled", relabeled)
+			.raw("written", output.is_some())
+			.raw("problems", json::array(problems.iter().map(|problem| json::string(problem))));
+		writeln!(out, "{}", result.finish())?;
+	} else {
+		writeln!(out, "rows: {}", rows)?;
+		writeln!(out, "version  cost  count")?;
+
+		for ((version, work_factor), count) in &distribution {
+			writeln!(out, "{:<7}  {:>4}  {:>5}", version, work_factor.log_rounds(), count)?;
+		}
+
+		writeln!(out, "below $2b$ at cost {}: {}", target.log_rounds(), outdated)?;
+
+		if output.is_some() {
+			writeln!(out, "rehashed: {}", rehashed)?;
+		} else {
+			writeln!(out, "can be rehashed: {} (pass --output to write them)", rehashed)?;
+		}
+
+		if relabel_v2y {
+			writeln!(out, "relabeled $2y$ to $2b$: {}", relabeled)?;
+		}
+
+		for problem in &problems {
+			writeln!(out, "  - {}", problem)?;
+		}
+	}
+
+	Ok(if problems.is_empty() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
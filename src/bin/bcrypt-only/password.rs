@@ -0,0 +1,130 @@
+//! Reading passwords.
+
+use std::io::{BufRead, Write};
+
+pub use bcrypt_only::Password;
+use bcrypt_only::{prompt_password_to, read_password};
+
+use super::CliError;
+
+/// Where a password comes from when it isn’t an argument.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PasswordSource {
+	/// A line of input, read as-is.
+	Input,
+
+	/// A prompt on the terminal, without echo.
+	Prompt,
+}
+
+/// Reads a password from `source`, writing any prompts to `prompts`. A new password (`confirm`) is prompted for twice, and has to be typed the same way both times.
+pub fn read<R: BufRead, W: Write>(input: &mut R, prompts: &mut W, source: PasswordSource, confirm: bool) -> Result<Password, CliError> {
+	match source {
+		PasswordSource::Input => Ok(read_password(input)?),
+		PasswordSource::Prompt if !confirm => Ok(prompt_password_to("Password: ", input, prompts)?),
+		PasswordSource::Prompt => {
+			let password = prompt_password_to("New password: ", input, prompts)?;
+
+			if *prompt_password_to("Confirm password: ", input, prompts)? != *password {
+				return Err(CliError::Failed("passwords don’t match".into()));
+			}
+
+			Ok(password)
+		}
+	}
+}
+
+/// Restores standard input’s terminal settings, as they are now, if the program is interrupted with Ctrl-C, so a prompt that has turned echo off doesn’t leave it off.
+#[cfg(unix)]
+pub fn restore_terminal_on_interrupt() {
+	// SAFETY: the settings are saved before the handler that reads them is installed, and the buffer is bigger than any platform’s `struct termios`.
+	unsafe {
+		if termios::tcgetattr(0, termios::SAVED.0.get().cast()) == 0 {
+			termios::signal(termios::SIGINT, termios::restore_and_reraise as extern "C" fn(core::ffi::c_int) as usize);
+		}
+	}
+}
+
+/// Restores standard input’s console mode, as it is now, if the program is interrupted with Ctrl-C, so a prompt that has turned echo off doesn’t leave it off.
+#[cfg(windows)]
+pub fn restore_terminal_on_interrupt() {
+	use core::sync::atomic::Ordering;
+
+	let mut mode = 0;
+
+	// SAFETY: `mode` is valid for writes, and `restore` is a valid handler for the life of the program.
+	unsafe {
+		if console::GetConsoleMode(console::GetStdHandle(console::STD_INPUT_HANDLE), &mut mode) != 0 {
+			console::SAVED_MODE.store(mode, Ordering::SeqCst);
+			console::SetConsoleCtrlHandler(Some(console::restore), 1);
+		}
+	}
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn restore_terminal_on_interrupt() {}
+
+#[cfg(unix)]
+mod termios {
+	use core::cell::UnsafeCell;
+	use core::ffi::c_int;
+
+	pub const SIGINT: c_int = 2;
+	pub const SIG_DFL: usize = 0;
+	pub const TCSANOW: c_int = 0;
+
+	/// The terminal settings to restore, saved by `tcgetattr`. Their layout varies by platform, so they’re kept opaque, in more room than any platform needs.
+	#[repr(C, align(8))]
+	pub struct Saved(pub UnsafeCell<[u8; 256]>);
+
+	// SAFETY: the settings are only written once, before the signal handler that reads them is installed.
+	unsafe impl Sync for Saved {}
+
+	pub static SAVED: Saved = Saved(UnsafeCell::new([0; 256]));
+
+	extern "C" {
+		pub fn tcgetattr(fd: c_int, termios: *mut u8) -> c_int;
+		pub fn tcsetattr(fd: c_int, action: c_int, termios: *const u8) -> c_int;
+		pub fn signal(signum: c_int, handler: usize) -> usize;
+		pub fn raise(signum: c_int) -> c_int;
+	}
+
+	/// Restores the saved terminal settings, then lets the signal take its default action.
+	pub extern "C" fn restore_and_reraise(signum: c_int) {
+		// SAFETY: `tcsetattr`, `signal`, and `raise` are async-signal-safe, and the settings were saved before this handler was installed.
+		unsafe {
+			tcsetattr(0, TCSANOW, SAVED.0.get().cast());
+			signal(signum, SIG_DFL);
+			raise(signum);
+		}
+	}
+}
+
+#[cfg(windows)]
+mod console {
+	use core::ffi::c_void;
+	use core::sync::atomic::{AtomicU32, Ordering};
+
+	pub const STD_INPUT_HANDLE: u32 = -10_i32 as u32;
+
+	/// The console mode to restore.
+	pub static SAVED_MODE: AtomicU32 = AtomicU32::new(0);
+
+	#[link(name = "kernel32")]
+	extern "system" {
+		pub fn GetStdHandle(std_handle: u32) -> *mut c_void;
+		pub fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+		pub fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+		pub fn SetConsoleCtrlHandler(handler: Option<extern "system" fn(u32) -> i32>, add: i32) -> i32;
+	}
+
+	/// Restores the saved console mode, then lets the next handler – by default, the one that exits – handle the event.
+	pub extern "system" fn restore(_ctrl_type: u32) -> i32 {
+		// SAFETY: `GetStdHandle` and `SetConsoleMode` take no pointers that need to be valid.
+		unsafe {
+			SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), SAVED_MODE.load(Ordering::SeqCst));
+		}
+
+		0
+	}
+}
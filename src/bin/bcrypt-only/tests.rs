@@ -0,0 +1,457 @@
+use std::cell::Cell;
+use std::ffi::OsString;
+use std::io;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use bcrypt_only::WorkFactor;
+use bcrypt_only::clock::Clock;
+use bcrypt_only::mcf::{self, HashString, Version};
+
+use super::args::{Arg, Args};
+use super::password::{self, PasswordSource};
+use super::verify;
+use super::json::{self, Value};
+use super::{CliError, audit, batch, bench, gen_vectors, hash, htpasswd, inspect, migrate};
+
+fn args(args: &[&str]) -> Args {
+	Args::new(args.iter().map(OsString::from))
+}
+
+/// A clock that advances by a fixed step each time it’s read.
+struct StepClock {
+	now: Cell<Duration>,
+	step: Duration,
+}
+
+impl Clock for StepClock {
+	fn now(&self) -> Duration {
+		let now = self.now.get();
+		self.now.set(now + self.step);
+		now
+	}
+}
+
+fn run_hash(arguments: &[&str], input: &str) -> Result<String, CliError> {
+	let mut out = Vec::new();
+	let code = hash::run(args(arguments), &mut input.as_bytes(), &mut out, PasswordSource::Input, false)?;
+	assert_eq!(code, ExitCode::SUCCESS);
+	Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn argument_parsing() {
+	let mut parsed = args(&["hash", "--cost=5", "--version", "2y", "--", "--salt"]);
+	assert_eq!(parsed.command().unwrap(), "hash");
+	assert_eq!(parsed.next().unwrap(), Some(Arg::Option("cost".into())));
+	assert_eq!(parsed.value_str("cost").unwrap(), "5");
+	assert_eq!(parsed.next().unwrap(), Some(Arg::Option("version".into())));
+	assert_eq!(parsed.value_str("version").unwrap(), "2y");
+	assert_eq!(parsed.next().unwrap(), Some(Arg::Positional("--salt".into())));
+	assert_eq!(parsed.next().unwrap(), None);
+
+	let mut parsed = args(&["--json", "hash", "--json", "--cost", "5", "--", "--json"]);
+	assert!(parsed.take_flag("json"));
+	assert_eq!(parsed.command().unwrap(), "hash");
+	assert!(!parsed.take_flag("json"));
+	assert_eq!(parsed.next().unwrap(), Some(Arg::Option("cost".into())));
+	assert_eq!(parsed.value_str("cost").unwrap(), "5");
+	assert_eq!(parsed.next().unwrap(), Some(Arg::Positional("--json".into())));
+
+	let mut parsed = args(&["--cost=5"]);
+	assert_eq!(parsed.next().unwrap(), Some(Arg::Option("cost".into())));
+	assert!(matches!(parsed.next(), Err(CliError::Usage(_))));
+}
+
+#[test]
+fn password_reading() {
+	let read = |input: &str, source, confirm| password::read(&mut input.as_bytes(), &mut io::sink(), source, confirm).map(|password| password.to_vec());
+
+	assert_eq!(read("secret\r\nother\n", PasswordSource::Input, true).unwrap(), b"secret");
+	assert_eq!(read("secret\nsecret\n", PasswordSource::Prompt, true).unwrap(), b"secret");
+	assert_eq!(read("secret\nother\n", PasswordSource::Prompt, false).unwrap(), b"secret");
+	assert!(matches!(read("secret\nother\n", PasswordSource::Prompt, true), Err(CliError::Failed(_))));
+	assert!(matches!(read("secret\n", PasswordSource::Prompt, true), Err(CliError::Failed(_))));
+	assert!(matches!(read("", PasswordSource::Input, false), Err(CliError::Failed(_))));
+}
+
+#[test]
+fn hash_command() {
+	let expected = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm\n";
+	assert_eq!(run_hash(&["--cost", "4", "--salt", "cVWp4XaNU8a4v1uMRum2SO", "Kk4DQuMMfZL9o"], "").unwrap(), expected);
+	assert_eq!(run_hash(&["--salt=cVWp4XaNU8a4v1uMRum2SO", "--cost=04"], "Kk4DQuMMfZL9o\r\n").unwrap(), expected);
+	assert_eq!(run_hash(&["--cost", "4", "--version", "2y", "--salt", "cVWp4XaNU8a4v1uMRum2SO"], "Kk4DQuMMfZL9o").unwrap(), expected.replacen("2b", "2y", 1));
+
+	let random = run_hash(&["--cost", "4", "password"], "").unwrap();
+	assert!(random.starts_with("$2b$04$") && random.len() == 61);
+
+	assert!(matches!(run_hash(&["--cost", "3", "password"], ""), Err(CliError::Usage(_))));
+	assert!(matches!(run_hash(&["--version", "2x", "password"], ""), Err(CliError::Usage(_))));
+	assert!(matches!(run_hash(&["--salt", "short", "password"], ""), Err(CliError::Usage(_))));
+	assert!(matches!(run_hash(&["--pepper", "x", "password"], ""), Err(CliError::Usage(_))));
+	assert!(matches!(run_hash(&["--cost", "4"], ""), Err(CliError::Failed(_))));
+	assert!(matches!(run_hash(&["--cost", "4", &"x".repeat(73)], ""), Err(CliError::Failed(_))));
+}
+
+#[test]
+fn verify_command() {
+	let hash = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm";
+	let run = |arguments: &[&str], input: &str| verify::run(args(arguments), &mut input.as_bytes(), &mut Vec::new(), PasswordSource::Input, false);
+
+	assert_eq!(run(&[hash], "Kk4DQuMMfZL9o\n").unwrap(), ExitCode::SUCCESS);
+	assert_eq!(run(&[hash], "Kk4DQuMMfZL9O\n").unwrap(), ExitCode::FAILURE);
+	assert_eq!(run(&[], &format!("{}\nKk4DQuMMfZL9o\n", hash)).unwrap(), ExitCode::SUCCESS);
+	assert_eq!(run(&[], &format!("{}\r\nKk4DQuMMfZL9o", hash)).unwrap(), ExitCode::SUCCESS);
+	assert_eq!(run(&[&hash.replacen("2b", "2y", 1)], "Kk4DQuMMfZL9o").unwrap(), ExitCode::SUCCESS);
+
+	let path = std::env::temp_dir().join(format!("bcrypt-only-verify-{}", std::process::id()));
+	std::fs::write(&path, format!("{}\n", hash)).unwrap();
+	let result = run(&["--hash-file", path.to_str().unwrap()], "Kk4DQuMMfZL9o\n");
+	std::fs::remove_file(&path).unwrap();
+	assert_eq!(result.unwrap(), ExitCode::SUCCESS);
+
+	assert!(matches!(run(&[hash, "--hash-file", "x"], "Kk4DQuMMfZL9o"), Err(CliError::Usage(_))));
+	assert!(matches!(run(&[hash, hash], "Kk4DQuMMfZL9o"), Err(CliError::Usage(_))));
+	assert!(matches!(run(&["$2b$04$short"], "Kk4DQuMMfZL9o"), Err(CliError::Failed(_))));
+	assert!(matches!(run(&[hash], ""), Err(CliError::Failed(_))));
+	assert!(matches!(run(&[], ""), Err(CliError::Failed(_))));
+	assert!(matches!(run(&[hash], &"x".repeat(73)), Err(CliError::Failed(_))));
+}
+
+#[test]
+fn inspect_command() {
+	let run = |arguments: &[&str], input: &str| {
+		let mut out = Vec::new();
+		inspect::run(args(arguments), &mut input.as_bytes(), &mut out, false).map(|code| (code, String::from_utf8(out).unwrap()))
+	};
+
+	let hash = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm";
+	let (code, out) = run(&[hash], "").unwrap();
+	assert_eq!(code, ExitCode::SUCCESS);
+	assert_eq!(out, "\
+version: 2b
+cost: 4 (16 rounds)
+salt: cVWp4XaNU8a4v1uMRum2SO
+salt (hex): 79762be9970f5be73ac77c0e4f0a3851
+hash: 026BWLIoQMD/TXg5uZV.0P.uO8m3YEm
+hash (hex): db8f0360d2aa48e1415598bbc1b5c0d9103043ea39686a
+valid: yes
+");
+	assert_eq!(run(&[], &format!("{}\n", hash)).unwrap(), (code, out));
+
+	let (code, out) = run(&["$2b$04$cVWp4XaNU8a4v1uMRum2SP026BWLIoQMD/TXg5uZV.0P.uO8m3YEn"], "").unwrap();
+	assert_eq!(code, ExitCode::FAILURE);
+	assert!(out.contains("salt is non-canonical: its last character has nonzero unused bits (canonical: cVWp4XaNU8a4v1uMRum2SO)"));
+	assert!(out.contains("hash is non-canonical: its last character has nonzero unused bits (canonical: 026BWLIoQMD/TXg5uZV.0P.uO8m3YEm)"));
+
+	let (code, out) = run(&["$2x$4$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3Y"], "").unwrap();
+	assert_eq!(code, ExitCode::FAILURE);
+	assert!(out.contains("version 2x isn’t supported"));
+	assert!(out.contains("cost \"4\" isn’t two digits"));
+	assert!(out.contains("hash is 29 characters instead of 31"));
+
+	assert!(matches!(run(&["password"], ""), Err(CliError::Failed(_))));
+	assert!(matches!(run(&[], ""), Err(CliError::Failed(_))));
+}
+
+#[test]
+fn bench_command() {
+	let run = |arguments: &[&str], step_ms: u64| {
+		let mut out = Vec::new();
+		let clock = StepClock { now: Cell::new(Duration::ZERO), step: Duration::from_millis(step_ms) };
+		bench::run(args(arguments), &mut out, &clock, false).map(|code| (code, String::from_utf8(out).unwrap()))
+	};
+
+	assert_eq!(run(&["--target", "100", "--max-cost", "5"], 40).unwrap(), (ExitCode::SUCCESS, "\
+cost  latency
+   4  40.000 ms
+   5  40.000 ms
+recommended cost for 100 ms: 5
+".into()));
+
+	assert_eq!(run(&["--target=10"], 40).unwrap().1, "\
+cost  latency
+   4  40.000 ms
+recommended cost for 10 ms: 4
+");
+
+	assert!(matches!(run(&["--target", "0"], 40), Err(CliError::Usage(_))));
+	assert!(matches!(run(&["--max-cost", "32"], 40), Err(CliError::Usage(_))));
+	assert!(matches!(run(&["12"], 40), Err(CliError::Usage(_))));
+}
+
+#[test]
+fn batch_command() {
+	let run = |arguments: &[&str], input: &[u8]| {
+		let mut out = Vec::new();
+		batch::run(args(arguments), &mut &input[..], &mut out, false).map(|code| (code, String::from_utf8(out).unwrap()))
+	};
+
+	let hash = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm";
+	let input = format!("Kk4DQuMMfZL9o\t{0}\nKk4DQuMMfZL9O\t{0}\r\nno tab\nKk4DQuMMfZL9o\t$2b$04$short\n{1}\t{0}\nKk4DQuMMfZL9o\t{0}", hash, "x".repeat(73));
+	let expected = "\
+1\tmatch
+2\tmismatch
+3\terror: expected a password and hash separated by a tab
+4\terror: invalid hash \"$2b$04$short\": bcrypt hash string has the wrong length
+5\terror: password too long
+6\tmatch
+";
+
+	assert_eq!(run(&["--jobs", "3"], input.as_bytes()).unwrap(), (ExitCode::FAILURE, expected.into()));
+	assert_eq!(run(&["--jobs=1"], input.as_bytes()).unwrap().1, expected);
+	assert_eq!(run(&["--null"], input.replace('\n', "\0").as_bytes()).unwrap().1, expected.replace('\n', "\0"));
+
+	let matching = format!("a\tb\tc\t{}\n", mcf::hash(b"a\tb\tc", &"cVWp4XaNU8a4v1uMRum2SO".parse().unwrap(), WorkFactor::EXP4).unwrap());
+	assert_eq!(run(&[], matching.as_bytes()).unwrap(), (ExitCode::SUCCESS, "1\tmatch\n".into()));
+	assert_eq!(run(&[], b"").unwrap(), (ExitCode::SUCCESS, String::new()));
+
+	assert!(matches!(run(&["--jobs", "0"], b""), Err(CliError::Usage(_))));
+}
+
+#[test]
+fn htpasswd_command() {
+	let path = std::env::temp_dir().join(format!("bcrypt-only-htpasswd-{}", std::process::id()));
+	let file = path.to_str().unwrap();
+	let run = |arguments: &[&str], input: &str| htpasswd::run(args(arguments), &mut input.as_bytes(), &mut Vec::new(), PasswordSource::Input, false);
+
+	assert!(matches!(run(&["add", "--cost=4", file, "alice"], "first\n"), Ok(ExitCode::SUCCESS)));
+
+	#[cfg(unix)]
+	assert_eq!(std::os::unix::fs::PermissionsExt::mode(&std::fs::metadata(&path).unwrap().permissions()) & 0o777, 0o600);
+	assert!(matches!(run(&["add", "--cost=4", file, "bob", "second"], ""), Ok(ExitCode::SUCCESS)));
+	assert!(matches!(run(&["add", "--cost=4", file, "alice"], "again\n"), Err(CliError::Failed(_))));
+
+	let contents = std::fs::read_to_string(&path).unwrap();
+	let lines: Vec<_> = contents.lines().collect();
+	assert_eq!(lines.len(), 2);
+	assert!(lines[0].starts_with("alice:$2y$04$") && lines[0].len() == 66);
+	assert!(lines[1].starts_with("bob:$2y$04$"));
+
+	assert_eq!(run(&["verify", file, "alice"], "first\n").unwrap(), ExitCode::SUCCESS);
+	assert_eq!(run(&["verify", file, "alice", "second"], "").unwrap(), ExitCode::FAILURE);
+	assert!(matches!(run(&["verify", file, "carol"], "first\n"), Err(CliError::Failed(_))));
+
+	assert!(matches!(run(&["update", "--cost=4", file, "alice", "changed"], ""), Ok(ExitCode::SUCCESS)));
+	assert!(matches!(run(&["update", "--cost=4", file, "carol", "changed"], ""), Err(CliError::Failed(_))));
+	assert_eq!(run(&["verify", file, "alice", "changed"], "").unwrap(), ExitCode::SUCCESS);
+	assert_eq!(std::fs::read_to_string(&path).unwrap().lines().nth(1), Some(lines[1]));
+
+	assert!(matches!(run(&["delete", file, "alice"], ""), Ok(ExitCode::SUCCESS)));
+	assert!(matches!(run(&["delete", file, "alice"], ""), Err(CliError::Failed(_))));
+	assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n", lines[1]));
+
+	assert!(matches!(run(&["add", file, "a:b", "x"], ""), Err(CliError::Usage(_))));
+	assert!(matches!(run(&["rename", file, "bob"], ""), Err(CliError::Usage(_))));
+	assert!(matches!(run(&["add", file], ""), Err(CliError::Usage(_))));
+
+	std::fs::remove_file(&path).unwrap();
+	assert!(matches!(run(&["verify", file, "bob", "second"], ""), Err(CliError::Failed(_))));
+}
+
+#[test]
+fn htpasswd_preserves_other_lines() {
+	let path = std::env::temp_dir().join(format!("bcrypt-only-htpasswd-lines-{}", std::process::id()));
+	let file = path.to_str().unwrap();
+	let run = |arguments: &[&str]| htpasswd::run(args(arguments), &mut "".as_bytes(), &mut Vec::new(), PasswordSource::Input, false);
+
+	let original: &[u8] = b"# managed by hand\r\nalice:$apr1$abc$def\r\n\nbob:{SHA}xyz\ncarol:\xff\xfe";
+	std::fs::write(&path, original).unwrap();
+
+	assert!(matches!(run(&["update", "--cost=4", file, "alice", "changed"]), Ok(ExitCode::SUCCESS)));
+	let contents = std::fs::read(&path).unwrap();
+	assert!(contents.starts_with(b"# managed by hand\r\nalice:$2y$04$"));
+	assert!(contents.ends_with(b"\r\n\nbob:{SHA}xyz\ncarol:\xff\xfe"));
+	assert_eq!(contents.len(), original.len() - b"$apr1$abc$def".len() + 60);
+	assert_eq!(run(&["verify", file, "alice", "changed"]).unwrap(), ExitCode::SUCCESS);
+
+	assert!(matches!(run(&["add", "--cost=4", file, "dave", "new"]), Ok(ExitCode::SUCCESS)));
+	let added = std::fs::read(&path).unwrap();
+	assert!(added.starts_with(&contents));
+	assert!(added[contents.len()..].starts_with(b"\ndave:$2y$04$") && added.ends_with(b"\n"));
+
+	assert!(matches!(run(&["delete", file, "alice"]), Ok(ExitCode::SUCCESS)));
+	assert!(matches!(run(&["delete", file, "alice"]), Err(CliError::Failed(_))));
+	let deleted = std::fs::read(&path).unwrap();
+	assert!(deleted.starts_with(b"# managed by hand\r\n\nbob:{SHA}xyz\ncarol:\xff\xfe\ndave:"));
+	let name = path.file_name().unwrap().to_str().unwrap();
+	let leftovers = std::fs::read_dir(std::env::temp_dir()).unwrap().filter(|entry| entry.as_ref().unwrap().file_name().to_str().is_some_and(|other| other.starts_with(name) && other != name));
+	assert_eq!(leftovers.count(), 0);
+
+	std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn audit_command() {
+	let run = |arguments: &[&str], input: &str| {
+		let mut out = Vec::new();
+		audit::run(args(arguments), &mut input.as_bytes(), &mut out, false).map(|code| (code, String::from_utf8(out).unwrap()))
+	};
+
+	let salt = "cVWp4XaNU8a4v1uMRum2SO".parse().unwrap();
+	let hash = |password: &[u8]| mcf::hash(password, &salt, WorkFactor::EXP4).unwrap().to_string();
+	let wordlist = format!("123456\r\npassword\n{}\nhunter2\nletmein\n", "x".repeat(73));
+
+	let path = std::env::temp_dir().join(format!("bcrypt-only-audit-{}", std::process::id()));
+	std::fs::write(&path, format!("alice:{}\n\nbob:{}\n{}\n", hash(b"hunter2"), hash(b"correct horse"), hash(b"123456"))).unwrap();
+	let result = run(&["--jobs", "3", "--hash-file", path.to_str().unwrap(), &hash(b"letmein")], &wordlist);
+	std::fs::remove_file(&path).unwrap();
+
+	assert_eq!(result.unwrap(), (ExitCode::FAILURE, "\
+argument 1\tfell to word 5
+alice\tfell to word 4
+line 4\tfell to word 1
+skipped 1 words bcrypt can’t hash
+3 of 4 hashes fell
+".into()));
+
+	let wordlist_path = std::env::temp_dir().join(format!("bcrypt-only-audit-wordlist-{}", std::process::id()));
+	std::fs::write(&wordlist_path, "123456\npassword").unwrap();
+	let result = run(&["--wordlist", wordlist_path.to_str().unwrap(), &hash(b"hunter2")], "");
+	std::fs::remove_file(&wordlist_path).unwrap();
+	assert_eq!(result.unwrap(), (ExitCode::SUCCESS, "0 of 1 hashes fell\n".into()));
+
+	assert!(matches!(run(&[], "password\n"), Err(CliError::Usage(_))));
+	assert!(matches!(run(&["$2b$04$short"], "password\n"), Err(CliError::Failed(_))));
+}
+
+#[test]
+fn json_objects() {
+	assert_eq!(json::parse_object(r#" {"id": 12, "hash":"a\"b\\\u00e9\ud83d\ude00", "x": null, "y": -1.5e3} "#).unwrap(), vec![
+		("id".into(), Value::Literal("12".into())),
+		("hash".into(), Value::String("a\"b\\é😀".into())),
+		("x".into(), Value::Literal("null".into())),
+		("y".into(), Value::Literal("-1.5e3".into())),
+	]);
+	assert_eq!(json::parse_object("{}").unwrap(), vec![]);
+
+	for invalid in ["", "{", "{\"a\"}", "{\"a\": [1]}", "{\"a\": nope}", "{\"a\": \"\\ud83d\"}", "{\"a\": \"\\u12\"}", "{\"a\": 1} x", "{\"a\": 1,}"] {
+		assert!(json::parse_object(invalid).is_err(), "{:?}", invalid);
+	}
+
+	assert_eq!(json::string("a\"b\\\n\u{1}é"), r#""a\"b\\\n\u0001é""#);
+}
+
+#[test]
+fn migrate_command() {
+	let run = |arguments: &[&str], input: &str| {
+		let mut out = Vec::new();
+		migrate::run(args(arguments), &mut input.as_bytes(), &mut out, false).map(|code| (code, String::from_utf8(out).unwrap()))
+	};
+
+	let salt = "cVWp4XaNU8a4v1uMRum2SO".parse().unwrap();
+	let hash = |password: &[u8], work_factor| mcf::hash(password, &salt, work_factor).unwrap();
+	let legacy = hash(b"legacy", WorkFactor::EXP4).as_str().replacen("2b", "2a", 1);
+	let current = hash(b"current", WorkFactor::EXP5);
+
+	let csv = format!("id,hash,password\n1,{},legacy\n\"two, quoted\",{}\n3,{},wrong\n4,{},current\n5\n6,$2b$04$short\n", legacy, hash(b"plain", WorkFactor::EXP4), legacy, current);
+	let path = std::env::temp_dir().join(format!("bcrypt-only-migrate-{}", std::process::id()));
+	let result = run(&["--cost", "5", "--output", path.to_str().unwrap()], &csv);
+	let rehashed = std::fs::read_to_string(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+
+	assert_eq!(result.unwrap(), (ExitCode::FAILURE, "\
+rows: 5
+version  cost  count
+2a          4      2
+2b          4      1
+2b          5      1
+below $2b$ at cost 5: 3
+rehashed: 1
+  - 3: password doesn’t match the stored hash
+  - line 6: expected id,hash or id,hash,password
+  - 6: bcrypt hash string has the wrong length
+".into()));
+
+	let (id, new_hash) = rehashed.trim_end().split_once(',').unwrap();
+	assert_eq!(id, "1");
+	let new_hash: HashString = new_hash.parse().unwrap();
+	assert_eq!((new_hash.version(), new_hash.work_factor()), (Version::V2b, WorkFactor::EXP5));
+	assert_eq!(mcf::verify(b"legacy", &new_hash), Ok(true));
+
+	let jsonl = format!("{{\"id\": 7, \"hash\": \"{}\", \"password\": \"legacy\"}}\n{{\"id\": \"x\", \"hash\": \"{}\", \"password\": null}}\n", legacy, current);
+	let (code, out) = run(&["--cost=5"], &jsonl).unwrap();
+	assert_eq!(code, ExitCode::SUCCESS);
+	assert!(out.ends_with("below $2b$ at cost 5: 1\ncan be rehashed: 1 (pass --output to write them)\n"));
+
+	let v2y = current.as_str().replacen("2b", "2y", 1);
+	let csv = format!("1,{0}\n2,{1}\n3,{2},legacy\n4,{2},wrong\n", v2y, legacy, legacy.replacen("2a", "2y", 1));
+	let path = std::env::temp_dir().join(format!("bcrypt-only-migrate-relabel-{}", std::process::id()));
+	let result = run(&["--cost=5", "--relabel-2y", "--output", path.to_str().unwrap()], &csv);
+	let updated = std::fs::read_to_string(&path).unwrap();
+	std::fs::remove_file(&path).unwrap();
+
+	let (code, out) = result.unwrap();
+	assert_eq!(code, ExitCode::FAILURE);
+	assert!(out.ends_with("below $2b$ at cost 5: 3\nrehashed: 1\nrelabeled $2y$ to $2b$: 2\n  - 4: password doesn’t match the stored hash\n"));
+	let updated: Vec<_> = updated.lines().collect();
+	assert_eq!(updated.len(), 3);
+	assert_eq!(updated[0], format!("1,{}", current));
+	assert!(updated[1].starts_with("3,$2b$05$"));
+	assert_eq!(updated[2], format!("4,{}", legacy.replacen("2a", "2b", 1)));
+
+	assert_eq!(migrate::Format::Jsonl.format_row(&Value::Literal("7".into()), &current), format!("{{\"id\":7,\"hash\":\"{}\"}}", current));
+	assert_eq!(migrate::Format::Csv.format_row(&Value::String("a\"b".into()), &current), format!("\"a\"\"b\",{}", current));
+	assert!(matches!(run(&["--format", "xml"], ""), Err(CliError::Usage(_))));
+}
+
+#[test]
+fn json_output() {
+	/// Replaces the number after each `"duration_ms":` with 0, for comparison.
+	fn without_durations(json: &str) -> String {
+		let mut parts = json.split("\"duration_ms\":");
+		let mut result = parts.next().unwrap().to_owned();
+
+		for part in parts {
+			let end = part.find([',', '}']).unwrap();
+			result.push_str("\"duration_ms\":0");
+			result.push_str(&part[end..]);
+		}
+
+		result
+	}
+
+	let hash = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm";
+	let mut out = Vec::new();
+
+	hash::run(args(&["--cost=4", "--salt=cVWp4XaNU8a4v1uMRum2SO", "Kk4DQuMMfZL9o"]), &mut &b""[..], &mut out, PasswordSource::Input, true).unwrap();
+	verify::run(args(&[hash]), &mut &b"Kk4DQuMMfZL9O\n"[..], &mut out, PasswordSource::Input, true).unwrap();
+	inspect::run(args(&["$2b$04$cVWp4XaNU8a4v1uMRum2SP026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"]), &mut &b""[..], &mut out, true).unwrap();
+	batch::run(args(&[]), &mut format!("Kk4DQuMMfZL9o\t{}\nno tab\n", hash).as_bytes(), &mut out, true).unwrap();
+	audit::run(args(&[&format!("alice:{}", hash)]), &mut &b"password\nKk4DQuMMfZL9o\n"[..], &mut out, true).unwrap();
+	migrate::run(args(&["--cost=4"]), &mut format!("1,{}\n", hash).as_bytes(), &mut out, true).unwrap();
+
+	let clock = StepClock { now: Cell::new(Duration::ZERO), step: Duration::from_millis(40) };
+	bench::run(args(&["--target=100", "--max-cost=4"]), &mut out, &clock, true).unwrap();
+
+	assert_eq!(without_durations(&String::from_utf8(out).unwrap()), format!(r#"{{"outcome":"hashed","hash":"{0}","version":"2b","cost":4,"duration_ms":0}}
+{{"outcome":"mismatch","version":"2b","cost":4,"duration_ms":0}}
+{{"version":"2b","cost":4,"rounds":16,"salt":"cVWp4XaNU8a4v1uMRum2SP","salt_hex":"79762be9970f5be73ac77c0e4f0a3851","hash":"026BWLIoQMD/TXg5uZV.0P.uO8m3YEm","hash_hex":"db8f0360d2aa48e1415598bbc1b5c0d9103043ea39686a","outcome":"invalid","problems":["salt is non-canonical: its last character has nonzero unused bits (canonical: cVWp4XaNU8a4v1uMRum2SO)"]}}
+{{"outcome":"match","record":1,"duration_ms":0}}
+{{"outcome":"error","error":"expected a password and hash separated by a tab","record":2,"duration_ms":0}}
+{{"outcome":"fell","hashes":1,"words":2,"skipped_words":0,"fallen":[{{"label":"alice","word":2}}],"duration_ms":0}}
+{{"outcome":"surveyed","rows":1,"distribution":[{{"version":"2b","cost":4,"count":1}}],"target_cost":4,"outdated":0,"rehashed":0,"relabeled":0,"written":false,"problems":[]}}
+{{"outcome":"benchmarked","target_ms":100,"results":[{{"cost":4,"duration_ms":0}}],"recommended_cost":5}}
+"#, hash));
+}
+
+#[test]
+fn gen_vectors_command() {
+	let run = |arguments: &[&str]| {
+		let mut out = Vec::new();
+		gen_vectors::run(args(arguments), &mut out, false).unwrap();
+		String::from_utf8(out).unwrap()
+	};
+
+	let vectors = run(&["--count", "12"]);
+	assert_eq!(vectors, run(&["--count=12", "--seed=0"]));
+	assert_ne!(vectors, run(&["--count=12", "--seed=1"]));
+	assert_eq!(bcrypt_only::vectors::check_vectors(vectors.as_bytes()).unwrap(), 12);
+
+	let lines: Vec<_> = vectors.lines().filter(|line| !line.starts_with('#')).collect();
+	assert_eq!(lines[0], ",$2b$04$p9PN5Hto2RxBnel0cw6XouHI2MtAR0/H422Flt02xfYoXH.EMdIgW");
+	assert_eq!(lines[1], "55,$2b$04$TWXipLaLYivncu.ODlHNHOKyHdiAZLuukg5wAYPD3SVVSEdmHwwTm");
+	assert!(lines.iter().any(|line| line.split_once(',').unwrap().0.len() == 2 * bcrypt_only::KEY_SIZE_MAX));
+
+	assert!(run(&["--count=1", "--cost=5"]).contains(",$2b$05$"));
+}
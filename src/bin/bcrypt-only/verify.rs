@@ -0,0 +1,81 @@
+//! `bcrypt-only verify`: checking a password against a hash string.
+
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use bcrypt_only::mcf::{self, HashString};
+
+use super::args::{Arg, Args, unknown};
+use super::hash::version_name;
+use super::password::{self, PasswordSource};
+use super::{CliError, json, parse_error_message};
+
+/// Parses a hash string, ignoring surrounding whitespace such as a trailing newline.
+pub fn parse_hash(hash: &str) -> Result<HashString, CliError> {
+	let hash = hash.trim();
+	hash.parse().map_err(|err| CliError::Failed(format!("invalid hash {:?}: {}", hash, parse_error_message(err))))
+}
+
+/// Reads the hash string from the first line of a file.
+fn read_hash_file(path: &OsString) -> Result<HashString, CliError> {
+	let contents = fs::read_to_string(path).map_err(|err| CliError::Failed(format!("couldn’t read {:?}: {}", path, err)))?;
+	parse_hash(contents.lines().next().unwrap_or(""))
+}
+
+/// Reads the hash string from one line of `input`.
+fn read_hash_line<R: BufRead>(input: &mut R) -> Result<HashString, CliError> {
+	let mut line = String::new();
+
+	if input.read_line(&mut line)? == 0 {
+		return Err(CliError::Failed("no hash given".into()));
+	}
+
+	parse_hash(&line)
+}
+
+/// The `--json` result of checking a password against `hash`.
+pub fn verification(matches: bool, hash: &HashString, duration: Duration) -> json::Object {
+	json::Object::new()
+		.string("outcome", if matches { "match" } else { "mismatch" })
+		.string("version", version_name(hash.version()))
+		.raw("cost", hash.work_factor().log_rounds())
+		.raw("duration_ms", json::milliseconds(duration))
+}
+
+pub fn run<R: BufRead, W: Write>(mut args: Args, input: &mut R, out: &mut W, password_source: PasswordSource, json: bool) -> Result<ExitCode, CliError> {
+	let mut hash_file = None;
+	let mut hash = None;
+
+	while let Some(arg) = args.next()? {
+		match arg {
+			Arg::Option(name) => match name.as_str() {
+				"hash-file" => hash_file = Some(args.value(&name)?),
+				_ => return Err(unknown(&name)),
+			},
+			Arg::Positional(value) if hash.is_none() => hash = Some(value),
+			Arg::Positional(value) => return Err(CliError::Usage(format!("unexpected argument {:?}", value))),
+		}
+	}
+
+	let hash = match (hash, hash_file) {
+		(Some(_), Some(_)) => return Err(CliError::Usage("give a hash or --hash-file, not both".into())),
+		(Some(hash), None) => parse_hash(&hash.into_string().map_err(|_| CliError::Usage("hash isn’t valid Unicode".into()))?)?,
+		(None, Some(path)) => read_hash_file(&path)?,
+		(None, None) => read_hash_line(input)?,
+	};
+
+	let password = password::read(input, &mut io::stderr(), password_source, false)?;
+
+	let start = Instant::now();
+	let matches = mcf::verify(&password, &hash)?;
+	let duration = start.elapsed();
+
+	if json {
+		writeln!(out, "{}", verification(matches, &hash, duration).finish())?;
+	}
+
+	Ok(if matches { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
@@ -0,0 +1,30 @@
+//! Running work on several threads, or on the current one where threads aren’t available.
+
+use std::num::NonZeroUsize;
+use std::thread;
+
+/// Runs `work` on `jobs` threads at once, returning what each returned. Each call of `work` should take its share from a common queue until it’s empty.
+///
+/// If threads can’t be started, as on WASI targets without threads, `work` runs once on the current thread instead.
+pub fn run<T: Send, F: Fn() -> T + Sync>(jobs: NonZeroUsize, work: F) -> Vec<T> {
+	if jobs.get() == 1 {
+		return vec![work()];
+	}
+
+	thread::scope(|scope| {
+		let mut workers = Vec::new();
+
+		for _ in 0..jobs.get() {
+			match thread::Builder::new().spawn_scoped(scope, &work) {
+				Ok(worker) => workers.push(worker),
+				Err(_) => break,
+			}
+		}
+
+		if workers.is_empty() {
+			return vec![work()];
+		}
+
+		workers.into_iter().map(|worker| worker.join().unwrap()).collect()
+	})
+}
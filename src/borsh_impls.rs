@@ -0,0 +1,50 @@
+//! `BorshSerialize` and `BorshDeserialize` for the `borsh` feature, as fixed-size bytes without length prefixes.
+
+use borsh::io::{Error, ErrorKind, Read, Result, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use super::mcf::{COMPACT_SIZE, HashString};
+use super::{SALT_SIZE, Salt, WorkFactor};
+
+/// The salt’s 16 bytes.
+impl BorshSerialize for Salt {
+	fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+		self.to_bytes().serialize(writer)
+	}
+}
+
+impl BorshDeserialize for Salt {
+	fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+		<[u8; SALT_SIZE]>::deserialize_reader(reader).map(|bytes| Salt::from_bytes(&bytes))
+	}
+}
+
+/// The base-2 exponent, as one byte.
+impl BorshSerialize for WorkFactor {
+	fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+		(self.log_rounds() as u8).serialize(writer)
+	}
+}
+
+/// Validates the exponent as [`WorkFactor::exp`] does.
+impl BorshDeserialize for WorkFactor {
+	fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+		let log_rounds = u8::deserialize_reader(reader)?;
+		WorkFactor::exp(log_rounds.into()).ok_or_else(|| Error::new(ErrorKind::InvalidData, "bcrypt work factor outside 4 to 31"))
+	}
+}
+
+/// The hash string’s 41-byte [compact form](HashString::to_compact).
+impl BorshSerialize for HashString {
+	fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+		self.to_compact().serialize(writer)
+	}
+}
+
+/// Validates the bytes with [`HashString::from_compact`].
+impl BorshDeserialize for HashString {
+	fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+		let bytes = <[u8; COMPACT_SIZE]>::deserialize_reader(reader)?;
+		HashString::from_compact(&bytes).map_err(|_| Error::new(ErrorKind::InvalidData, "invalid compact bcrypt hash string"))
+	}
+}
@@ -0,0 +1,37 @@
+//! A backend calling a transcription of OpenBSD’s C implementation, compiled by the build script, for differential testing of the round logic. Not meant for anything else.
+
+#![allow(unsafe_code)]
+
+use super::backend::BcryptBackend;
+use super::{BcryptError, HASH_SIZE, KEY_SIZE_MAX, SALT_SIZE, Salt, WorkFactor, check_key};
+
+extern "C" {
+	fn bcrypt_only_reference(key: *const u8, salt: *const u8, log_rounds: u8, hash: *mut u8);
+}
+
+/// OpenBSD’s C implementation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CReference;
+
+impl BcryptBackend for CReference {
+	fn name(&self) -> &'static str {
+		"c-reference"
+	}
+
+	fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		check_key(key)?;
+
+		let mut key_nul = [0_u8; KEY_SIZE_MAX + 1];
+		key_nul[..key.len()].copy_from_slice(key);
+
+		let salt: [u8; SALT_SIZE] = salt.to_bytes();
+		let mut hash = [0_u8; HASH_SIZE];
+
+		// SAFETY: `key_nul` is NUL-terminated, `salt` is the 16 bytes and `hash` the 23 writable bytes the C function expects, and it doesn’t retain any of them.
+		unsafe {
+			bcrypt_only_reference(key_nul.as_ptr(), salt.as_ptr(), work_factor.log_rounds() as u8, hash.as_mut_ptr());
+		}
+
+		Ok(hash)
+	}
+}
@@ -0,0 +1,137 @@
+use core::fmt;
+use core::hint::black_box;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::vec::Vec;
+
+use super::BcryptError;
+use super::clock::Clock;
+use super::mcf::{self, HashString};
+use super::rng::Rng;
+use super::sha512::hmac;
+
+const ENTRY_KEY_SIZE: usize = 32;
+
+struct Entry {
+	key: [u8; ENTRY_KEY_SIZE],
+	expires: Duration,
+}
+
+impl Drop for Entry {
+	fn drop(&mut self) {
+		self.key.fill(0);
+		black_box(&mut self.key);
+	}
+}
+
+/// Remembers recent successful verifications for a limited time, so repeated logins with the same credentials – a hot service account, or a client retrying in a loop – skip the hash after the first.
+///
+/// **This weakens bcrypt for the cached credentials.** Each entry is an HMAC-SHA-512 of the key and hash string under a secret generated for the cache, so anyone who can read the process’s memory while an entry is live can test guesses against it at the speed of HMAC rather than bcrypt. Keep the time-to-live short and only use the cache where that exposure is acceptable. Only matches are cached: a wrong key always costs a full hash, so the cache never makes guessing cheaper for a remote attacker, and changing a password changes the hash string, so entries for the old one can no longer match.
+///
+/// Entries are kept in a fixed-capacity list searched linearly, meant for tens to hundreds of hot credentials, and their keys are zeroed when they’re evicted or the cache is dropped.
+pub struct VerifyCache<C: Clock> {
+	secret: [u8; 64],
+	ttl: Duration,
+	capacity: usize,
+	clock: C,
+	entries: Mutex<Vec<Entry>>,
+}
+
+impl<C: Clock> VerifyCache<C> {
+	/// Creates a cache holding up to `capacity` matches for `ttl` each, with a secret drawn from `rng`.
+	pub fn new<R: Rng>(mut rng: R, clock: C, ttl: Duration, capacity: usize) -> Self {
+		let mut secret = [0_u8; 64];
+		rng.fill_bytes(&mut secret);
+
+		Self {
+			secret,
+			ttl,
+			capacity,
+			clock,
+			entries: Mutex::new(Vec::with_capacity(capacity)),
+		}
+	}
+
+	/// [`mcf::verify`], answering from the cache when the same key matched the same hash string less than the time-to-live ago.
+	pub fn verify(&self, key: &[u8], hash_string: &HashString) -> Result<bool, BcryptError> {
+		let mut entry_key = self.entry_key(key, hash_string);
+		let now = self.clock.now();
+
+		let cached = {
+			let mut entries = self.entries.lock().unwrap();
+			entries.retain(|entry| entry.expires > now);
+			entries.iter().any(|entry| keys_equal(&entry.key, &entry_key))
+		};
+
+		let result = if cached {
+			Ok(true)
+		} else {
+			let result = mcf::verify(key, hash_string);
+
+			if result == Ok(true) && self.capacity != 0 {
+				self.insert(entry_key, now + self.ttl);
+			}
+
+			result
+		};
+
+		entry_key.fill(0);
+		black_box(&mut entry_key);
+		result
+	}
+
+	/// Forgets every cached match.
+	pub fn clear(&self) {
+		self.entries.lock().unwrap().clear();
+	}
+
+	fn entry_key(&self, key: &[u8], hash_string: &HashString) -> [u8; ENTRY_KEY_SIZE] {
+		let mut mac = hmac(&self.secret, &[&(key.len() as u64).to_be_bytes(), key, hash_string.as_str().as_bytes()]);
+		let mut entry_key = [0_u8; ENTRY_KEY_SIZE];
+		entry_key.copy_from_slice(&mac[..ENTRY_KEY_SIZE]);
+		mac.fill(0);
+		black_box(&mut mac);
+		entry_key
+	}
+
+	fn insert(&self, key: [u8; ENTRY_KEY_SIZE], expires: Duration) {
+		let mut entries = self.entries.lock().unwrap();
+
+		if entries.iter().any(|entry| keys_equal(&entry.key, &key)) {
+			return;
+		}
+
+		if entries.len() == self.capacity {
+			// the oldest entry expires first
+			entries.remove(0);
+		}
+
+		entries.push(Entry { key, expires });
+	}
+}
+
+impl<C: Clock> fmt::Debug for VerifyCache<C> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("VerifyCache")
+			.field("ttl", &self.ttl)
+			.field("capacity", &self.capacity)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<C: Clock> Drop for VerifyCache<C> {
+	fn drop(&mut self) {
+		self.secret.fill(0);
+		black_box(&mut self.secret);
+	}
+}
+
+fn keys_equal(a: &[u8; ENTRY_KEY_SIZE], b: &[u8; ENTRY_KEY_SIZE]) -> bool {
+	let mut difference = 0_u8;
+
+	for (x, y) in a.iter().zip(b) {
+		difference |= black_box(x ^ y);
+	}
+
+	difference == 0
+}
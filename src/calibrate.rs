@@ -0,0 +1,60 @@
+use core::fmt;
+use core::time::Duration;
+
+use super::clock::Clock;
+use super::{Salt, WorkFactor, bcrypt_runtime};
+
+/// The work factor at which calibration gives up if the clock doesn’t show a hash taking longer than one at the lowest work factor, which it should by a factor of 64. A hash at this work factor takes tens of milliseconds even on fast hardware – several ticks of a coarse clock.
+const STALL_LOG_ROUNDS: u32 = 10;
+
+/// An error from [`calibrate`]: the clock didn’t advance while hashing, or advanced by the same amount however long a hash took, so hashes couldn’t be timed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClockStalled;
+
+impl fmt::Display for ClockStalled {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("clock didn’t advance while hashing")
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ClockStalled {}
+
+/// Estimates the highest work factor that hashes in at most `target` on this machine, timed by `clock`, or returns the lowest work factor if even that takes longer.
+///
+/// Hashes are timed from the lowest work factor up until one takes at least a quarter of the target, and the rest is extrapolated from there (each work factor takes twice as long as the last), so calibration takes about half the target. Fails if, by work factor 10, the clock still doesn’t show hashes getting slower, rather than climbing to work factors that would take days.
+pub fn calibrate<C: Clock>(target: Duration, clock: C) -> Result<WorkFactor, ClockStalled> {
+	let salt = Salt::from_bytes(&[0; 16]);
+	let mut log_rounds = 4;
+	let mut first = None;
+
+	let mut elapsed = loop {
+		let start = clock.now();
+		bcrypt_runtime(b"calibration", &salt, WorkFactor(log_rounds)).unwrap();
+		let elapsed = clock.now().saturating_sub(start);
+
+		if elapsed >= target / 4 || log_rounds == 31 {
+			break elapsed;
+		}
+
+		let first = *first.get_or_insert(elapsed);
+
+		if log_rounds == STALL_LOG_ROUNDS && elapsed <= first {
+			return Err(ClockStalled);
+		}
+
+		log_rounds += 1;
+	};
+
+	while log_rounds < 31 && elapsed.saturating_mul(2) <= target {
+		elapsed *= 2;
+		log_rounds += 1;
+	}
+
+	while log_rounds > 4 && elapsed > target {
+		elapsed /= 2;
+		log_rounds -= 1;
+	}
+
+	Ok(WorkFactor(log_rounds))
+}
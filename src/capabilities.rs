@@ -0,0 +1,199 @@
+use core::fmt;
+use core::ops::BitOr;
+
+/// A set of things this build of the crate supports, as bit flags so they cross FFI boundaries unchanged. Flags are only ever added, never renumbered.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+	/// Parsing and verifying `$2a$` hash strings (the `mcf` feature).
+	pub const VERSION_2A: Self = Self(1 << 0);
+
+	/// Producing, parsing, and verifying `$2b$` hash strings (the `mcf` feature).
+	pub const VERSION_2B: Self = Self(1 << 1);
+
+	/// Parsing and verifying `$2y$` hash strings (the `mcf` feature).
+	pub const VERSION_2Y: Self = Self(1 << 2);
+
+	/// The portable backend. Always present.
+	pub const BACKEND_PORTABLE: Self = Self(1 << 8);
+
+	/// The system crypt backend (the `system-crypt` feature, for differential testing only).
+	pub const BACKEND_SYSTEM_CRYPT: Self = Self(1 << 9);
+
+	/// The C reference backend (the `c-reference` feature, for differential testing only).
+	pub const BACKEND_C_REFERENCE: Self = Self(1 << 10);
+
+	/// The `bcrypt` crate backend (the `bcrypt-crate` feature, for differential testing only).
+	pub const BACKEND_BCRYPT_CRATE: Self = Self(1 << 11);
+
+	/// APIs needing the standard library (the `std` feature).
+	pub const STD: Self = Self(1 << 16);
+
+	/// Initial tables placed in `.data` (the `ram-tables` feature).
+	pub const RAM_TABLES: Self = Self(1 << 17);
+
+	/// Initial tables generated at runtime (the `runtime-tables` feature).
+	pub const RUNTIME_TABLES: Self = Self(1 << 18);
+
+	/// The smaller, slower core (the `size-opt` feature).
+	pub const SIZE_OPT: Self = Self(1 << 19);
+
+	/// The deterministic test doubles (the `test-support` feature).
+	pub const TEST_SUPPORT: Self = Self(1 << 20);
+
+	/// The `extern "C"` functions (the `capi` feature).
+	pub const CAPI: Self = Self(1 << 21);
+
+	/// The modular crypt format and everything built on it (the `mcf` feature).
+	pub const MCF: Self = Self(1 << 22);
+
+	/// The experimental APIs in the `unstable` module (the `unstable` feature).
+	pub const UNSTABLE: Self = Self(1 << 23);
+
+	/// `bcrypt_pbkdf` (the `bcrypt-pbkdf` feature).
+	pub const BCRYPT_PBKDF: Self = Self(1 << 24);
+
+	/// `Salt::derive` (the `derived-salt` feature).
+	pub const DERIVED_SALT: Self = Self(1 << 25);
+
+	/// Error `Display` impls that write only the error code (the `compact-errors` feature).
+	pub const COMPACT_ERRORS: Self = Self(1 << 26);
+
+	/// `count_rounds` (the `round-count` feature).
+	pub const ROUND_COUNT: Self = Self(1 << 27);
+
+	/// Spans and events from `mcf::hash` and `mcf::verify` (the `tracing` feature).
+	pub const TRACING: Self = Self(1 << 28);
+
+	/// `Bcrypt`, implementing the `password-hash` crate’s traits (the `password-hash` feature).
+	pub const PASSWORD_HASH: Self = Self(1 << 29);
+
+	/// `Serialize` and `Deserialize` implementations (the `serde` feature).
+	pub const SERDE: Self = Self(1 << 30);
+
+	/// `Arbitrary` implementations (the `arbitrary` feature).
+	pub const ARBITRARY: Self = Self(1 << 31);
+
+	/// The `strategies` module (the `proptest` feature).
+	pub const PROPTEST: Self = Self(1 << 32);
+
+	/// `HashArray` and `SaltArray` (the `hybrid-array` feature).
+	pub const HYBRID_ARRAY: Self = Self(1 << 33);
+
+	/// Borsh encodings (the `borsh` feature).
+	pub const BORSH: Self = Self(1 << 34);
+
+	/// JSON schemas (the `schemars` feature).
+	pub const SCHEMARS: Self = Self(1 << 35);
+
+	/// The JavaScript API (the `wasm` feature).
+	pub const WASM: Self = Self(1 << 36);
+
+	/// `AsyncVerifier` (the `tokio` feature).
+	pub const TOKIO: Self = Self(1 << 37);
+
+	/// ufmt formatting (the `ufmt` feature).
+	pub const UFMT: Self = Self(1 << 38);
+
+	/// sqlx column types (the `sqlx` feature).
+	pub const SQLX: Self = Self(1 << 39);
+
+	/// diesel column types (the `diesel` feature).
+	pub const DIESEL: Self = Self(1 << 40);
+
+	/// The Python module (the `python` feature).
+	pub const PYTHON: Self = Self(1 << 41);
+
+	/// The Kotlin and Swift API (the `uniffi` feature).
+	pub const UNIFFI: Self = Self(1 << 42);
+
+	/// The Node addon (the `node` feature).
+	pub const NODE: Self = Self(1 << 43);
+
+	/// No capabilities.
+	pub const fn empty() -> Self {
+		Self(0)
+	}
+
+	/// Creates a set from its bits, keeping unknown ones.
+	pub const fn from_bits(bits: u64) -> Self {
+		Self(bits)
+	}
+
+	/// The bits making up the set.
+	pub const fn bits(self) -> u64 {
+		self.0
+	}
+
+	/// Whether every capability in `other` is in this set.
+	pub const fn contains(self, other: Self) -> bool {
+		self.0 & other.0 == other.0
+	}
+
+	/// The union of two sets.
+	pub const fn union(self, other: Self) -> Self {
+		Self(self.0 | other.0)
+	}
+
+	const fn union_if(self, condition: bool, other: Self) -> Self {
+		if condition {
+			self.union(other)
+		} else {
+			self
+		}
+	}
+}
+
+impl BitOr for Capabilities {
+	type Output = Self;
+
+	fn bitor(self, other: Self) -> Self {
+		self.union(other)
+	}
+}
+
+impl fmt::Debug for Capabilities {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "Capabilities({:#x})", self.0)
+	}
+}
+
+/// The capabilities of this build of the crate, for negotiating behavior at runtime – e.g. across an FFI boundary – rather than guessing from feature names.
+pub const fn capabilities() -> Capabilities {
+	Capabilities::BACKEND_PORTABLE
+		.union_if(cfg!(feature = "mcf"), Capabilities::VERSION_2A)
+		.union_if(cfg!(feature = "mcf"), Capabilities::VERSION_2B)
+		.union_if(cfg!(feature = "mcf"), Capabilities::VERSION_2Y)
+		.union_if(cfg!(feature = "system-crypt"), Capabilities::BACKEND_SYSTEM_CRYPT)
+		.union_if(cfg!(feature = "c-reference"), Capabilities::BACKEND_C_REFERENCE)
+		.union_if(cfg!(feature = "bcrypt-crate"), Capabilities::BACKEND_BCRYPT_CRATE)
+		.union_if(cfg!(feature = "std"), Capabilities::STD)
+		.union_if(cfg!(feature = "ram-tables"), Capabilities::RAM_TABLES)
+		.union_if(cfg!(feature = "runtime-tables"), Capabilities::RUNTIME_TABLES)
+		.union_if(cfg!(feature = "size-opt"), Capabilities::SIZE_OPT)
+		.union_if(cfg!(feature = "test-support"), Capabilities::TEST_SUPPORT)
+		.union_if(cfg!(feature = "capi"), Capabilities::CAPI)
+		.union_if(cfg!(feature = "mcf"), Capabilities::MCF)
+		.union_if(cfg!(feature = "unstable"), Capabilities::UNSTABLE)
+		.union_if(cfg!(feature = "bcrypt-pbkdf"), Capabilities::BCRYPT_PBKDF)
+		.union_if(cfg!(feature = "derived-salt"), Capabilities::DERIVED_SALT)
+		.union_if(cfg!(feature = "compact-errors"), Capabilities::COMPACT_ERRORS)
+		.union_if(cfg!(feature = "round-count"), Capabilities::ROUND_COUNT)
+		.union_if(cfg!(feature = "tracing"), Capabilities::TRACING)
+		.union_if(cfg!(feature = "password-hash"), Capabilities::PASSWORD_HASH)
+		.union_if(cfg!(feature = "serde"), Capabilities::SERDE)
+		.union_if(cfg!(feature = "arbitrary"), Capabilities::ARBITRARY)
+		.union_if(cfg!(feature = "proptest"), Capabilities::PROPTEST)
+		.union_if(cfg!(feature = "hybrid-array"), Capabilities::HYBRID_ARRAY)
+		.union_if(cfg!(feature = "borsh"), Capabilities::BORSH)
+		.union_if(cfg!(feature = "schemars"), Capabilities::SCHEMARS)
+		.union_if(cfg!(feature = "wasm"), Capabilities::WASM)
+		.union_if(cfg!(feature = "tokio"), Capabilities::TOKIO)
+		.union_if(cfg!(feature = "ufmt"), Capabilities::UFMT)
+		.union_if(cfg!(feature = "sqlx"), Capabilities::SQLX)
+		.union_if(cfg!(feature = "diesel"), Capabilities::DIESEL)
+		.union_if(cfg!(feature = "python"), Capabilities::PYTHON)
+		.union_if(cfg!(feature = "uniffi"), Capabilities::UNIFFI)
+		.union_if(cfg!(feature = "node"), Capabilities::NODE)
+}
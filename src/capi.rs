@@ -0,0 +1,287 @@
+//! A C API, for linking this implementation into C and C++ programs in place of crypt_blowfish.
+//!
+//! Every function returns a negative error code on failure, except [`bcrypt_only_capabilities`] and [`bcrypt_only_session_destroy`], which can’t fail. On success, [`bcrypt_only_verify`] returns 1 for a match and 0 for a mismatch, [`bcrypt_only_session_step`] returns 1 once all of the session’s rounds have run and 0 if some remain, and the rest return `BCRYPT_ONLY_OK`. Strings are NUL-terminated. The signatures are stable.
+//!
+//! `capi/bcrypt_only.h` declares them for C, and `make capi-lib` builds static and shared libraries.
+
+#![allow(unsafe_code)]
+
+use core::ffi::{CStr, c_char, c_int, c_uint};
+use core::mem::{align_of, size_of};
+use core::{ptr, slice};
+
+use super::backend::{BcryptBackend, DefaultBackend};
+use super::mcf::{HASH_STRING_SIZE, HashString, ParseError, SETTING_SIZE, Version};
+use super::{BcryptError, BcryptState, HASH_SIZE, SALT_SIZE, Salt, WorkFactor, capabilities};
+
+/// Success.
+pub const BCRYPT_ONLY_OK: c_int = 0;
+
+/// The key was longer than 72 bytes.
+pub const BCRYPT_ONLY_ERR_KEY_LENGTH: c_int = BcryptError::Length.error_code() as c_int;
+
+/// The key contained a 0 byte.
+pub const BCRYPT_ONLY_ERR_KEY_ZERO_BYTE: c_int = BcryptError::ZeroByte.error_code() as c_int;
+
+/// A setting or hash string wasn’t valid.
+pub const BCRYPT_ONLY_ERR_PARSE: c_int = ParseError::Length.error_code() as c_int;
+
+/// A work factor was outside 4 to 31.
+pub const BCRYPT_ONLY_ERR_WORK_FACTOR: c_int = -4;
+
+/// A required pointer was null.
+pub const BCRYPT_ONLY_ERR_NULL: c_int = -5;
+
+/// A session was finished before all of its rounds had run.
+pub const BCRYPT_ONLY_ERR_INCOMPLETE: c_int = -6;
+
+/// The size of the buffer [`bcrypt_only_gensalt`] writes, including the NUL terminator.
+pub const BCRYPT_ONLY_SETTING_BUFFER_SIZE: usize = 30;
+
+/// The size of the buffer [`bcrypt_only_hash`] writes, including the NUL terminator.
+pub const BCRYPT_ONLY_HASH_BUFFER_SIZE: usize = 61;
+
+/// The size of a [`Session`], in bytes.
+pub const BCRYPT_ONLY_SESSION_SIZE: usize = 4608;
+
+/// Storage for a hash in progress, `bcrypt_only_session` in C. The caller places it wherever suits – on a task’s stack, in a static – so sessions never allocate. Its contents are private.
+#[repr(C)]
+pub struct Session {
+	opaque: [u64; BCRYPT_ONLY_SESSION_SIZE / 8],
+}
+
+/// What a [`Session`] holds once created.
+struct SessionState {
+	state: BcryptState,
+	setting: HashString,
+}
+
+const _: () = assert!(size_of::<SessionState>() <= BCRYPT_ONLY_SESSION_SIZE && align_of::<SessionState>() <= align_of::<Session>());
+
+const _: () = assert!(BCRYPT_ONLY_SETTING_BUFFER_SIZE == SETTING_SIZE + 1 && BCRYPT_ONLY_HASH_BUFFER_SIZE == HASH_STRING_SIZE + 1);
+
+/// The key bytes behind a pointer and length, allowing a null pointer for an empty key.
+unsafe fn key_slice<'a>(key: *const u8, key_len: usize) -> Option<&'a [u8]> {
+	if key_len == 0 {
+		Some(&[])
+	} else if key.is_null() {
+		None
+	} else {
+		// SAFETY: the caller guarantees `key` points to `key_len` readable bytes.
+		Some(unsafe { slice::from_raw_parts(key, key_len) })
+	}
+}
+
+/// Parses a setting, or the setting part of a hash string, as accepted by `crypt`.
+fn parse_setting(setting: &CStr) -> Option<HashString> {
+	let setting = setting.to_bytes();
+
+	if setting.len() != SETTING_SIZE && setting.len() != HASH_STRING_SIZE {
+		return None;
+	}
+
+	// A run of `.` is a valid, canonical encoding of a zero hash, so the setting parses as a hash string.
+	let mut padded = [b'.'; HASH_STRING_SIZE];
+	padded[..SETTING_SIZE].copy_from_slice(&setting[..SETTING_SIZE]);
+	HashString::parse(core::str::from_utf8(&padded).ok()?).ok()
+}
+
+/// The bits of this build’s [`capabilities`](super::capabilities()), which `capi/bcrypt_only.h` names as `BCRYPT_ONLY_CAP_*`.
+#[no_mangle]
+pub extern "C" fn bcrypt_only_capabilities() -> u64 {
+	capabilities().bits()
+}
+
+/// Writes a `$2b$` setting for `log_rounds` and the 16 random bytes at `random` into the [`BCRYPT_ONLY_SETTING_BUFFER_SIZE`]-byte buffer at `output`. The caller supplies the randomness, so this works on targets without an operating system.
+///
+/// # Safety
+///
+/// `random` has to point to 16 readable bytes, and `output` to [`BCRYPT_ONLY_SETTING_BUFFER_SIZE`] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bcrypt_only_gensalt(log_rounds: c_uint, random: *const u8, output: *mut c_char) -> c_int {
+	if random.is_null() || output.is_null() {
+		return BCRYPT_ONLY_ERR_NULL;
+	}
+
+	let work_factor = match WorkFactor::exp(log_rounds) {
+		Some(work_factor) => work_factor,
+		None => return BCRYPT_ONLY_ERR_WORK_FACTOR,
+	};
+
+	// SAFETY: the caller guarantees `random` points to 16 readable bytes.
+	let random = unsafe { &*random.cast::<[u8; SALT_SIZE]>() };
+	let hash_string = HashString::new(Version::V2b, work_factor, &Salt::from_bytes(random), &[0; HASH_SIZE]);
+
+	// SAFETY: the caller guarantees `output` points to `BCRYPT_ONLY_SETTING_BUFFER_SIZE` writable bytes.
+	let output = unsafe { slice::from_raw_parts_mut(output.cast::<u8>(), BCRYPT_ONLY_SETTING_BUFFER_SIZE) };
+	output[..SETTING_SIZE].copy_from_slice(&hash_string.as_str().as_bytes()[..SETTING_SIZE]);
+	output[SETTING_SIZE] = 0;
+
+	BCRYPT_ONLY_OK
+}
+
+/// Hashes the `key_len`-byte key at `key` with a setting from [`bcrypt_only_gensalt`] (or a whole hash string, whose setting part is used), writing the hash string into the [`BCRYPT_ONLY_HASH_BUFFER_SIZE`]-byte buffer at `output`. The hash string keeps the setting’s version prefix.
+///
+/// # Safety
+///
+/// `key` has to point to `key_len` readable bytes, or can be null if `key_len` is 0. `setting` has to be a NUL-terminated string, and `output` has to point to [`BCRYPT_ONLY_HASH_BUFFER_SIZE`] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bcrypt_only_hash(key: *const u8, key_len: usize, setting: *const c_char, output: *mut c_char) -> c_int {
+	if setting.is_null() || output.is_null() {
+		return BCRYPT_ONLY_ERR_NULL;
+	}
+
+	// SAFETY: the caller guarantees `key` points to `key_len` readable bytes.
+	let key = match unsafe { key_slice(key, key_len) } {
+		Some(key) => key,
+		None => return BCRYPT_ONLY_ERR_NULL,
+	};
+
+	// SAFETY: the caller guarantees `setting` is NUL-terminated.
+	let setting = match parse_setting(unsafe { CStr::from_ptr(setting) }) {
+		Some(setting) => setting,
+		None => return BCRYPT_ONLY_ERR_PARSE,
+	};
+
+	let salt = setting.salt();
+	let hash = match DefaultBackend::default().hash(key, &salt, setting.work_factor()) {
+		Ok(hash) => hash,
+		Err(error) => return error.error_code() as c_int,
+	};
+	let hash_string = HashString::new(setting.version(), setting.work_factor(), &salt, &hash);
+
+	// SAFETY: the caller guarantees `output` points to `BCRYPT_ONLY_HASH_BUFFER_SIZE` writable bytes.
+	let output = unsafe { slice::from_raw_parts_mut(output.cast::<u8>(), BCRYPT_ONLY_HASH_BUFFER_SIZE) };
+	output[..HASH_STRING_SIZE].copy_from_slice(hash_string.as_str().as_bytes());
+	output[HASH_STRING_SIZE] = 0;
+
+	BCRYPT_ONLY_OK
+}
+
+/// Checks whether the `key_len`-byte key at `key` matches a hash string, comparing the hashes in constant time. Returns 1 for a match, 0 for a mismatch, or a negative error code.
+///
+/// # Safety
+///
+/// `key` has to point to `key_len` readable bytes, or can be null if `key_len` is 0. `hash_string` has to be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bcrypt_only_verify(key: *const u8, key_len: usize, hash_string: *const c_char) -> c_int {
+	if hash_string.is_null() {
+		return BCRYPT_ONLY_ERR_NULL;
+	}
+
+	// SAFETY: the caller guarantees `key` points to `key_len` readable bytes.
+	let key = match unsafe { key_slice(key, key_len) } {
+		Some(key) => key,
+		None => return BCRYPT_ONLY_ERR_NULL,
+	};
+
+	// SAFETY: the caller guarantees `hash_string` is NUL-terminated.
+	let hash_string = match unsafe { CStr::from_ptr(hash_string) }.to_str().ok().and_then(|s| HashString::parse(s).ok()) {
+		Some(hash_string) => hash_string,
+		None => return BCRYPT_ONLY_ERR_PARSE,
+	};
+
+	match super::mcf::verify(key, &hash_string) {
+		Ok(matched) => matched as c_int,
+		Err(error) => error.error_code() as c_int,
+	}
+}
+
+/// Starts hashing the `key_len`-byte key at `key` with a setting, as [`bcrypt_only_hash`] would, in the session at `session`. Only the cheap setup happens here; [`bcrypt_only_session_step`] runs the expensive rounds.
+///
+/// # Safety
+///
+/// `session` has to point to a writable [`Session`] that isn’t in use. `key` has to point to `key_len` readable bytes, or can be null if `key_len` is 0, and `setting` has to be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bcrypt_only_session_create(session: *mut Session, key: *const u8, key_len: usize, setting: *const c_char) -> c_int {
+	if session.is_null() || setting.is_null() {
+		return BCRYPT_ONLY_ERR_NULL;
+	}
+
+	// SAFETY: the caller guarantees `key` points to `key_len` readable bytes.
+	let key = match unsafe { key_slice(key, key_len) } {
+		Some(key) => key,
+		None => return BCRYPT_ONLY_ERR_NULL,
+	};
+
+	// SAFETY: the caller guarantees `setting` is NUL-terminated.
+	let setting = match parse_setting(unsafe { CStr::from_ptr(setting) }) {
+		Some(setting) => setting,
+		None => return BCRYPT_ONLY_ERR_PARSE,
+	};
+
+	let state = match BcryptState::new(key, &setting.salt(), setting.work_factor()) {
+		Ok(state) => state,
+		Err(error) => return error.error_code() as c_int,
+	};
+
+	// SAFETY: the caller guarantees `session` is writable, and it’s large and aligned enough for a `SessionState`.
+	unsafe {
+		ptr::write(session.cast::<SessionState>(), SessionState { state, setting });
+	}
+
+	BCRYPT_ONLY_OK
+}
+
+/// Runs up to `rounds` more expensive rounds of the session at `session`. Returns 1 once all of them have run, 0 if some remain, or a negative error code.
+///
+/// # Safety
+///
+/// `session` has to point to a session created by [`bcrypt_only_session_create`] and not yet destroyed.
+#[no_mangle]
+pub unsafe extern "C" fn bcrypt_only_session_step(session: *mut Session, rounds: u32) -> c_int {
+	if session.is_null() {
+		return BCRYPT_ONLY_ERR_NULL;
+	}
+
+	// SAFETY: the caller guarantees `session` holds a `SessionState`.
+	let session = unsafe { &mut *session.cast::<SessionState>() };
+	session.state.run(rounds).is_some() as c_int
+}
+
+/// Writes the hash string for the session at `session`, all of whose rounds have run, into the [`BCRYPT_ONLY_HASH_BUFFER_SIZE`]-byte buffer at `output`.
+///
+/// # Safety
+///
+/// `session` has to point to a session created by [`bcrypt_only_session_create`] and not yet destroyed, and `output` to [`BCRYPT_ONLY_HASH_BUFFER_SIZE`] writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn bcrypt_only_session_finish(session: *mut Session, output: *mut c_char) -> c_int {
+	if session.is_null() || output.is_null() {
+		return BCRYPT_ONLY_ERR_NULL;
+	}
+
+	// SAFETY: the caller guarantees `session` holds a `SessionState`.
+	let session = unsafe { &mut *session.cast::<SessionState>() };
+
+	let hash = match session.state.run(0) {
+		Some(hash) => hash,
+		None => return BCRYPT_ONLY_ERR_INCOMPLETE,
+	};
+	let setting = &session.setting;
+	let hash_string = HashString::new(setting.version(), setting.work_factor(), &setting.salt(), &hash);
+
+	// SAFETY: the caller guarantees `output` points to `BCRYPT_ONLY_HASH_BUFFER_SIZE` writable bytes.
+	let output = unsafe { slice::from_raw_parts_mut(output.cast::<u8>(), BCRYPT_ONLY_HASH_BUFFER_SIZE) };
+	output[..HASH_STRING_SIZE].copy_from_slice(hash_string.as_str().as_bytes());
+	output[HASH_STRING_SIZE] = 0;
+
+	BCRYPT_ONLY_OK
+}
+
+/// Ends the session at `session`, finished or not, and zeroes its storage, which can then be reused for another session.
+///
+/// # Safety
+///
+/// `session` has to point to a session created by [`bcrypt_only_session_create`] and not yet destroyed, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn bcrypt_only_session_destroy(session: *mut Session) {
+	if session.is_null() {
+		return;
+	}
+
+	// SAFETY: the caller guarantees `session` holds a `SessionState`, which isn’t used again.
+	unsafe {
+		ptr::drop_in_place(session.cast::<SessionState>());
+		ptr::write_bytes(session, 0, 1);
+	}
+}
@@ -0,0 +1,61 @@
+use core::time::Duration;
+
+/// A monotonic clock, for the APIs that time hashing.
+pub trait Clock {
+	/// The time elapsed since an arbitrary fixed point, never decreasing.
+	fn now(&self) -> Duration;
+
+	/// Waits until `duration` has passed by this clock. By default, spins reading [`now`](Self::now); clocks that can sleep, or that only pretend to pass time, should override it.
+	fn sleep(&self, duration: Duration) {
+		let start = self.now();
+
+		while self.now().saturating_sub(start) < duration {
+			core::hint::spin_loop();
+		}
+	}
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+	fn now(&self) -> Duration {
+		(**self).now()
+	}
+
+	fn sleep(&self, duration: Duration) {
+		(**self).sleep(duration)
+	}
+}
+
+/// The system’s monotonic clock.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+	origin: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl SystemClock {
+	/// Creates a clock measuring from now.
+	pub fn new() -> Self {
+		Self {
+			origin: std::time::Instant::now(),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl Default for SystemClock {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+	fn now(&self) -> Duration {
+		self.origin.elapsed()
+	}
+
+	fn sleep(&self, duration: Duration) {
+		std::thread::sleep(duration)
+	}
+}
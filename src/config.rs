@@ -0,0 +1,63 @@
+use core::fmt;
+use core::ops::RangeInclusive;
+use std::env;
+
+use super::WorkFactor;
+
+/// An error resolving a configured work factor.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WorkFactorConfigError {
+	/// The value wasn’t a decimal integer.
+	Syntax,
+
+	/// The environment variable wasn’t valid Unicode.
+	NotUnicode,
+
+	/// The value was outside the allowed range.
+	OutOfRange {
+		value: u32,
+		allowed: RangeInclusive<WorkFactor>,
+	},
+}
+
+impl fmt::Display for WorkFactorConfigError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WorkFactorConfigError::Syntax => f.write_str("bcrypt work factor isn’t a number"),
+			WorkFactorConfigError::NotUnicode => f.write_str("bcrypt work factor isn’t valid Unicode"),
+			WorkFactorConfigError::OutOfRange { value, allowed } => write!(f, "bcrypt work factor {} outside {} to {}", value, allowed.start().log_rounds(), allowed.end().log_rounds()),
+		}
+	}
+}
+
+impl std::error::Error for WorkFactorConfigError {}
+
+/// Resolves a work factor from a configuration value: `default` if there’s no value, and otherwise the value as a base-2 exponent, which has to be within `allowed`.
+///
+/// A value that’s present but invalid is an error rather than a reason to use the default, so a typo in a deployment’s configuration fails loudly instead of quietly hashing at a cost nobody chose. Surrounding whitespace is ignored.
+pub fn resolve_work_factor(value: Option<&str>, default: WorkFactor, allowed: RangeInclusive<WorkFactor>) -> Result<WorkFactor, WorkFactorConfigError> {
+	let value = match value {
+		Some(value) => value.trim(),
+		None => return Ok(default),
+	};
+
+	if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+		return Err(WorkFactorConfigError::Syntax);
+	}
+
+	let log_rounds = value.parse::<u32>().unwrap_or(u32::MAX);
+
+	match WorkFactor::exp(log_rounds) {
+		Some(work_factor) if allowed.contains(&work_factor) => Ok(work_factor),
+		_ => Err(WorkFactorConfigError::OutOfRange { value: log_rounds, allowed }),
+	}
+}
+
+/// [`resolve_work_factor`] with the value of the environment variable `name`, using `default` if it isn’t set.
+pub fn work_factor_from_env(name: &str, default: WorkFactor, allowed: RangeInclusive<WorkFactor>) -> Result<WorkFactor, WorkFactorConfigError> {
+	match env::var(name) {
+		Ok(value) => resolve_work_factor(Some(&value), default, allowed),
+		Err(env::VarError::NotPresent) => Ok(default),
+		Err(env::VarError::NotUnicode(_)) => Err(WorkFactorConfigError::NotUnicode),
+	}
+}
@@ -0,0 +1,18 @@
+use super::sha512::hmac;
+use super::{SALT_SIZE, Salt};
+
+impl Salt {
+	/// Derives a salt from a namespace and a record identifier, for systems that can’t store a salt alongside each hash – with HMAC-SHA-512 keyed by `namespace`, truncated to the salt size.
+	///
+	/// **Prefer [`Salt::generate`] wherever a random salt can be stored.** A derived salt is only as unique as the identifier it comes from:
+	///
+	/// - Rehashing a record, such as after a password change, reuses its salt, so an old hash and a new one of the same password are identical, and an attacker who has both can tell.
+	/// - Anyone who knows the namespace and an identifier knows that record’s salt in advance, and can start a dictionary attack on it before the hash leaks.
+	/// - Two systems sharing a namespace give the same user the same salt; use a namespace unique to each system, generated randomly and kept secret where possible.
+	pub fn derive(namespace: &[u8; 16], identifier: &[u8]) -> Self {
+		let mac = hmac(namespace, &[b"bcrypt-only salt\0", identifier]);
+		let mut bytes = [0_u8; SALT_SIZE];
+		bytes.copy_from_slice(&mac[..SALT_SIZE]);
+		Self::from_bytes(&bytes)
+	}
+}
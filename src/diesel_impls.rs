@@ -0,0 +1,42 @@
+//! `FromSql` and `ToSql` for the `diesel` feature, for every backend. `HashString` derives `AsExpression` and `FromSqlRow` for `Text` in the `mcf` module.
+
+use core::convert::TryFrom;
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::{Binary, Text};
+use std::string::String;
+use std::vec::Vec;
+
+use super::mcf::{COMPACT_SIZE, HashString, ParseError};
+
+/// Validates the text with [`HashString::parse`].
+impl<DB: Backend> FromSql<Text, DB> for HashString
+where
+	String: FromSql<Text, DB>,
+{
+	fn from_sql(value: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		Ok(HashString::parse(&String::from_sql(value)?)?)
+	}
+}
+
+/// The hash string, as text.
+impl<DB: Backend> ToSql<Text, DB> for HashString
+where
+	str: ToSql<Text, DB>,
+{
+	fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+		self.as_str().to_sql(out)
+	}
+}
+
+/// Validates the bytes with [`HashString::from_compact`]. Bind [`to_compact`](HashString::to_compact)’s bytes to write a binary column.
+impl<DB: Backend> FromSql<Binary, DB> for HashString
+where
+	Vec<u8>: FromSql<Binary, DB>,
+{
+	fn from_sql(value: DB::RawValue<'_>) -> deserialize::Result<Self> {
+		let bytes = <[u8; COMPACT_SIZE]>::try_from(Vec::from_sql(value)?).map_err(|_| ParseError::Length)?;
+		Ok(HashString::from_compact(&bytes)?)
+	}
+}
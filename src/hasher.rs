@@ -0,0 +1,59 @@
+//! The [`password-hash`](https://docs.rs/password-hash) crate’s traits, for frameworks generic over password hashing functions.
+
+use core::convert::TryFrom;
+
+use password_hash::{Error, PasswordHasher, PasswordVerifier};
+
+use super::mcf::{self, HashString, ParseError};
+use super::{BcryptError, SALT_SIZE, Salt, WorkFactor};
+
+/// bcrypt as a [`PasswordHasher`] producing `$2b$` hash strings at a work factor, and a [`PasswordVerifier`] of hash strings. Enable `password-hash`’s `getrandom` feature for `hash_password`, which picks the salt itself.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Bcrypt {
+	/// The work factor new hashes use. Verification uses each hash string’s own.
+	pub work_factor: WorkFactor,
+}
+
+/// Every key bcrypt rejects is an invalid password to the traits.
+fn key_error(_err: BcryptError) -> Error {
+	Error::PasswordInvalid
+}
+
+fn parse_error(err: ParseError) -> Error {
+	match err {
+		ParseError::Version => Error::Algorithm,
+		ParseError::WorkFactor => Error::ParamInvalid { name: "cost" },
+		ParseError::Length | ParseError::Encoding | ParseError::NonCanonical => Error::EncodingInvalid,
+	}
+}
+
+/// Hashes with [`mcf::hash`]. Fails with [`Error::SaltInvalid`] unless the salt is exactly 16 bytes, and with [`Error::PasswordInvalid`] for a key bcrypt doesn’t accept.
+impl PasswordHasher<HashString> for Bcrypt {
+	fn hash_password_with_salt(&self, password: &[u8], salt: &[u8]) -> password_hash::Result<HashString> {
+		let salt = <&[u8; SALT_SIZE]>::try_from(salt).map_err(|_| Error::SaltInvalid)?;
+		mcf::hash(password, &Salt::from_bytes(salt), self.work_factor).map_err(key_error)
+	}
+}
+
+/// Verifies with [`mcf::verify`], failing with [`Error::PasswordInvalid`] for a mismatch or a key bcrypt doesn’t accept.
+impl PasswordVerifier<HashString> for Bcrypt {
+	fn verify_password(&self, password: &[u8], hash: &HashString) -> password_hash::Result<()> {
+		match mcf::verify(password, hash) {
+			Ok(true) => Ok(()),
+			Ok(false) => Err(Error::PasswordInvalid),
+			Err(err) => Err(key_error(err)),
+		}
+	}
+}
+
+/// Verifies a stored hash string – `$2a$` and `$2y$` as well as `$2b$` – as [`HashString::parse`] parses it. `password-hash` 0.6 has no `McfHasher` to upgrade such strings to PHC strings; frameworks generic over the traits verify them as they are. Fails with [`Error::Algorithm`] for another algorithm’s string, and with [`Error::ParamInvalid`] or [`Error::EncodingInvalid`] for a malformed one.
+impl PasswordVerifier<str> for Bcrypt {
+	fn verify_password(&self, password: &[u8], hash: &str) -> password_hash::Result<()> {
+		// Checked before the length, so another algorithm’s string is reported as one.
+		if !hash.starts_with("$2") {
+			return Err(Error::Algorithm);
+		}
+
+		self.verify_password(password, &HashString::parse(hash).map_err(parse_error)?)
+	}
+}
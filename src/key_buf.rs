@@ -0,0 +1,142 @@
+use core::fmt;
+use core::hint::black_box;
+
+use super::{HASH_SIZE, KEY_SIZE_MAX, Salt, WorkFactor, bcrypt_raw, record_rounds, runtime_initial_state};
+
+/// An error adding bytes to a [`KeyBuf`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum KeyBufError {
+	/// The buffer was full.
+	Full,
+
+	/// The byte was 0, which bcrypt keys can’t contain.
+	ZeroByte,
+}
+
+impl fmt::Display for KeyBufError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			KeyBufError::Full => "password buffer full",
+			KeyBufError::ZeroByte => "password contains a NUL character",
+		})
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyBufError {}
+
+/// A key collected into a fixed buffer of up to `N` bytes, zeroed when dropped – for firmware reading a password a byte at a time.
+///
+/// `N` can’t be more than 72, checked at compile time, and bytes are checked as they’re added, so the key is always valid and hashing it can’t fail.
+///
+/// ```compile_fail
+/// let key = bcrypt_only::KeyBuf::<73>::new();
+/// ```
+pub struct KeyBuf<const N: usize> {
+	bytes: [u8; N],
+	len: usize,
+}
+
+impl<const N: usize> KeyBuf<N> {
+	const CAPACITY_CHECK: () = assert!(N <= KEY_SIZE_MAX, "a KeyBuf can’t hold more than 72 bytes");
+
+	/// Creates an empty buffer.
+	pub const fn new() -> Self {
+		#[allow(clippy::let_unit_value)]
+		let () = Self::CAPACITY_CHECK;
+
+		Self {
+			bytes: [0; N],
+			len: 0,
+		}
+	}
+
+	/// Adds a byte to the end of the key.
+	pub fn push(&mut self, b: u8) -> Result<(), KeyBufError> {
+		if b == 0 {
+			return Err(KeyBufError::ZeroByte);
+		}
+
+		if self.len == N {
+			return Err(KeyBufError::Full);
+		}
+
+		self.bytes[self.len] = b;
+		self.len += 1;
+		Ok(())
+	}
+
+	/// Adds bytes to the end of the key. If any of them can’t be added, none are.
+	pub fn extend_from_slice(&mut self, bytes: &[u8]) -> Result<(), KeyBufError> {
+		if bytes.contains(&0) {
+			return Err(KeyBufError::ZeroByte);
+		}
+
+		if bytes.len() > N - self.len {
+			return Err(KeyBufError::Full);
+		}
+
+		self.bytes[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+		self.len += bytes.len();
+		Ok(())
+	}
+
+	/// Removes the last byte of the key, as for a backspace.
+	pub fn pop(&mut self) {
+		if self.len != 0 {
+			self.len -= 1;
+			self.bytes[self.len] = 0;
+		}
+	}
+
+	/// Removes every byte of the key, zeroing them.
+	pub fn clear(&mut self) {
+		self.bytes.fill(0);
+		black_box(&mut self.bytes);
+		self.len = 0;
+	}
+
+	/// The key.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes[..self.len]
+	}
+
+	/// The number of bytes in the key.
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Whether the key is empty.
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Hashes the key with bcrypt, like [`bcrypt`](crate::bcrypt) but without an error case.
+	pub fn hash(&self, salt: &Salt, work_factor: WorkFactor) -> [u8; HASH_SIZE] {
+		let mut state = runtime_initial_state();
+		let (hash, rounds) = bcrypt_raw(&mut state, self.as_bytes(), salt, work_factor);
+		record_rounds(rounds);
+		state.zero();
+		hash
+	}
+}
+
+impl<const N: usize> Default for KeyBuf<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const N: usize> Drop for KeyBuf<N> {
+	fn drop(&mut self) {
+		self.clear();
+	}
+}
+
+impl<const N: usize> fmt::Debug for KeyBuf<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("KeyBuf")
+			.field("len", &self.len)
+			.finish_non_exhaustive()
+	}
+}
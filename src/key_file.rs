@@ -0,0 +1,91 @@
+//! Hashing machine credentials stored as key files.
+
+use core::fmt;
+use core::hint::black_box;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use super::{BcryptError, KEY_SIZE_MAX, KeyBuf, KeyBufError, KeyPolicy, Salt, WorkFactor};
+use super::mcf::{self, HashString, Version};
+
+/// An error hashing or verifying a key file.
+#[derive(Debug)]
+pub enum KeyFileError {
+	/// The file couldn’t be read.
+	Io(io::Error),
+
+	/// The file’s contents aren’t a key bcrypt accepts.
+	Bcrypt(BcryptError),
+}
+
+impl fmt::Display for KeyFileError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			KeyFileError::Io(err) => err.fmt(f),
+			KeyFileError::Bcrypt(err) => err.fmt(f),
+		}
+	}
+}
+
+impl std::error::Error for KeyFileError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			KeyFileError::Io(err) => Some(err),
+			KeyFileError::Bcrypt(err) => Some(err),
+		}
+	}
+}
+
+impl From<io::Error> for KeyFileError {
+	fn from(err: io::Error) -> Self {
+		KeyFileError::Io(err)
+	}
+}
+
+/// Reads a key file into a buffer that’s zeroed when dropped, prepared by `policy` – with [`strip_newline`](KeyPolicy::strip_newline) for a file written with a trailing line ending. The file is read through a fixed buffer on the stack, which is zeroed too, and never more than a byte past the longest key bcrypt accepts.
+pub fn read_key_file<P: AsRef<Path>>(path: P, policy: &KeyPolicy) -> Result<KeyBuf<KEY_SIZE_MAX>, KeyFileError> {
+	// Room for the longest key, a line ending, and one more byte to tell whether there was more.
+	let mut buffer = [0_u8; KEY_SIZE_MAX + 3];
+	let result = read_into(path.as_ref(), &mut buffer, policy);
+	buffer.fill(0);
+	black_box(&mut buffer);
+	result
+}
+
+fn read_into(path: &Path, buffer: &mut [u8], policy: &KeyPolicy) -> Result<KeyBuf<KEY_SIZE_MAX>, KeyFileError> {
+	let mut file = File::open(path)?;
+	let mut len = 0;
+
+	while len < buffer.len() {
+		match file.read(&mut buffer[len..]) {
+			Ok(0) => break,
+			Ok(n) => len += n,
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+			Err(err) => return Err(err.into()),
+		}
+	}
+
+	let contents = policy.prepare(&buffer[..len]).map_err(KeyFileError::Bcrypt)?;
+	let mut key = KeyBuf::new();
+
+	key.extend_from_slice(contents).map_err(|err| KeyFileError::Bcrypt(match err {
+		KeyBufError::Full => BcryptError::Length,
+		KeyBufError::ZeroByte => BcryptError::ZeroByte,
+	}))?;
+
+	Ok(key)
+}
+
+/// Reads a key file with [`read_key_file`] and hashes its contents into a `$2b$` hash string.
+pub fn hash_key_file<P: AsRef<Path>>(path: P, policy: &KeyPolicy, salt: &Salt, work_factor: WorkFactor) -> Result<HashString, KeyFileError> {
+	let key = read_key_file(path, policy)?;
+	Ok(HashString::new(Version::V2b, work_factor, salt, &key.hash(salt, work_factor)))
+}
+
+/// Reads a key file with [`read_key_file`] and checks whether its contents match a hash string.
+pub fn verify_key_file<P: AsRef<Path>>(path: P, policy: &KeyPolicy, hash_string: &HashString) -> Result<bool, KeyFileError> {
+	let key = read_key_file(path, policy)?;
+	let hash = key.hash(&hash_string.salt(), hash_string.work_factor());
+	Ok(mcf::hashes_equal(&hash, &hash_string.hash()))
+}
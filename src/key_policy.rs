@@ -0,0 +1,46 @@
+use super::{BcryptError, HASH_SIZE, Salt, WorkFactor, bcrypt_runtime, check_key};
+
+/// How keys are prepared before they’re hashed or verified, for input that doesn’t arrive exactly as typed. The default changes nothing.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct KeyPolicy {
+	/// Remove one trailing `\n` or `\r\n`. Passwords piped in by scripts usually carry the newline `echo` adds, and hashing it in makes the same password typed interactively fail to verify.
+	pub strip_newline: bool,
+
+	/// Reject a key that’s empty once prepared with [`BcryptError::Empty`]. bcrypt accepts the empty key, and existing hashes of it still verify without this, but most deployments would rather hear about a blank credential – usually a missing environment variable or an unfilled form field – than store a hash of it.
+	pub reject_empty: bool,
+}
+
+impl KeyPolicy {
+	/// The policy that changes nothing.
+	pub const DEFAULT: Self = Self {
+		strip_newline: false,
+		reject_empty: false,
+	};
+
+	/// Prepares a key according to the policy, then checks it as [`bcrypt`](crate::bcrypt) would.
+	pub const fn prepare<'a>(&self, key: &'a [u8]) -> Result<&'a [u8], BcryptError> {
+		let key = if self.strip_newline { strip_newline(key) } else { key };
+
+		if self.reject_empty && key.is_empty() {
+			return Err(BcryptError::Empty);
+		}
+
+		match check_key(key) {
+			Ok(()) => Ok(key),
+			Err(err) => Err(err),
+		}
+	}
+
+	/// [`bcrypt`](crate::bcrypt), on the key as prepared by the policy.
+	pub fn bcrypt(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		bcrypt_runtime(self.prepare(key)?, salt, work_factor)
+	}
+}
+
+/// `bytes` without one trailing `\n` or `\r\n`, the line ending everything that reads a line or a one-line file removes.
+pub(crate) const fn strip_newline(bytes: &[u8]) -> &[u8] {
+	match bytes {
+		[rest @ .., b'\r', b'\n'] | [rest @ .., b'\n'] => rest,
+		_ => bytes,
+	}
+}
@@ -1,13 +1,222 @@
 #![no_std]
+#![deny(unsafe_code)]
 
 #[cfg(feature = "std")]
 extern crate std;
 
+use core::convert::TryFrom;
 use core::fmt;
+use core::hint::black_box;
+
+pub mod backend;
+pub mod base64;
+pub mod clock;
+pub mod observer;
+pub mod rng;
+
+#[cfg(feature = "mcf")]
+pub mod mcf;
+
+#[cfg(feature = "mcf")]
+pub mod policy;
+
+#[cfg(feature = "mcf")]
+pub mod preset;
+
+#[cfg(feature = "mcf")]
+pub mod relief;
+
+#[cfg(feature = "mcf")]
+pub mod wrap;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+#[cfg(feature = "unstable")]
+pub mod unstable;
+
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "std")]
+pub mod vectors;
+
+mod calibrate;
+mod capabilities;
+mod key_buf;
+mod key_policy;
+mod pi;
+mod session;
+
+#[cfg(any(feature = "mcf", feature = "bcrypt-pbkdf"))]
+mod sha512;
+
+#[cfg(feature = "bcrypt-pbkdf")]
+mod pbkdf;
+
+#[cfg(feature = "derived-salt")]
+mod derived_salt;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls;
+
+#[cfg(feature = "hybrid-array")]
+mod array;
+
+#[cfg(feature = "tokio")]
+mod async_verifier;
+
+#[cfg(feature = "borsh")]
+mod borsh_impls;
+
+#[cfg(feature = "diesel")]
+mod diesel_impls;
+
+#[cfg(feature = "password-hash")]
+mod hasher;
+
+#[cfg(feature = "schemars")]
+mod schemars_impls;
+
+#[cfg(feature = "node")]
+mod node;
+
+#[cfg(feature = "python")]
+mod python;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
+
+#[cfg(feature = "sqlx")]
+mod sqlx_impls;
+
+#[cfg(feature = "ufmt")]
+mod ufmt_impls;
+
+#[cfg(feature = "uniffi")]
+mod uniffi_api;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+#[cfg(feature = "c-reference")]
+mod c_reference;
+
+#[cfg(feature = "system-crypt")]
+mod system;
+
+#[cfg(feature = "bcrypt-crate")]
+mod bcrypt_crate;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "std")]
+mod cache;
+
+#[cfg(feature = "std")]
+mod config;
+
+#[cfg(feature = "std")]
+mod key_file;
+
+#[cfg(feature = "std")]
+mod load;
+
+#[cfg(feature = "std")]
+mod padding;
+
+#[cfg(feature = "std")]
+mod password;
+
+#[cfg(feature = "std")]
+mod pepper;
+
+#[cfg(feature = "std")]
+mod pool;
+
+#[cfg(feature = "round-count")]
+mod round_count;
+
+#[cfg(feature = "std")]
+mod simulate;
+
+#[cfg(feature = "std")]
+mod throttle;
+
+#[cfg(feature = "std")]
+mod throughput;
+
+#[cfg(feature = "std")]
+mod verifier;
+
+#[cfg(feature = "tracing")]
+mod trace;
+
+pub use calibrate::{ClockStalled, calibrate};
+pub use capabilities::{Capabilities, capabilities};
+pub use key_buf::{KeyBuf, KeyBufError};
+pub use key_policy::KeyPolicy;
+pub use pi::pi_tables;
+pub use session::{BcryptState, HashFuture};
+
+#[cfg(feature = "round-count")]
+pub use round_count::count_rounds;
+
+#[cfg(feature = "hybrid-array")]
+pub use array::{HashArray, SaltArray};
+
+#[cfg(feature = "tokio")]
+pub use async_verifier::{AsyncVerifier, AsyncVerify};
+
+#[cfg(feature = "password-hash")]
+pub use hasher::Bcrypt;
+
+#[cfg(feature = "bcrypt-pbkdf")]
+pub use pbkdf::{InvalidPbkdfParameters, PBKDF_OUTPUT_SIZE_MAX, bcrypt_pbkdf};
+
+#[cfg(feature = "std")]
+pub use cache::VerifyCache;
+
+#[cfg(feature = "std")]
+pub use config::{WorkFactorConfigError, resolve_work_factor, work_factor_from_env};
+
+#[cfg(feature = "std")]
+pub use key_file::{KeyFileError, hash_key_file, read_key_file, verify_key_file};
+
+#[cfg(feature = "std")]
+pub use load::LoadAwareCost;
+
+#[cfg(feature = "std")]
+pub use padding::VerifyPadding;
+
+#[cfg(feature = "std")]
+pub use password::{PASSWORD_SIZE_MAX, Password, prompt_password, prompt_password_to, read_password, read_password_stdin};
+
+#[cfg(feature = "std")]
+pub use pepper::{Pepper, PepperSource, SealedFile};
+
+#[cfg(feature = "std")]
+pub use pool::{PendingVerify, PoolOptions, VerifierPool};
+
+#[cfg(feature = "std")]
+pub use simulate::{Simulation, SimulationReport, simulate_load};
+
+#[cfg(feature = "std")]
+pub use throttle::{Throttle, ThrottledVerify};
+
+#[cfg(feature = "std")]
+pub use throughput::{Throughput, measure_throughput};
+
+#[cfg(feature = "std")]
+pub use verifier::{Verifier, VerifierOptions};
+
 /// The maximum number of bytes in a bcrypt key.
 pub const KEY_SIZE_MAX: usize = 72;
 
@@ -17,26 +226,72 @@ pub const SALT_SIZE: usize = 16;
 /// The number of bytes in a bcrypt hash.
 pub const HASH_SIZE: usize = 23;
 
+/// The message bcrypt enciphers with the key-derived state to produce a hash, read as six big-endian 32-bit words.
+pub const BCRYPT_MESSAGE: [u8; 24] = *b"OrpheanBeholderScryDoubt";
+
+/// The number of times bcrypt enciphers [`BCRYPT_MESSAGE`], in ECB mode, after the expensive rounds.
+pub const BCRYPT_MESSAGE_ENCRYPTIONS: usize = 64;
+
 /// A bcrypt work factor.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct WorkFactor(u32);
 
-/// A bcrypt hashing error.
+/// A bcrypt hashing error. More kinds of error may be added without a major version bump.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
 pub enum BcryptError {
 	/// The key was longer than the limit of 72 bytes.
 	Length,
 
 	/// The key contained a 0 byte.
 	ZeroByte,
+
+	/// The key was empty, and the [`KeyPolicy`] rejects empty keys. bcrypt itself accepts them.
+	Empty,
 }
 
-impl fmt::Display for BcryptError {
-	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "{}", match self {
+impl BcryptError {
+	/// A small negative integer identifying the error, the same one the C API returns for it. The codes are stable.
+	///
+	/// | Error | Code |
+	/// |---|---|
+	/// | [`Length`](Self::Length) | -1 |
+	/// | [`ZeroByte`](Self::ZeroByte) | -2 |
+	/// | [`Empty`](Self::Empty) | -7 |
+	///
+	/// The C API doesn’t apply key policies, so it never returns -7.
+	pub const fn error_code(self) -> i32 {
+		match self {
+			BcryptError::Length => -1,
+			BcryptError::ZeroByte => -2,
+			BcryptError::Empty => -7,
+		}
+	}
+
+	/// What `Display` writes.
+	#[cfg(not(feature = "compact-errors"))]
+	const fn description(self) -> &'static str {
+		match self {
 			BcryptError::Length => "password too long",
 			BcryptError::ZeroByte => "password contains a NUL character",
-		})
+			BcryptError::Empty => "password is empty",
+		}
+	}
+
+	#[cfg(feature = "compact-errors")]
+	const fn description(self) -> &'static str {
+		match self {
+			BcryptError::Length => "-1",
+			BcryptError::ZeroByte => "-2",
+			BcryptError::Empty => "-7",
+		}
+	}
+}
+
+/// A description of the error, or with the `compact-errors` feature, just its [error code](BcryptError::error_code).
+impl fmt::Display for BcryptError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.description())
 	}
 }
 
@@ -51,27 +306,31 @@ pub struct Salt {
 
 impl Salt {
 	/// Creates a bcrypt salt from any 16 bytes.
-	pub fn from_bytes(bytes: &[u8; SALT_SIZE]) -> Self {
+	pub const fn from_bytes(bytes: &[u8; SALT_SIZE]) -> Self {
 		let mut be = [0_u32; 4];
+		let mut i = 0;
 
-		for i in 0..4 {
+		while i < 4 {
 			be[i] = u32::from_be_bytes([
 				bytes[4 * i],
 				bytes[4 * i + 1],
 				bytes[4 * i + 2],
 				bytes[4 * i + 3],
 			]);
+			i += 1;
 		}
 
 		Self { be }
 	}
 
 	/// Gets the bytes making up a bcrypt salt.
-	pub fn to_bytes(&self) -> [u8; SALT_SIZE] {
+	pub const fn to_bytes(&self) -> [u8; SALT_SIZE] {
 		let mut bytes = [0_u8; 16];
+		let mut i = 0;
 
-		for (b, w) in bytes.chunks_exact_mut(4).zip(self.be.iter().copied()) {
-			b.copy_from_slice(&w.to_be_bytes());
+		while i < 16 {
+			bytes[i] = self.be[i / 4].to_be_bytes()[i % 4];
+			i += 1;
 		}
 
 		bytes
@@ -109,7 +368,7 @@ impl WorkFactor {
 	pub const EXP31: Self = Self(31);
 
 	/// Creates a bcrypt work factor from a typical base-2 exponent between 4 and 31 (inclusive). The number of rounds is 2\*\*`log_rounds`.
-	pub fn exp(log_rounds: u32) -> Option<Self> {
+	pub const fn exp(log_rounds: u32) -> Option<Self> {
 		if log_rounds >= 4 && log_rounds <= 31 {
 			Some(Self(log_rounds))
 		} else {
@@ -128,8 +387,40 @@ impl WorkFactor {
 	}
 }
 
+/// Validates a base-2 exponent as in [`WorkFactor::exp`].
+impl TryFrom<u32> for WorkFactor {
+	type Error = InvalidWorkFactor;
+
+	fn try_from(log_rounds: u32) -> Result<Self, InvalidWorkFactor> {
+		Self::exp(log_rounds).ok_or(InvalidWorkFactor(log_rounds))
+	}
+}
+
+impl From<WorkFactor> for u32 {
+	fn from(work_factor: WorkFactor) -> u32 {
+		work_factor.log_rounds()
+	}
+}
+
+/// An error converting an integer outside 4 to 31 to a [`WorkFactor`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InvalidWorkFactor(pub u32);
+
+impl fmt::Display for InvalidWorkFactor {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("bcrypt work factor outside 4 to 31")
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidWorkFactor {}
+
 const BLF_N: usize = 16;
 
+/// The number of words in the P-array and S-boxes, all of which every expansion of the state overwrites.
+const STATE_WORDS: usize = BLF_N + 2 + 4 * 256;
+
+#[cfg_attr(feature = "runtime-tables", allow(dead_code))]
 const BLOWFISH_INITIAL: BlowfishContext = BlowfishContext {
 	s: include!("sbox-init.in"),
 	p: [
@@ -141,22 +432,16 @@ const BLOWFISH_INITIAL: BlowfishContext = BlowfishContext {
 	],
 };
 
-const BCRYPT_MESSAGE: [u32; 6] = {
-	const fn u32_from_be_bytes(bytes: [u8; 4]) -> u32 {
-		(bytes[0] as u32) << 24
-		| (bytes[1] as u32) << 16
-		| (bytes[2] as u32) << 8
-		| (bytes[3] as u32)
-	}
-
-	[
-		u32_from_be_bytes(*b"Orph"),
-		u32_from_be_bytes(*b"eanB"),
-		u32_from_be_bytes(*b"ehol"),
-		u32_from_be_bytes(*b"derS"),
-		u32_from_be_bytes(*b"cryD"),
-		u32_from_be_bytes(*b"oubt"),
-	]
+const BCRYPT_MESSAGE_WORDS: [u32; 6] = {
+	let mut words = [0_u32; 6];
+	let mut i = 0;
+
+	while i < words.len() {
+		words[i] = u32::from_be_bytes([BCRYPT_MESSAGE[4 * i], BCRYPT_MESSAGE[4 * i + 1], BCRYPT_MESSAGE[4 * i + 2], BCRYPT_MESSAGE[4 * i + 3]]);
+		i += 1;
+	}
+
+	words
 };
 
 #[derive(Clone)]
@@ -165,25 +450,31 @@ struct BlowfishContext {
 	p: [u32; BLF_N + 2], // subkeys
 }
 
-fn read_u32_be<T: Iterator<Item = u8>>(bytes: &mut T) -> u32 {
-	u32::from(bytes.next().unwrap()) << 24
-	| u32::from(bytes.next().unwrap()) << 16
-	| u32::from(bytes.next().unwrap()) << 8
-	| u32::from(bytes.next().unwrap())
+impl BlowfishContext {
+	/// Overwrites the key-derived state with zeroes.
+	fn zero(&mut self) {
+		self.s = [[0; 256]; 4];
+		self.p = [0; BLF_N + 2];
+		black_box(self);
+	}
 }
 
-fn f(c: &BlowfishContext, x: u32) -> u32 {
+const fn f(c: &BlowfishContext, x: u32) -> u32 {
 	let [b0, b1, b2, b3] = x.to_be_bytes();
-	let h = c.s[0][usize::from(b0)].wrapping_add(c.s[1][usize::from(b1)]);
-	(h ^ c.s[2][usize::from(b2)]).wrapping_add(c.s[3][usize::from(b3)])
+	let h = c.s[0][b0 as usize].wrapping_add(c.s[1][b1 as usize]);
+	(h ^ c.s[2][b2 as usize]).wrapping_add(c.s[3][b3 as usize])
 }
 
-fn blowfish_encipher(c: &BlowfishContext, mut l: u32, mut r: u32) -> (u32, u32) {
-	for i in (0..16).step_by(2) {
+#[cfg(not(feature = "size-opt"))]
+const fn blowfish_encipher(c: &BlowfishContext, mut l: u32, mut r: u32) -> (u32, u32) {
+	let mut i = 0;
+
+	while i < 16 {
 		l ^= c.p[i];
 		r ^= f(c, l);
 		r ^= c.p[i + 1];
 		l ^= f(c, r);
+		i += 2;
 	}
 
 	l ^= c.p[16];
@@ -192,133 +483,424 @@ fn blowfish_encipher(c: &BlowfishContext, mut l: u32, mut r: u32) -> (u32, u32)
 	(r, l)
 }
 
-/// An iterator yielding the bytes of a key, then 0, forever.
-struct KeyCycle<'a> {
-	key: &'a [u8],
-	index: usize,
+/// One Feistel round per iteration, kept out of line so there’s a single copy of the round function.
+#[cfg(feature = "size-opt")]
+#[inline(never)]
+const fn blowfish_encipher(c: &BlowfishContext, mut l: u32, mut r: u32) -> (u32, u32) {
+	let mut i = 0;
+
+	while i < 16 {
+		l ^= c.p[i];
+		r ^= f(c, l);
+		let t = l;
+		l = r;
+		r = t;
+		i += 1;
+	}
+
+	l ^= c.p[16];
+	r ^= c.p[17];
+
+	(r, l)
 }
 
-impl<'a> Iterator for KeyCycle<'a> {
-	type Item = u8;
+/// Reads the next 4 bytes of the infinite sequence made of the bytes of a key followed by 0, repeated, starting at `*index` in that sequence.
+const fn read_key_cycle_u32_be(key: &[u8], index: &mut usize) -> u32 {
+	let mut result = 0_u32;
+	let mut i = 0;
 
-	fn next(&mut self) -> Option<u8> {
-		if self.index == self.key.len() {
-			self.index = 0;
-			return Some(0);
-		}
+	while i < 4 {
+		let b = if *index == key.len() {
+			*index = 0;
+			0
+		} else {
+			*index += 1;
+			key[*index - 1]
+		};
 
-		let result = self.key[self.index];
-		self.index += 1;
-		Some(result)
+		result = result << 8 | b as u32;
+		i += 1;
 	}
+
+	result
 }
 
-fn blowfish_expandstate_key(c: &mut BlowfishContext, key: &[u8]) {
-	let mut key_cycle = KeyCycle { key, index: 0 };
+const fn blowfish_expandstate_key(c: &mut BlowfishContext, key: &[u8]) {
+	let mut index = 0;
+	let mut i = 0;
 
-	for pi in &mut c.p {
-		let temp = read_u32_be(&mut key_cycle);
-		*pi ^= temp;
+	while i < BLF_N + 2 {
+		c.p[i] ^= read_key_cycle_u32_be(key, &mut index);
+		i += 1;
 	}
 }
 
-fn blowfish_expandstate_data(c: &mut BlowfishContext, data: &[u32; 4]) {
+/// Expands the state with data read cyclically, which has to be 4 words, as bcrypt’s salt is, or 16, as a SHA-512 digest is – lengths that 256 is a multiple of and that leave 2 words over after the 18 subkeys, so the S-box loops can index it the same way for both.
+const fn blowfish_expandstate_data<const N: usize>(c: &mut BlowfishContext, data: &[u32; N]) {
 	let mut datal = 0_u32;
 	let mut datar = 0_u32;
+	let mut written = 0;
+
+	let mut i = 0;
 
-	for i in (0..BLF_N + 2).step_by(2) {
-		datal ^= data[i % 4];
-		datar ^= data[i % 4 + 1];
+	while i < BLF_N + 2 {
+		datal ^= data[i % N];
+		datar ^= data[i % N + 1];
 		let (nextl, nextr) = blowfish_encipher(c, datal, datar);
 		datal = nextl;
 		datar = nextr;
 
 		c.p[i] = datal;
 		c.p[i + 1] = datar;
+		written += 2;
+		i += 2;
 	}
 
-	for i in 0..4 {
-		for k in (0..256).step_by(2) {
-			datal ^= data[(k + 2) % 4];
-			datar ^= data[(k + 2) % 4 + 1];
+	let mut i = 0;
+
+	while i < 4 {
+		let mut k = 0;
+
+		while k < 256 {
+			datal ^= data[(k + 2) % N];
+			datar ^= data[(k + 2) % N + 1];
 			let (nextl, nextr) = blowfish_encipher(c, datal, datar);
 			datal = nextl;
 			datar = nextr;
 
 			c.s[i][k] = datal;
 			c.s[i][k + 1] = datar;
+			written += 2;
+			k += 2;
 		}
+
+		i += 1;
 	}
+
+	debug_assert!(written == STATE_WORDS, "expansion left state words unwritten");
 }
 
-fn blowfish_expandstate_data0(c: &mut BlowfishContext) {
+#[cfg(not(feature = "size-opt"))]
+const fn blowfish_expandstate_data0(c: &mut BlowfishContext) {
 	let mut datal = 0_u32;
 	let mut datar = 0_u32;
+	let mut written = 0;
+
+	let mut i = 0;
 
-	for i in (0..BLF_N + 2).step_by(2) {
+	while i < BLF_N + 2 {
 		let (nextl, nextr) = blowfish_encipher(c, datal, datar);
 		datal = nextl;
 		datar = nextr;
 
 		c.p[i] = datal;
 		c.p[i + 1] = datar;
+		written += 2;
+		i += 2;
 	}
 
-	for i in 0..4 {
-		for k in (0..256).step_by(2) {
+	let mut i = 0;
+
+	while i < 4 {
+		let mut k = 0;
+
+		while k < 256 {
 			let (nextl, nextr) = blowfish_encipher(c, datal, datar);
 			datal = nextl;
 			datar = nextr;
 
 			c.s[i][k] = datal;
 			c.s[i][k + 1] = datar;
+			written += 2;
+			k += 2;
 		}
+
+		i += 1;
 	}
+
+	debug_assert!(written == STATE_WORDS, "expansion left state words unwritten");
 }
 
-/// Hashes a key and salt with bcrypt according to a work factor. The key can’t be longer than 72 bytes and can’t contain a 0 byte.
-pub fn bcrypt(key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+/// Expanding with all-zero data is the same as expanding with no data.
+#[cfg(feature = "size-opt")]
+const fn blowfish_expandstate_data0(c: &mut BlowfishContext) {
+	blowfish_expandstate_data(c, &[0; 4]);
+}
+
+#[cfg(not(feature = "runtime-tables"))]
+const fn initial_state() -> BlowfishContext {
+	BLOWFISH_INITIAL
+}
+
+/// Generates the initial tables instead of embedding 4 KiB of them. Only [`bcrypt`], which can’t reach a cache at compile time, does this for every hash; everything else goes through [`runtime_initial_state`].
+#[cfg(feature = "runtime-tables")]
+const fn initial_state() -> BlowfishContext {
+	let (p, s) = pi_tables();
+	BlowfishContext { s, p }
+}
+
+#[cfg(not(all(feature = "runtime-tables", feature = "std")))]
+fn runtime_initial_state() -> BlowfishContext {
+	initial_state()
+}
+
+/// Generates the initial tables once per process, since that takes about as long as fifty hashes at the lowest work factor.
+#[cfg(all(feature = "runtime-tables", feature = "std"))]
+fn runtime_initial_state() -> BlowfishContext {
+	static INITIAL: std::sync::OnceLock<BlowfishContext> = std::sync::OnceLock::new();
+	INITIAL.get_or_init(initial_state).clone()
+}
+
+// Fails the build if the initial tables are ever corrupted, by checking the first of Eric Young’s Blowfish test vectors: an all-zero key (which bcrypt’s key schedule treats the same way as Blowfish’s) and an all-zero block. A bcrypt known-answer test would take seconds of const evaluation; this covers the same constants in milliseconds.
+#[cfg(not(feature = "runtime-tables"))]
+const _: () = {
+	let mut c = BLOWFISH_INITIAL;
+	blowfish_expandstate_data0(&mut c);
+	let (l, r) = blowfish_encipher(&c, 0, 0);
+	assert!(l == 0x4ef99745 && r == 0x6198dd78, "Blowfish known-answer test failed");
+};
+
+/// Reusable working state for bcrypt: the S-boxes and P-array that the expensive rounds read and write.
+///
+/// [`bcrypt`] keeps this state on the stack. Hashing with [`bcrypt_with_context`] instead lets the caller decide where it lives – for example, in a `static` given a `#[link_section]` that maps to tightly-coupled memory or SRAM on a microcontroller, where running the rounds against state in slow memory can double the hashing time. The contents are overwritten at the start of every hash, and zeroed at the end of it, so a long-lived context doesn’t keep state derived from the last key.
+///
+/// With the `runtime-tables` feature, a context also keeps the initial tables it generates for its first hash, and copies them for later ones. Without `std`, where there’s no process-wide copy to share, this is how to avoid generating them for every hash.
+pub struct Context {
+	state: BlowfishContext,
+	/// The generated initial tables, kept from the first hash so later ones only copy them.
+	#[cfg(feature = "runtime-tables")]
+	initial: Option<BlowfishContext>,
+}
+
+impl Context {
+	/// Creates a new context.
+	pub const fn new() -> Self {
+		Self {
+			state: BlowfishContext {
+				s: [[0; 256]; 4],
+				p: [0; BLF_N + 2],
+			},
+			#[cfg(feature = "runtime-tables")]
+			initial: None,
+		}
+	}
+}
+
+impl Default for Context {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl fmt::Debug for Context {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Context { .. }")
+	}
+}
+
+/// A copy of the initial Blowfish state placed in `.data` rather than read-only data, so startup code on targets that copy `.data` to RAM puts it there. Section names differ by object format: Mach-O needs a segment and section, and COFF groups `.data$…` into `.data`.
+#[cfg(all(feature = "ram-tables", not(feature = "runtime-tables")))]
+#[allow(unsafe_code)]
+#[cfg_attr(not(any(target_vendor = "apple", windows)), link_section = ".data.bcrypt_only.initial")]
+#[cfg_attr(target_vendor = "apple", link_section = "__DATA,__bcrypt_init")]
+#[cfg_attr(windows, link_section = ".data$bcrypt_only")]
+static BLOWFISH_INITIAL_RAM: BlowfishContext = BLOWFISH_INITIAL;
+
+#[cfg(not(feature = "runtime-tables"))]
+fn reset(c: &mut BlowfishContext) {
+	#[cfg(feature = "ram-tables")]
+	c.clone_from(&BLOWFISH_INITIAL_RAM);
+
+	#[cfg(not(feature = "ram-tables"))]
+	{
+		*c = initial_state();
+	}
+}
+
+const fn check_key(key: &[u8]) -> Result<(), BcryptError> {
 	if key.len() > KEY_SIZE_MAX {
 		return Err(BcryptError::Length);
 	}
 
-	if key.contains(&b'\0') {
-		return Err(BcryptError::ZeroByte);
+	let mut i = 0;
+
+	while i < key.len() {
+		if key[i] == 0 {
+			return Err(BcryptError::ZeroByte);
+		}
+
+		i += 1;
 	}
 
-	let mut state = BLOWFISH_INITIAL;
+	Ok(())
+}
 
-	blowfish_expandstate_key(&mut state, key);
-	blowfish_expandstate_data(&mut state, &salt.be);
+/// Starts bcrypt from a `state` that’s already been set to the initial Blowfish state, by expanding it with the key and salt.
+#[inline(always)]
+const fn bcrypt_setup(state: &mut BlowfishContext, key: &[u8], salt: &Salt) {
+	blowfish_expandstate_key(state, key);
+	blowfish_expandstate_data(state, &salt.be);
+}
 
-	for _ in 0..work_factor.linear_rounds() {
-		blowfish_expandstate_key(&mut state, key);
-		blowfish_expandstate_data0(&mut state);
+/// Runs one of bcrypt’s expensive rounds.
+#[inline(always)]
+const fn bcrypt_round(state: &mut BlowfishContext, key: &[u8], salt: &Salt) {
+	blowfish_expandstate_key(state, key);
+	blowfish_expandstate_data0(state);
 
-		for i in 0..(BLF_N + 2) {
-			state.p[i] ^= salt.be[i % 4];
-		}
+	let mut i = 0;
 
-		blowfish_expandstate_data0(&mut state);
+	while i < BLF_N + 2 {
+		state.p[i] ^= salt.be[i % 4];
+		i += 1;
 	}
 
-	let mut cdata = BCRYPT_MESSAGE;
+	debug_assert!(i == BLF_N + 2, "salt not applied to every subkey");
+
+	blowfish_expandstate_data0(state);
+}
 
-	for _ in 0..64 {
-		for i in (0..BCRYPT_MESSAGE.len()).step_by(2) {
-			let (l, r) = blowfish_encipher(&state, cdata[i], cdata[i + 1]);
+/// Enciphers a message 64 times with the state after the expensive rounds.
+#[inline(always)]
+const fn encipher_message(state: &BlowfishContext, message: [u32; 6]) -> [u32; 6] {
+	let mut cdata = message;
+	let mut blocks = 0;
+
+	let mut n = 0;
+
+	while n < BCRYPT_MESSAGE_ENCRYPTIONS {
+		let mut i = 0;
+
+		while i < cdata.len() {
+			let (l, r) = blowfish_encipher(state, cdata[i], cdata[i + 1]);
 			cdata[i] = l;
 			cdata[i + 1] = r;
+			blocks += 1;
+			i += 2;
 		}
+
+		n += 1;
 	}
 
+	debug_assert!(blocks == 3 * BCRYPT_MESSAGE_ENCRYPTIONS, "message not enciphered 64 times");
+
+	cdata
+}
+
+/// Finishes bcrypt after the expensive rounds, by enciphering the message 64 times.
+#[inline(always)]
+const fn bcrypt_finish(state: &BlowfishContext) -> [u8; HASH_SIZE] {
+	let cdata = encipher_message(state, BCRYPT_MESSAGE_WORDS);
+
 	let mut result = [0_u8; 23];
 
-	for (b, w) in result.chunks_exact_mut(4).zip(cdata.iter().copied()) {
-		b.copy_from_slice(&w.to_be_bytes());
+	let mut i = 0;
+
+	// The truncation `truncate_ciphertext` describes, without assembling the last byte.
+	while i < HASH_SIZE {
+		result[i] = cdata[i / 4].to_be_bytes()[i % 4];
+		i += 1;
 	}
 
-	result[20..].copy_from_slice(&cdata[5].to_be_bytes()[0..3]);
+	result
+}
+
+/// Copies `src` into `dst`, which has to be the same length, like `copy_from_slice` – which isn’t usable in `const fn` before Rust 1.87.
+const fn copy_bytes(dst: &mut [u8], src: &[u8]) {
+	assert!(dst.len() == src.len());
+
+	let mut i = 0;
+
+	while i < dst.len() {
+		dst[i] = src[i];
+		i += 1;
+	}
+}
+
+/// The hash bcrypt outputs from its enciphered message: all but the last byte. The original implementation encoded 23 bytes into its hash strings, dropping the last for a 31-character encoding, and everything since has kept the truncation.
+pub const fn truncate_ciphertext(ciphertext: &[u8; 24]) -> [u8; HASH_SIZE] {
+	let mut hash = [0_u8; HASH_SIZE];
+	let (kept, _) = ciphertext.split_at(HASH_SIZE);
+	copy_bytes(&mut hash, kept);
+	hash
+}
+
+/// Runs bcrypt starting from a `state` that’s already been set to the initial Blowfish state. Returns the hash and the number of expensive rounds run, for [`record_rounds`].
+const fn bcrypt_raw(state: &mut BlowfishContext, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> ([u8; HASH_SIZE], u64) {
+	bcrypt_setup(state, key, salt);
+
+	let mut round = 0;
+
+	while round < work_factor.linear_rounds() {
+		bcrypt_round(state, key, salt);
+		round += 1;
+	}
+
+	(bcrypt_finish(state), round as u64)
+}
+
+#[cfg(feature = "round-count")]
+use round_count::record as record_rounds;
+
+/// Counts expensive rounds run, for [`count_rounds`] with the `round-count` feature.
+#[cfg(not(feature = "round-count"))]
+#[inline(always)]
+fn record_rounds(_rounds: u64) {}
+
+/// Hashes a key and salt with bcrypt according to a work factor. The key can’t be longer than 72 bytes and can’t contain a 0 byte.
+///
+/// This can be evaluated at compile time, so known-answer hashes can be embedded as constants. Even the lowest work factor takes long enough that the constant needs `#[allow(long_running_const_eval)]`.
+///
+/// With the `runtime-tables` feature, this generates the initial tables for every call, since a `const fn` can’t reach a cache; [`bcrypt_with_context`] and the hash string functions don’t.
+pub const fn bcrypt(key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+	if let Err(err) = check_key(key) {
+		return Err(err);
+	}
+
+	let mut state = initial_state();
+	let (hash, _) = bcrypt_raw(&mut state, key, salt, work_factor);
+	Ok(hash)
+}
+
+/// [`bcrypt`] for callers that only run it at runtime, so the `runtime-tables` feature can copy the initial state from a cache.
+pub(crate) fn bcrypt_runtime(key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+	check_key(key)?;
+
+	let mut state = runtime_initial_state();
+	let (hash, rounds) = bcrypt_raw(&mut state, key, salt, work_factor);
+	record_rounds(rounds);
+	Ok(hash)
+}
+
+/// [`bcrypt`], using the working state in `context` instead of the stack.
+pub fn bcrypt_with_context(key: &[u8], salt: &Salt, work_factor: WorkFactor, context: &mut Context) -> Result<[u8; HASH_SIZE], BcryptError> {
+	check_key(key)?;
+
+	#[cfg(feature = "runtime-tables")]
+	context.state.clone_from(context.initial.get_or_insert_with(runtime_initial_state));
+	#[cfg(not(feature = "runtime-tables"))]
+	reset(&mut context.state);
+
+	let (hash, rounds) = bcrypt_raw(&mut context.state, key, salt, work_factor);
+	record_rounds(rounds);
+	context.state.zero();
+	Ok(hash)
+}
+
+/// Checks whether a key matches a raw digest from a legacy store, comparing in constant time. The digest can be bcrypt’s 23-byte hash or the full 24-byte enciphered message some stores kept; the 24th byte is ignored, since bcrypt’s hash is defined as the first 23 (see [`truncate_ciphertext`]). A digest of any other length doesn’t match.
+pub fn verify_digest(key: &[u8], salt: &Salt, work_factor: WorkFactor, digest: &[u8]) -> Result<bool, BcryptError> {
+	let hash = bcrypt_runtime(key, salt, work_factor)?;
+
+	let stored = match digest.len() {
+		HASH_SIZE | 24 => &digest[..HASH_SIZE],
+		_ => return Ok(false),
+	};
+
+	let mut difference = 0_u8;
+
+	for (x, y) in hash.iter().zip(stored) {
+		difference |= black_box(x ^ y);
+	}
 
-	Ok(result)
+	Ok(difference == 0)
 }
@@ -0,0 +1,74 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::WorkFactor;
+
+#[derive(Debug)]
+struct LoadState {
+	average: Option<Duration>,
+	degraded: bool,
+}
+
+/// Chooses between a normal and a degraded work factor based on recent hashing latency, for services that need to stay responsive under load (e.g. credential stuffing).
+///
+/// Latency samples – typically the time a hash spent queued plus the time it took – are fed in with [`record`](Self::record) and smoothed into a moving average. The degraded work factor is selected once the average rises above `enter`, and the normal one again only once it falls below `exit`, so the selection doesn’t flap around a single threshold.
+#[derive(Debug)]
+pub struct LoadAwareCost {
+	normal: WorkFactor,
+	degraded: WorkFactor,
+	enter: Duration,
+	exit: Duration,
+	state: Mutex<LoadState>,
+}
+
+impl LoadAwareCost {
+	/// Creates a selector that starts out using the normal work factor. Returns `None` if `exit` is greater than `enter`.
+	pub fn new(normal: WorkFactor, degraded: WorkFactor, enter: Duration, exit: Duration) -> Option<Self> {
+		if exit > enter {
+			return None;
+		}
+
+		Some(Self {
+			normal,
+			degraded,
+			enter,
+			exit,
+			state: Mutex::new(LoadState {
+				average: None,
+				degraded: false,
+			}),
+		})
+	}
+
+	/// Records one latency sample. Each sample contributes an eighth of the new moving average.
+	pub fn record(&self, latency: Duration) {
+		let mut state = self.state.lock().unwrap();
+
+		let average = match state.average {
+			Some(average) => average.saturating_mul(7).saturating_add(latency) / 8,
+			None => latency,
+		};
+
+		state.average = Some(average);
+
+		if average > self.enter {
+			state.degraded = true;
+		} else if average < self.exit {
+			state.degraded = false;
+		}
+	}
+
+	/// Whether the degraded work factor is currently selected.
+	pub fn is_degraded(&self) -> bool {
+		self.state.lock().unwrap().degraded
+	}
+
+	/// The work factor to use for the next hash.
+	pub fn work_factor(&self) -> WorkFactor {
+		if self.is_degraded() {
+			self.degraded
+		} else {
+			self.normal
+		}
+	}
+}
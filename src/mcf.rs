@@ -0,0 +1,670 @@
+//! The modular crypt format for bcrypt: `$2b$`, a two-digit work factor, `$`, then the salt and hash in bcrypt’s base64.
+
+use core::fmt;
+use core::hint::black_box;
+use core::ops::Deref;
+use core::str::{self, FromStr};
+use core::time::Duration;
+
+use super::{BcryptError, BcryptState, HASH_SIZE, SALT_SIZE, Salt, WorkFactor, base64, check_key, copy_bytes};
+use super::backend::{BcryptBackend, DefaultBackend};
+use super::clock::Clock;
+use super::observer::{Observer, bcrypt_observed};
+
+#[cfg(feature = "tracing")]
+use super::trace::Traced;
+
+/// The number of rounds [`verify_with_deadline`] runs between checks of the clock.
+const DEADLINE_CHECK_ROUNDS: u32 = 16;
+
+/// The length of a bcrypt hash string.
+pub const HASH_STRING_SIZE: usize = 60;
+
+/// The length of the setting part of a hash string: everything up to and including the salt.
+pub const SETTING_SIZE: usize = 29;
+
+/// The length of a salt in bcrypt’s base64, as it appears in a hash string.
+pub const SALT_STRING_SIZE: usize = 22;
+
+/// The length of the compact binary form of a hash string: the version letter, the work factor, the salt, and the hash.
+pub const COMPACT_SIZE: usize = 2 + SALT_SIZE + HASH_SIZE;
+
+/// A bcrypt version identifier. All three are the same algorithm for any key this crate accepts; they differ only in how historical implementations handled keys it doesn’t.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Version {
+	/// `$2a$`.
+	V2a,
+
+	/// `$2b$`, the current version.
+	V2b,
+
+	/// `$2y$`, crypt_blowfish’s name for `$2b$`.
+	V2y,
+}
+
+impl Version {
+	const fn letter(self) -> u8 {
+		match self {
+			Version::V2a => b'a',
+			Version::V2b => b'b',
+			Version::V2y => b'y',
+		}
+	}
+}
+
+/// The owned, fixed-capacity modular crypt format string type, under the name other crypt APIs use for it.
+pub type McfString = HashString;
+
+/// An error parsing a hash string.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ParseError {
+	/// The string wasn’t 60 bytes long.
+	Length,
+
+	/// The string didn’t start with a supported version prefix.
+	Version,
+
+	/// The work factor wasn’t two digits between 04 and 31.
+	WorkFactor,
+
+	/// The salt or hash wasn’t valid bcrypt base64.
+	Encoding,
+
+	/// The salt or hash had nonzero unused bits in its last character, which no bcrypt implementation produces.
+	NonCanonical,
+}
+
+impl ParseError {
+	/// The error code the C API returns for any parse error, -3, which [`BcryptError::error_code`]’s codes don’t overlap. The code is stable.
+	pub const fn error_code(self) -> i32 {
+		-3
+	}
+
+	/// What `Display` writes.
+	#[cfg(not(feature = "compact-errors"))]
+	pub(crate) const fn description(self) -> &'static str {
+		match self {
+			ParseError::Length => "bcrypt hash string has the wrong length",
+			ParseError::Version => "unsupported bcrypt version",
+			ParseError::WorkFactor => "invalid bcrypt work factor",
+			ParseError::Encoding => "invalid base64 in bcrypt hash string",
+			ParseError::NonCanonical => "non-canonical base64 in bcrypt hash string",
+		}
+	}
+
+	#[cfg(feature = "compact-errors")]
+	pub(crate) const fn description(self) -> &'static str {
+		"-3"
+	}
+}
+
+/// A description of the error, or with the `compact-errors` feature, just its [error code](ParseError::error_code).
+impl fmt::Display for ParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.description())
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+/// A valid bcrypt hash string, stored inline in 60 bytes – an owned encoded hash without allocation. It dereferences to `str`.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "diesel", derive(diesel::expression::AsExpression, diesel::deserialize::FromSqlRow), diesel(sql_type = diesel::sql_types::Text))]
+pub struct HashString {
+	bytes: [u8; HASH_STRING_SIZE],
+}
+
+impl HashString {
+	/// Formats the parts of a bcrypt hash into a hash string.
+	pub const fn new(version: Version, work_factor: WorkFactor, salt: &Salt, hash: &[u8; HASH_SIZE]) -> Self {
+		let mut bytes = [0_u8; HASH_STRING_SIZE];
+		let log_rounds = work_factor.log_rounds() as u8;
+
+		bytes[0] = b'$';
+		bytes[1] = b'2';
+		bytes[2] = version.letter();
+		bytes[3] = b'$';
+		bytes[4] = b'0' + log_rounds / 10;
+		bytes[5] = b'0' + log_rounds % 10;
+		bytes[6] = b'$';
+
+		let (_, rest) = bytes.split_at_mut(7);
+		let (salt_chars, hash_chars) = rest.split_at_mut(SALT_STRING_SIZE);
+		base64::encode(&salt.to_bytes(), salt_chars);
+		base64::encode(hash, hash_chars);
+
+		Self { bytes }
+	}
+
+	/// Parses a hash string with a `$2a$`, `$2b$`, or `$2y$` prefix.
+	pub const fn parse(s: &str) -> Result<Self, ParseError> {
+		let s = s.as_bytes();
+
+		if let Err(err) = validate(s) {
+			return Err(err);
+		}
+
+		let mut bytes = [0_u8; HASH_STRING_SIZE];
+		copy_bytes(&mut bytes, s);
+		Ok(Self { bytes })
+	}
+
+	/// [`parse`](Self::parse), after undoing the damage hash strings pick up in exports: surrounding whitespace and line breaks, an LDAP `{CRYPT}` or `{BLF-CRYPT}` scheme prefix, and `$` doubled by a layer of escaping. For recovering old dumps; anything parsing input it controls should use [`parse`](Self::parse).
+	pub fn parse_lenient(s: &str) -> Result<Self, ParseError> {
+		let mut s = s.trim();
+
+		for prefix in ["{CRYPT}", "{BLF-CRYPT}"] {
+			if s.len() >= prefix.len() && s.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes()) {
+				s = s[prefix.len()..].trim_start();
+				break;
+			}
+		}
+
+		// `$` isn’t in bcrypt’s base64 alphabet, so runs of it only ever stand for one.
+		let mut bytes = [0_u8; HASH_STRING_SIZE];
+		let mut len = 0;
+		let mut previous = 0;
+
+		for b in s.bytes() {
+			if b == b'$' && previous == b'$' {
+				continue;
+			}
+
+			if len == HASH_STRING_SIZE {
+				return Err(ParseError::Length);
+			}
+
+			bytes[len] = b;
+			len += 1;
+			previous = b;
+		}
+
+		// Only whole ASCII characters were removed, so it’s still UTF-8.
+		Self::parse(str::from_utf8(&bytes[..len]).unwrap())
+	}
+
+	/// Decodes the fixed-size binary form produced by [`to_compact`](Self::to_compact).
+	pub const fn from_compact(bytes: &[u8; COMPACT_SIZE]) -> Result<Self, ParseError> {
+		let version = match bytes[0] {
+			b'a' => Version::V2a,
+			b'b' => Version::V2b,
+			b'y' => Version::V2y,
+			_ => return Err(ParseError::Version),
+		};
+
+		let work_factor = match WorkFactor::exp(bytes[1] as u32) {
+			Some(work_factor) => work_factor,
+			None => return Err(ParseError::WorkFactor),
+		};
+
+		let (_, rest) = bytes.split_at(2);
+		let (salt, hash) = rest.split_at(SALT_SIZE);
+		let mut salt_bytes = [0_u8; SALT_SIZE];
+		let mut hash_bytes = [0_u8; HASH_SIZE];
+		copy_bytes(&mut salt_bytes, salt);
+		copy_bytes(&mut hash_bytes, hash);
+
+		Ok(Self::new(version, work_factor, &Salt::from_bytes(&salt_bytes), &hash_bytes))
+	}
+
+	/// Encodes the hash string in a fixed-size binary form, for compact binary envelopes: the version letter, the work factor as one byte, the salt, then the hash. Every valid hash string round-trips through it.
+	pub const fn to_compact(&self) -> [u8; COMPACT_SIZE] {
+		let mut bytes = [0_u8; COMPACT_SIZE];
+		bytes[0] = self.bytes[2];
+		bytes[1] = self.work_factor().log_rounds() as u8;
+
+		let (_, rest) = bytes.split_at_mut(2);
+		let (salt, hash) = rest.split_at_mut(SALT_SIZE);
+		copy_bytes(salt, &self.salt().to_bytes());
+		copy_bytes(hash, &self.hash());
+
+		bytes
+	}
+
+	/// The hash string.
+	pub const fn as_str(&self) -> &str {
+		match str::from_utf8(&self.bytes) {
+			Ok(s) => s,
+			Err(_) => unreachable!(),
+		}
+	}
+
+	/// The hash string’s version prefix.
+	pub const fn version(&self) -> Version {
+		match self.bytes[2] {
+			b'a' => Version::V2a,
+			b'b' => Version::V2b,
+			_ => Version::V2y,
+		}
+	}
+
+	/// The same hash with a `$2b$` prefix in place of `$2y$`, which crypt_blowfish uses for the same algorithm. Other hash strings are returned unchanged: `$2a$` hashes from some older implementations differ from `$2b$` ones for the same key, so only rehashing can upgrade them.
+	pub const fn relabel_v2y(self) -> Self {
+		let mut bytes = self.bytes;
+
+		if bytes[2] == b'y' {
+			bytes[2] = b'b';
+		}
+
+		Self { bytes }
+	}
+
+	/// The hash string’s work factor.
+	pub const fn work_factor(&self) -> WorkFactor {
+		WorkFactor(((self.bytes[4] - b'0') * 10 + (self.bytes[5] - b'0')) as u32)
+	}
+
+	/// The hash string’s salt.
+	pub const fn salt(&self) -> Salt {
+		let mut salt = [0_u8; SALT_SIZE];
+		let (_, rest) = self.bytes.split_at(7);
+		let (salt_chars, _) = rest.split_at(SALT_STRING_SIZE);
+		base64::decode(salt_chars, &mut salt).unwrap();
+		Salt::from_bytes(&salt)
+	}
+
+	/// The hash string’s hash.
+	pub const fn hash(&self) -> [u8; HASH_SIZE] {
+		let mut hash = [0_u8; HASH_SIZE];
+		let (_, hash_chars) = self.bytes.split_at(SETTING_SIZE);
+		base64::decode(hash_chars, &mut hash).unwrap();
+		hash
+	}
+}
+
+/// A bcrypt setting string: `$2b$`, a two-digit work factor, `$`, and a salt – a hash string without its hash. Both constructors are `const fn`, so a device provisioned with a fixed salt can embed a setting validated at compile time.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct Setting {
+	bytes: [u8; SETTING_SIZE],
+}
+
+impl Setting {
+	/// Formats a `$2b$` setting string.
+	pub const fn new(work_factor: WorkFactor, salt: &Salt) -> Self {
+		let full = HashString::new(Version::V2b, work_factor, salt, &[0; HASH_SIZE]);
+		let mut bytes = [0_u8; SETTING_SIZE];
+		let (setting, _) = full.bytes.split_at(SETTING_SIZE);
+		copy_bytes(&mut bytes, setting);
+		Self { bytes }
+	}
+
+	/// Parses a setting string with a `$2a$`, `$2b$`, or `$2y$` prefix, checked the same way as [`HashString::parse`].
+	pub const fn parse(s: &str) -> Result<Self, ParseError> {
+		let s = s.as_bytes();
+
+		if s.len() != SETTING_SIZE {
+			return Err(ParseError::Length);
+		}
+
+		// an all-zero hash is always valid, so only the setting can fail
+		let mut full = [b'.'; HASH_STRING_SIZE];
+		let (setting, _) = full.split_at_mut(SETTING_SIZE);
+		copy_bytes(setting, s);
+
+		match str::from_utf8(&full) {
+			Ok(full) => match HashString::parse(full) {
+				Ok(_) => {
+					let mut bytes = [0_u8; SETTING_SIZE];
+					copy_bytes(&mut bytes, s);
+					Ok(Self { bytes })
+				}
+				Err(err) => Err(err),
+			},
+			Err(_) => Err(ParseError::Encoding),
+		}
+	}
+
+	/// The setting string.
+	pub const fn as_str(&self) -> &str {
+		match str::from_utf8(&self.bytes) {
+			Ok(s) => s,
+			Err(_) => unreachable!(),
+		}
+	}
+
+	const fn with_zero_hash(&self) -> HashString {
+		let mut bytes = [b'.'; HASH_STRING_SIZE];
+		let (setting, _) = bytes.split_at_mut(SETTING_SIZE);
+		copy_bytes(setting, &self.bytes);
+		HashString { bytes }
+	}
+
+	/// The setting’s version prefix.
+	pub const fn version(&self) -> Version {
+		self.with_zero_hash().version()
+	}
+
+	/// The setting’s work factor.
+	pub const fn work_factor(&self) -> WorkFactor {
+		self.with_zero_hash().work_factor()
+	}
+
+	/// The setting’s salt.
+	pub const fn salt(&self) -> Salt {
+		self.with_zero_hash().salt()
+	}
+
+	/// Hashes a key with this setting into a hash string with the same version prefix.
+	pub fn hash(&self, key: &[u8]) -> Result<HashString, BcryptError> {
+		let salt = self.salt();
+		let hash = super::bcrypt_runtime(key, &salt, self.work_factor())?;
+		Ok(HashString::new(self.version(), self.work_factor(), &salt, &hash))
+	}
+}
+
+impl HashString {
+	/// The hash string’s setting: everything but the hash.
+	pub const fn setting(&self) -> Setting {
+		let mut bytes = [0_u8; SETTING_SIZE];
+		let (setting, _) = self.bytes.split_at(SETTING_SIZE);
+		copy_bytes(&mut bytes, setting);
+		Setting { bytes }
+	}
+}
+
+impl FromStr for Setting {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, ParseError> {
+		Self::parse(s)
+	}
+}
+
+impl fmt::Display for Setting {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl fmt::Debug for Setting {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("Setting").field(&self.as_str()).finish()
+	}
+}
+
+impl FromStr for HashString {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, ParseError> {
+		Self::parse(s)
+	}
+}
+
+impl Deref for HashString {
+	type Target = str;
+
+	fn deref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl AsRef<str> for HashString {
+	fn as_ref(&self) -> &str {
+		self.as_str()
+	}
+}
+
+impl fmt::Display for HashString {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+impl fmt::Debug for HashString {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("HashString").field(&self.as_str()).finish()
+	}
+}
+
+/// Formats a salt in bcrypt’s base64, as it appears in a hash string.
+impl fmt::Display for Salt {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut chars = [0_u8; SALT_STRING_SIZE];
+		base64::encode(&self.to_bytes(), &mut chars);
+		f.write_str(str::from_utf8(&chars).unwrap())
+	}
+}
+
+/// Parses a salt in bcrypt’s base64, as it appears in a hash string.
+impl FromStr for Salt {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, ParseError> {
+		let s = s.as_bytes();
+
+		if s.len() != SALT_STRING_SIZE {
+			return Err(ParseError::Length);
+		}
+
+		let mut salt = [0_u8; SALT_SIZE];
+
+		if base64::decode(s, &mut salt).is_none() {
+			return Err(ParseError::Encoding);
+		}
+
+		if !base64::is_canonical(s) {
+			return Err(ParseError::NonCanonical);
+		}
+
+		Ok(Salt::from_bytes(&salt))
+	}
+}
+
+/// Checks whether bytes are a valid hash string, as [`HashString::parse`] would, without copying them – for checking a store of hash strings before a migration, with no hashing involved.
+pub const fn validate(s: &[u8]) -> Result<(), ParseError> {
+	if s.len() != HASH_STRING_SIZE {
+		return Err(ParseError::Length);
+	}
+
+	if s[0] != b'$' || s[1] != b'2' || !matches!(s[2], b'a' | b'b' | b'y') || s[3] != b'$' {
+		return Err(ParseError::Version);
+	}
+
+	if !s[4].is_ascii_digit() || !s[5].is_ascii_digit() || s[6] != b'$' {
+		return Err(ParseError::WorkFactor);
+	}
+
+	if WorkFactor::exp(((s[4] - b'0') * 10 + (s[5] - b'0')) as u32).is_none() {
+		return Err(ParseError::WorkFactor);
+	}
+
+	let (_, rest) = s.split_at(7);
+	let (salt_chars, hash_chars) = rest.split_at(SALT_STRING_SIZE);
+
+	if !base64::is_valid(rest) {
+		return Err(ParseError::Encoding);
+	}
+
+	if !base64::is_canonical(salt_chars) || !base64::is_canonical(hash_chars) {
+		return Err(ParseError::NonCanonical);
+	}
+
+	Ok(())
+}
+
+/// Checks every line of a dump of hash strings – a file read or mapped into memory, one hash string per line – with [`validate`], giving the 1-based number of each invalid line and what’s wrong with it. Line endings can be `\n` or `\r\n`, and there can be one at the end.
+pub fn invalid_lines(data: &[u8]) -> impl Iterator<Item = (usize, ParseError)> + '_ {
+	data.split_inclusive(|&b| b == b'\n')
+		.enumerate()
+		.filter_map(|(i, line)| {
+			let line = line.strip_suffix(b"\n").unwrap_or(line);
+			let line = line.strip_suffix(b"\r").unwrap_or(line);
+			validate(line).err().map(|err| (i + 1, err))
+		})
+}
+
+/// Compares two hashes in constant time.
+pub fn hashes_equal(a: &[u8; HASH_SIZE], b: &[u8; HASH_SIZE]) -> bool {
+	let mut difference = 0_u8;
+
+	for (x, y) in a.iter().zip(b) {
+		difference |= black_box(x ^ y);
+	}
+
+	difference == 0
+}
+
+/// Hashes a key with bcrypt into a `$2b$` hash string.
+pub fn hash(key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<HashString, BcryptError> {
+	hash_with_backend(&DefaultBackend::default(), key, salt, work_factor)
+}
+
+/// [`hash`], hashing with `backend`.
+pub fn hash_with_backend<B: BcryptBackend + ?Sized>(backend: &B, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<HashString, BcryptError> {
+	#[cfg(feature = "tracing")]
+	let traced = Traced::start("hash", backend.name(), work_factor);
+
+	let hash = backend.hash(key, salt, work_factor);
+
+	#[cfg(feature = "tracing")]
+	match hash {
+		Ok(_) => traced.finish("ok"),
+		Err(err) => traced.fail(err),
+	}
+
+	Ok(HashString::new(Version::V2b, work_factor, salt, &hash?))
+}
+
+/// [`hash`], reporting to an observer as [`bcrypt_observed`] does.
+pub fn hash_with_observer<O: Observer>(key: &[u8], salt: &Salt, work_factor: WorkFactor, observer: &O) -> Result<HashString, BcryptError> {
+	let hash = bcrypt_observed(key, salt, work_factor, observer)?;
+	Ok(HashString::new(Version::V2b, work_factor, salt, &hash))
+}
+
+/// Checks whether a key matches a hash string, comparing the hashes in constant time.
+pub fn verify(key: &[u8], hash_string: &HashString) -> Result<bool, BcryptError> {
+	verify_with_backend(&DefaultBackend::default(), key, hash_string)
+}
+
+/// [`verify`], hashing with `backend`.
+pub fn verify_with_backend<B: BcryptBackend + ?Sized>(backend: &B, key: &[u8], hash_string: &HashString) -> Result<bool, BcryptError> {
+	#[cfg(feature = "tracing")]
+	let traced = Traced::start("verify", backend.name(), hash_string.work_factor());
+
+	let matched = backend.hash(key, &hash_string.salt(), hash_string.work_factor()).map(|hash| hashes_equal(&hash, &hash_string.hash()));
+
+	#[cfg(feature = "tracing")]
+	match matched {
+		Ok(true) => traced.finish("match"),
+		Ok(false) => traced.finish("mismatch"),
+		Err(err) => traced.fail(err),
+	}
+
+	matched
+}
+
+/// [`verify`], reporting to an observer as [`bcrypt_observed`] does.
+pub fn verify_with_observer<O: Observer>(key: &[u8], hash_string: &HashString, observer: &O) -> Result<bool, BcryptError> {
+	let hash = bcrypt_observed(key, &hash_string.salt(), hash_string.work_factor(), observer)?;
+	Ok(hashes_equal(&hash, &hash_string.hash()))
+}
+
+/// Checks a key against each of several hash strings – the duplicate rows left for one account by merging systems, say – giving the version of each one that matches and `None` for each that doesn’t. Every version hashes keys this crate accepts the same way, so a match under any of them is a match under all of them; the version says which system’s row it was.
+pub fn verify_each<'a>(key: &'a [u8], hash_strings: &'a [HashString]) -> Result<impl Iterator<Item = Option<Version>> + 'a, BcryptError> {
+	check_key(key)?;
+
+	Ok(hash_strings.iter().map(move |hash_string| {
+		// The key was checked above.
+		match verify(key, hash_string).unwrap() {
+			true => Some(hash_string.version()),
+			false => None,
+		}
+	}))
+}
+
+/// [`verify_each`], spreading the hash strings across as many threads as the system has cores.
+#[cfg(feature = "std")]
+pub fn verify_each_parallel(key: &[u8], hash_strings: &[HashString]) -> Result<std::vec::Vec<Option<Version>>, BcryptError> {
+	check_key(key)?;
+
+	let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+	let chunk_size = hash_strings.len().div_ceil(threads).max(1);
+	let mut results = std::vec![None; hash_strings.len()];
+
+	std::thread::scope(|scope| {
+		for (hash_strings, results) in hash_strings.chunks(chunk_size).zip(results.chunks_mut(chunk_size)) {
+			scope.spawn(move || {
+				for (result, matched) in results.iter_mut().zip(verify_each(key, hash_strings).unwrap()) {
+					*result = matched;
+				}
+			});
+		}
+	});
+
+	Ok(results)
+}
+
+/// The result of a verification that says more than whether the key matched: [`verify_outcome`], [`verify_with_deadline`], and, with the `std` feature, `Verifier::verify`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[must_use]
+pub enum VerifyOutcome {
+	/// The key matched, and the hash string is up to date.
+	Match,
+
+	/// The key didn’t match.
+	Mismatch,
+
+	/// The key matched, but the hash string should be replaced with a new hash of the key while it’s at hand: its work factor is below the target, or it’s a `$2a$` hash, which some older implementations computed differently. `$2y$` hashes don’t need rehashing, only [relabeling](HashString::relabel_v2y).
+	NeedsRehash {
+		version: Version,
+		work_factor: WorkFactor,
+	},
+
+	/// The key matched only as one of a `Verifier`’s legacy key policies prepared it – the one at index `policy` in `VerifierOptions::legacy_key_policies`. The hash string should be replaced with a new hash of the key as the current policy prepares it.
+	LegacyQuirkMatch {
+		policy: usize,
+	},
+
+	/// The deadline passed before the hash was done, so whether the key matched is unknown.
+	TimedOut,
+}
+
+impl VerifyOutcome {
+	/// Whether the key matched, whether or not the hash string needs replacing.
+	pub const fn is_match(self) -> bool {
+		matches!(self, VerifyOutcome::Match | VerifyOutcome::NeedsRehash { .. } | VerifyOutcome::LegacyQuirkMatch { .. })
+	}
+
+	/// Whether the key matched, but the hash string should be replaced with a new hash of it.
+	pub const fn needs_rehash(self) -> bool {
+		matches!(self, VerifyOutcome::NeedsRehash { .. } | VerifyOutcome::LegacyQuirkMatch { .. })
+	}
+}
+
+/// [`verify`], also checking whether a matching hash string should be rehashed at `target`.
+pub fn verify_outcome(key: &[u8], hash_string: &HashString, target: WorkFactor) -> Result<VerifyOutcome, BcryptError> {
+	if !verify(key, hash_string)? {
+		return Ok(VerifyOutcome::Mismatch);
+	}
+
+	let version = hash_string.version();
+	let work_factor = hash_string.work_factor();
+
+	Ok(if work_factor < target || version == Version::V2a {
+		VerifyOutcome::NeedsRehash { version, work_factor }
+	} else {
+		VerifyOutcome::Match
+	})
+}
+
+/// [`verify`], giving up once `clock` reaches `deadline`, so an overloaded service answers slow verifications with an explicit timeout instead of an unbounded wait. The outcome is [`Match`](VerifyOutcome::Match), [`Mismatch`](VerifyOutcome::Mismatch), or [`TimedOut`](VerifyOutcome::TimedOut).
+///
+/// The hash runs as a [`BcryptState`], checking the clock every 16 rounds – around a millisecond of work on a typical server core – so the deadline is overshot by about that much at most.
+pub fn verify_with_deadline<C: Clock>(key: &[u8], hash_string: &HashString, clock: &C, deadline: Duration) -> Result<VerifyOutcome, BcryptError> {
+	let mut state = BcryptState::new(key, &hash_string.salt(), hash_string.work_factor())?;
+
+	loop {
+		if let Some(hash) = state.run(DEADLINE_CHECK_ROUNDS) {
+			return Ok(if hashes_equal(&hash, &hash_string.hash()) {
+				VerifyOutcome::Match
+			} else {
+				VerifyOutcome::Mismatch
+			});
+		}
+
+		if clock.now() >= deadline {
+			return Ok(VerifyOutcome::TimedOut);
+		}
+	}
+}
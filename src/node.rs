@@ -0,0 +1,158 @@
+//! `genSalt`, `hash`, and `compare` for Node, through napi-rs, mirroring the `bcrypt` package’s promise API and hashing on libuv’s thread pool like its addon does. Build with `cargo rustc --lib --features node --release --crate-type cdylib`, adding `-C link-arg=-undefined -C link-arg=dynamic_lookup` after `--` on macOS, and load the library renamed to `bcrypt_only.node`.
+
+// napi-derive leaves out registering the exports in tests, where Node’s symbols aren’t there to link against.
+#![cfg_attr(test, allow(dead_code))]
+
+use core::fmt;
+use core::hint::black_box;
+use napi::bindgen_prelude::{AsyncTask, Buffer, Either};
+use napi::{Env, Task};
+use napi_derive::napi;
+use std::format;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use super::mcf::{self, HashString, SETTING_SIZE, Setting};
+use super::rng::OsRng;
+use super::{BcryptError, Salt, WorkFactor};
+
+/// Why a promise was rejected, with the `bcrypt` package’s messages where it has one.
+#[derive(Debug)]
+pub(crate) enum Rejection {
+	InvalidRounds,
+	InvalidSalt,
+	Password(BcryptError),
+}
+
+impl fmt::Display for Rejection {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::InvalidRounds => f.write_str("Invalid rounds"),
+			Self::InvalidSalt => f.write_str("Invalid salt"),
+			Self::Password(err) => err.fmt(f),
+		}
+	}
+}
+
+impl From<Rejection> for napi::Error {
+	fn from(rejection: Rejection) -> Self {
+		Self::from_reason(rejection.to_string())
+	}
+}
+
+/// A copy of a password, zeroed when it’s dropped.
+struct Password(Vec<u8>);
+
+impl From<Either<String, Buffer>> for Password {
+	fn from(data: Either<String, Buffer>) -> Self {
+		Self(match data {
+			Either::A(data) => data.into_bytes(),
+			Either::B(data) => data.to_vec(),
+		})
+	}
+}
+
+impl Drop for Password {
+	fn drop(&mut self) {
+		self.0.fill(0);
+		black_box(&mut self.0);
+	}
+}
+
+pub(crate) fn generate_setting(rounds: u32) -> Result<Setting, Rejection> {
+	let work_factor = WorkFactor::exp(rounds).ok_or(Rejection::InvalidRounds)?;
+	Ok(Setting::new(work_factor, &Salt::generate(OsRng)))
+}
+
+/// Hashes with the setting at the start of a salt string, or with a new salt at a number of rounds.
+pub(crate) fn hash_with(password: &[u8], salt_or_rounds: &Either<u32, String>) -> Result<HashString, Rejection> {
+	let setting = match salt_or_rounds {
+		Either::A(rounds) => generate_setting(*rounds)?,
+		Either::B(salt) => salt.get(..SETTING_SIZE)
+			.and_then(|setting| Setting::parse(setting).ok())
+			.ok_or(Rejection::InvalidSalt)?,
+	};
+	setting.hash(password).map_err(Rejection::Password)
+}
+
+/// Verifies a password, with `false` for a hash string that isn’t valid, as the `bcrypt` package’s addon gives.
+pub(crate) fn compare_with(password: &[u8], hash_string: &str) -> Result<bool, Rejection> {
+	match HashString::parse(hash_string) {
+		Ok(hash_string) => mcf::verify(password, &hash_string).map_err(Rejection::Password),
+		Err(_) => Ok(false),
+	}
+}
+
+/// The work of [`gen_salt`].
+pub struct GenSalt {
+	rounds: u32,
+}
+
+impl Task for GenSalt {
+	type Output = Setting;
+	type JsValue = String;
+
+	fn compute(&mut self) -> napi::Result<Setting> {
+		Ok(generate_setting(self.rounds)?)
+	}
+
+	fn resolve(&mut self, _env: Env, setting: Setting) -> napi::Result<String> {
+		Ok(setting.as_str().into())
+	}
+}
+
+/// The work of [`hash`].
+pub struct Hash {
+	password: Password,
+	salt_or_rounds: Either<u32, String>,
+}
+
+impl Task for Hash {
+	type Output = HashString;
+	type JsValue = String;
+
+	fn compute(&mut self) -> napi::Result<HashString> {
+		Ok(hash_with(&self.password.0, &self.salt_or_rounds)?)
+	}
+
+	fn resolve(&mut self, _env: Env, hash_string: HashString) -> napi::Result<String> {
+		Ok(hash_string.as_str().into())
+	}
+}
+
+/// The work of [`compare`].
+pub struct Compare {
+	password: Password,
+	hash_string: String,
+}
+
+impl Task for Compare {
+	type Output = bool;
+	type JsValue = bool;
+
+	fn compute(&mut self) -> napi::Result<bool> {
+		Ok(compare_with(&self.password.0, &self.hash_string)?)
+	}
+
+	fn resolve(&mut self, _env: Env, matched: bool) -> napi::Result<bool> {
+		Ok(matched)
+	}
+}
+
+/// Generates a `$2b$` setting with a salt from the operating system. Rejects if `rounds` isn’t 4 to 31.
+#[napi(ts_return_type = "Promise<string>")]
+pub fn gen_salt(rounds: Option<u32>) -> AsyncTask<GenSalt> {
+	AsyncTask::new(GenSalt { rounds: rounds.unwrap_or(10) })
+}
+
+/// Hashes a password with a setting from `genSalt`, which can be a whole hash string, or with a new salt at a number of rounds. Rejects if the salt or rounds aren’t valid, or the password is longer than 72 bytes or contains a NUL byte.
+#[napi(ts_return_type = "Promise<string>")]
+pub fn hash(data: Either<String, Buffer>, salt_or_rounds: Either<u32, String>) -> AsyncTask<Hash> {
+	AsyncTask::new(Hash { password: data.into(), salt_or_rounds })
+}
+
+/// Checks a password against a hash string in constant time, resolving to `false` if the hash string isn’t valid. Rejects if the password is longer than 72 bytes or contains a NUL byte.
+#[napi(ts_return_type = "Promise<boolean>")]
+pub fn compare(data: Either<String, Buffer>, encrypted: String) -> AsyncTask<Compare> {
+	AsyncTask::new(Compare { password: data.into(), hash_string: encrypted })
+}
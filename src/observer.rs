@@ -0,0 +1,82 @@
+use core::time::Duration;
+
+use super::{BcryptError, HASH_SIZE, Salt, WorkFactor, bcrypt_runtime};
+
+/// The counter incremented for each successful hash.
+pub const HASHES: &str = "bcrypt_hashes";
+
+/// The counter incremented for each key rejected before hashing.
+pub const ERRORS: &str = "bcrypt_errors";
+
+/// The measurement observed for the duration of each successful hash. Only reported with the `std` feature, since there’s no clock without it.
+pub const HASH_DURATION: &str = "bcrypt_hash_duration";
+
+/// The gauge set to the number of verifications waiting in a `VerifierPool`’s queue.
+pub const POOL_QUEUE_DEPTH: &str = "bcrypt_pool_queue_depth";
+
+/// The gauge set to the number of verifications a `VerifierPool` is running.
+pub const POOL_IN_FLIGHT: &str = "bcrypt_pool_in_flight";
+
+/// The measurement observed for the time each verification spent queued in a `VerifierPool`.
+pub const POOL_WAIT_DURATION: &str = "bcrypt_pool_wait_duration";
+
+/// The measurement observed for the time each verification took to run in a `VerifierPool`.
+pub const POOL_SERVICE_DURATION: &str = "bcrypt_pool_service_duration";
+
+/// Receives metrics from the high-level APIs. Every method does nothing by default, so an implementation only needs to provide the ones it reports. Durations are reported one at a time; percentiles are up to the implementation’s histograms.
+pub trait Observer {
+	/// Increments the counter called `name` by one.
+	fn increment(&self, name: &'static str) {
+		let _ = name;
+	}
+
+	/// Records one `duration` for the measurement called `name`.
+	fn observe(&self, name: &'static str, duration: Duration) {
+		let _ = (name, duration);
+	}
+
+	/// Sets the gauge called `name` to `value`.
+	fn gauge(&self, name: &'static str, value: usize) {
+		let _ = (name, value);
+	}
+}
+
+/// An observer that discards everything.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoopObserver;
+
+impl Observer for NoopObserver {}
+
+impl<T: Observer + ?Sized> Observer for &T {
+	fn increment(&self, name: &'static str) {
+		(**self).increment(name);
+	}
+
+	fn observe(&self, name: &'static str, duration: Duration) {
+		(**self).observe(name, duration);
+	}
+
+	fn gauge(&self, name: &'static str, value: usize) {
+		(**self).gauge(name, value);
+	}
+}
+
+/// [`bcrypt`](crate::bcrypt), reporting to an observer.
+pub fn bcrypt_observed<O: Observer>(key: &[u8], salt: &Salt, work_factor: WorkFactor, observer: &O) -> Result<[u8; HASH_SIZE], BcryptError> {
+	#[cfg(feature = "std")]
+	let start = std::time::Instant::now();
+
+	let result = bcrypt_runtime(key, salt, work_factor);
+
+	match result {
+		Ok(_) => {
+			observer.increment(HASHES);
+
+			#[cfg(feature = "std")]
+			observer.observe(HASH_DURATION, start.elapsed());
+		}
+		Err(_) => observer.increment(ERRORS),
+	}
+
+	result
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::BcryptError;
+use super::clock::Clock;
+use super::mcf::{self, HashString};
+
+/// Pads verifications to a fixed duration, so how long a response takes doesn’t tell a client which path it took: a hash string that failed to parse, a dummy hash for a user who doesn’t exist, or a real verification.
+///
+/// Each call is padded to `minimum` or to the longest any call has taken so far, whichever is longer, so a minimum set below the real hash time is raised to it as soon as a real hash has been timed. The padding never goes past `maximum`, so one call stalled by a busy machine can’t slow every call after it by as much; calls that take longer than `maximum` aren’t padded at all.
+#[derive(Debug)]
+pub struct VerifyPadding<C> {
+	clock: C,
+	minimum: Duration,
+	maximum: Duration,
+	longest: Mutex<Duration>,
+}
+
+impl<C: Clock> VerifyPadding<C> {
+	/// Creates padding to at least `minimum` and at most `maximum`, sleeping on `clock`. A `maximum` below `minimum` is raised to it.
+	pub fn new(clock: C, minimum: Duration, maximum: Duration) -> Self {
+		Self {
+			clock,
+			minimum,
+			maximum: maximum.max(minimum),
+			longest: Mutex::new(Duration::ZERO),
+		}
+	}
+
+	/// The duration calls are currently padded to.
+	pub fn padded_duration(&self) -> Duration {
+		self.padded(*self.longest.lock().unwrap())
+	}
+
+	fn padded(&self, longest: Duration) -> Duration {
+		longest.clamp(self.minimum, self.maximum)
+	}
+
+	/// Runs `f` – the whole of a verification, including parsing and any dummy hash – then sleeps until the padded duration has passed since it started.
+	pub fn run<T, F: FnOnce() -> T>(&self, f: F) -> T {
+		let start = self.clock.now();
+		let result = f();
+		let elapsed = self.clock.now().saturating_sub(start);
+
+		let padded = {
+			let mut longest = self.longest.lock().unwrap();
+			*longest = (*longest).max(elapsed).min(self.maximum);
+			self.padded(*longest)
+		};
+
+		self.clock.sleep(padded.saturating_sub(elapsed));
+		result
+	}
+
+	/// [`mcf::verify`], padded.
+	pub fn verify(&self, key: &[u8], hash_string: &HashString) -> Result<bool, BcryptError> {
+		self.run(|| mcf::verify(key, hash_string))
+	}
+}
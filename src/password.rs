@@ -0,0 +1,307 @@
+//! Reading passwords from standard input, a terminal, or a file descriptor, for daemons and command-line tools that would otherwise each write their own.
+
+use core::fmt;
+use core::hint::black_box;
+use core::ops::Deref;
+use std::ffi::OsStr;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+#[cfg(any(unix, windows))]
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::vec::Vec;
+
+use super::key_policy::strip_newline;
+
+/// The most bytes a password read by [`read_password`] can have: far more than bcrypt accepts, so callers can report an overlong password as such, but few enough that a stream with no line breaks can’t make it allocate without limit.
+pub const PASSWORD_SIZE_MAX: usize = 1024;
+
+/// A password, zeroed when it’s dropped.
+///
+/// Only this buffer is zeroed: copies made on the way in, like standard input’s own buffer, aren’t.
+pub struct Password(Vec<u8>);
+
+impl Password {
+	/// The bytes of an OS string – a command-line argument, an environment variable – as a password, converted the same way on every run so hashes stay verifiable:
+	///
+	/// - On Unix, the string’s bytes, exactly as the OS passed them.
+	/// - On Windows, the string’s UTF-16 encoded as [WTF-8](https://simonsapin.github.io/wtf-8/): UTF-8 for valid Unicode, with any unpaired surrogate encoded as three bytes like other code points of its size, rather than replaced by U+FFFD.
+	/// - Elsewhere, UTF-8, or `None` if the string isn’t valid Unicode.
+	///
+	/// Every valid Unicode string becomes its UTF-8 on every platform.
+	pub fn from_os_str(s: &OsStr) -> Option<Self> {
+		#[cfg(unix)]
+		{
+			use std::os::unix::ffi::OsStrExt;
+			Some(Self(s.as_bytes().to_vec()))
+		}
+
+		#[cfg(windows)]
+		{
+			use std::os::windows::ffi::OsStrExt;
+			Some(encode_wtf8(s.encode_wide()))
+		}
+
+		#[cfg(not(any(unix, windows)))]
+		{
+			s.to_str().map(|s| Self(s.as_bytes().to_vec()))
+		}
+	}
+}
+
+/// Encodes UTF-16 that may contain unpaired surrogates as WTF-8.
+#[cfg(any(windows, test))]
+pub fn encode_wtf8<I: IntoIterator<Item = u16>>(units: I) -> Password {
+	let units = units.into_iter();
+	let mut password = Password(Vec::with_capacity(units.size_hint().0 * 3));
+
+	for c in char::decode_utf16(units) {
+		match c {
+			Ok(c) => password.0.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+			Err(err) => {
+				let u = err.unpaired_surrogate();
+				password.0.extend_from_slice(&[0xe0 | (u >> 12) as u8, 0x80 | (u >> 6 & 0x3f) as u8, 0x80 | (u & 0x3f) as u8]);
+			}
+		}
+	}
+
+	password
+}
+
+impl From<Vec<u8>> for Password {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+}
+
+impl Deref for Password {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+impl Drop for Password {
+	fn drop(&mut self) {
+		self.0.fill(0);
+		black_box(&mut self.0);
+	}
+}
+
+impl fmt::Debug for Password {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Password { .. }")
+	}
+}
+
+/// Reads a password from one line of `input`, without the line ending. To read from a file descriptor, wrap it in a [`BufReader`](std::io::BufReader).
+///
+/// Fails with [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) if `input` is already at its end, and with [`InvalidData`](io::ErrorKind::InvalidData) if the line is longer than [`PASSWORD_SIZE_MAX`], without reading past it.
+pub fn read_password<R: BufRead>(input: &mut R) -> io::Result<Password> {
+	// Room for the longest password and a line ending, so reading one doesn’t reallocate and leave a copy behind.
+	let limit = PASSWORD_SIZE_MAX + 2;
+	let mut line = Password(Vec::with_capacity(limit));
+
+	if input.take(limit as u64).read_until(b'\n', &mut line.0)? == 0 {
+		return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no password given"));
+	}
+
+	let len = strip_newline(&line).len();
+	line.0.truncate(len);
+
+	if line.len() > PASSWORD_SIZE_MAX {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, "password too long"));
+	}
+
+	Ok(line)
+}
+
+/// Writes `message` to standard error and reads a password with [`read_password`], with the terminal’s echo turned off while it’s typed.
+///
+/// Fails with [`Unsupported`](io::ErrorKind::Unsupported) on platforms other than Unix and Windows, where echo can’t be turned off. Signals are left to the program: one that’s interrupted with Ctrl-C while the password is typed leaves echo off, unless its own handler restores the terminal’s settings.
+pub fn prompt_password<R: BufRead>(message: &str, input: &mut R) -> io::Result<Password> {
+	prompt_password_to(message, input, &mut io::stderr())
+}
+
+/// Like [`prompt_password`], but writes `message` to `prompts` instead of standard error.
+pub fn prompt_password_to<R: BufRead, W: Write>(message: &str, input: &mut R, prompts: &mut W) -> io::Result<Password> {
+	let echo = EchoOff::new()?;
+
+	prompts.write_all(message.as_bytes())?;
+	prompts.flush()?;
+
+	let password = read_password(input);
+
+	if echo.is_some() {
+		// Elsewhere, there’s never an `EchoOff` to drop.
+		#[cfg_attr(not(any(unix, windows)), allow(clippy::drop_non_drop))]
+		drop(echo);
+		prompts.write_all(b"\n")?;
+	}
+
+	password
+}
+
+/// Reads a password from standard input: with [`prompt_password`] if it’s a terminal, and otherwise as its next line.
+pub fn read_password_stdin(message: &str) -> io::Result<Password> {
+	let stdin = io::stdin();
+
+	if stdin.is_terminal() {
+		prompt_password(message, &mut stdin.lock())
+	} else {
+		read_password(&mut stdin.lock())
+	}
+}
+
+/// Terminal echo turned off on standard input until it’s dropped, or `None` if standard input isn’t a terminal.
+#[cfg(unix)]
+struct EchoOff {
+	saved: termios::Termios,
+	_lock: MutexGuard<'static, ()>,
+}
+
+/// Held while echo is off, so two threads prompting at once can’t restore each other’s settings out of order.
+#[cfg(any(unix, windows))]
+static ECHO_OFF: Mutex<()> = Mutex::new(());
+
+#[cfg(unix)]
+#[allow(unsafe_code)]
+mod termios {
+	use core::ffi::c_int;
+
+	/// `tcflag_t`, which is `unsigned long` on Apple platforms and `unsigned int` elsewhere.
+	#[cfg(target_vendor = "apple")]
+	type Tcflag = core::ffi::c_ulong;
+	#[cfg(not(target_vendor = "apple"))]
+	type Tcflag = core::ffi::c_uint;
+
+	pub const TCSANOW: c_int = 0;
+
+	/// The `ECHO` bit of `c_lflag`, the same on every Unix.
+	pub const ECHO: Tcflag = 0o10;
+
+	/// `struct termios`: the four flag fields every platform starts it with, in the same order, then the rest, in more room than any platform needs.
+	#[derive(Clone, Copy)]
+	#[repr(C)]
+	pub struct Termios {
+		pub iflag: Tcflag,
+		pub oflag: Tcflag,
+		pub cflag: Tcflag,
+		pub lflag: Tcflag,
+		rest: [u64; 32],
+	}
+
+	impl Termios {
+		pub const fn zeroed() -> Self {
+			Self { iflag: 0, oflag: 0, cflag: 0, lflag: 0, rest: [0; 32] }
+		}
+	}
+
+	extern "C" {
+		pub fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+		pub fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+	}
+}
+
+#[cfg(unix)]
+#[allow(unsafe_code)]
+impl EchoOff {
+	fn new() -> io::Result<Option<Self>> {
+		let lock = ECHO_OFF.lock().unwrap_or_else(PoisonError::into_inner);
+		let mut saved = termios::Termios::zeroed();
+
+		// SAFETY: `Termios` is at least as big as the platform’s `struct termios`, with the flags where it keeps them.
+		if unsafe { termios::tcgetattr(0, &mut saved) } != 0 {
+			return Ok(None);
+		}
+
+		let mut quiet = saved;
+		quiet.lflag &= !termios::ECHO;
+
+		// SAFETY: as above, and `quiet` holds settings `tcgetattr` returned, with only echo changed.
+		if unsafe { termios::tcsetattr(0, termios::TCSANOW, &quiet) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(Some(Self { saved, _lock: lock }))
+	}
+}
+
+#[cfg(unix)]
+#[allow(unsafe_code)]
+impl Drop for EchoOff {
+	fn drop(&mut self) {
+		// SAFETY: the settings were saved by `tcgetattr` in `new`.
+		unsafe {
+			termios::tcsetattr(0, termios::TCSANOW, &self.saved);
+		}
+	}
+}
+
+/// Console echo turned off on standard input until it’s dropped, or `None` if standard input isn’t a console.
+#[cfg(windows)]
+struct EchoOff {
+	handle: *mut core::ffi::c_void,
+	mode: u32,
+	_lock: MutexGuard<'static, ()>,
+}
+
+#[cfg(windows)]
+#[allow(unsafe_code)]
+mod console {
+	use core::ffi::c_void;
+
+	pub const STD_INPUT_HANDLE: u32 = -10_i32 as u32;
+	pub const ENABLE_ECHO_INPUT: u32 = 0x0004;
+
+	#[link(name = "kernel32")]
+	extern "system" {
+		pub fn GetStdHandle(std_handle: u32) -> *mut c_void;
+		pub fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+		pub fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+	}
+}
+
+#[cfg(windows)]
+#[allow(unsafe_code)]
+impl EchoOff {
+	fn new() -> io::Result<Option<Self>> {
+		let lock = ECHO_OFF.lock().unwrap_or_else(PoisonError::into_inner);
+		let mut mode = 0;
+
+		// SAFETY: `GetStdHandle` takes no pointers.
+		let handle = unsafe { console::GetStdHandle(console::STD_INPUT_HANDLE) };
+
+		// SAFETY: `mode` is valid for writes.
+		if unsafe { console::GetConsoleMode(handle, &mut mode) } == 0 {
+			return Ok(None);
+		}
+
+		// SAFETY: the handle came from `GetStdHandle`.
+		if unsafe { console::SetConsoleMode(handle, mode & !console::ENABLE_ECHO_INPUT) } == 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok(Some(Self { handle, mode, _lock: lock }))
+	}
+}
+
+#[cfg(windows)]
+#[allow(unsafe_code)]
+impl Drop for EchoOff {
+	fn drop(&mut self) {
+		// SAFETY: the handle came from `GetStdHandle`.
+		unsafe {
+			console::SetConsoleMode(self.handle, self.mode);
+		}
+	}
+}
+
+#[cfg(not(any(unix, windows)))]
+struct EchoOff;
+
+#[cfg(not(any(unix, windows)))]
+impl EchoOff {
+	fn new() -> io::Result<Option<Self>> {
+		Err(io::Error::new(io::ErrorKind::Unsupported, "can’t turn off terminal echo on this platform"))
+	}
+}
@@ -0,0 +1,150 @@
+//! `bcrypt_pbkdf`, the key derivation function OpenSSH uses to encrypt private keys with a passphrase: PBKDF2 with SHA-512 in place of HMAC and a bcrypt-like hash in place of the pseudorandom function, as in OpenBSD’s `bcrypt_pbkdf.c`.
+
+use core::fmt;
+use core::hint::black_box;
+
+use super::{BLF_N, BlowfishContext, blowfish_encipher, blowfish_expandstate_data, blowfish_expandstate_data0, runtime_initial_state};
+use super::sha512::{DIGEST_SIZE, Sha512, sha512};
+
+/// The message `bcrypt_pbkdf`’s hash enciphers, in place of bcrypt’s `OrpheanBeholderScryDoubt`.
+const PBKDF_MESSAGE: [u8; 32] = *b"OxychromaticBlowfishSwatDynamite";
+
+/// The size of each block of output `bcrypt_pbkdf`’s hash produces.
+const PBKDF_HASH_SIZE: usize = PBKDF_MESSAGE.len();
+
+/// The most bytes [`bcrypt_pbkdf`] can derive.
+pub const PBKDF_OUTPUT_SIZE_MAX: usize = PBKDF_HASH_SIZE * PBKDF_HASH_SIZE;
+
+/// An error from [`bcrypt_pbkdf`]: zero rounds, an empty passphrase or salt, a salt longer than 1 MiB, or no output or more than [`PBKDF_OUTPUT_SIZE_MAX`] bytes of it – the parameters OpenBSD’s implementation rejects.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InvalidPbkdfParameters;
+
+impl fmt::Display for InvalidPbkdfParameters {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("invalid bcrypt_pbkdf parameters")
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidPbkdfParameters {}
+
+/// Reads a SHA-512 digest as big-endian words.
+fn digest_words(digest: &[u8; DIGEST_SIZE]) -> [u32; DIGEST_SIZE / 4] {
+	let mut words = [0_u32; DIGEST_SIZE / 4];
+
+	for (word, chunk) in words.iter_mut().zip(digest.chunks_exact(4)) {
+		*word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+	}
+
+	words
+}
+
+/// Expands the subkeys with a digest and no data, as Blowfish’s key schedule does with a key.
+fn expand0(state: &mut BlowfishContext, key: &[u32; DIGEST_SIZE / 4]) {
+	for i in 0..BLF_N + 2 {
+		state.p[i] ^= key[i % key.len()];
+	}
+
+	blowfish_expandstate_data0(state);
+}
+
+/// The hash at the core of `bcrypt_pbkdf`: bcrypt’s expensive key setup at a fixed 64 rounds, on the digests of the passphrase and salt, then its message enciphered 64 times. The words come out little-endian.
+fn pbkdf_hash(sha2pass: &[u8; DIGEST_SIZE], sha2salt: &[u8; DIGEST_SIZE]) -> [u8; PBKDF_HASH_SIZE] {
+	let mut pass = digest_words(sha2pass);
+	let mut salt = digest_words(sha2salt);
+
+	let mut state = runtime_initial_state();
+
+	for i in 0..BLF_N + 2 {
+		state.p[i] ^= pass[i % pass.len()];
+	}
+
+	blowfish_expandstate_data(&mut state, &salt);
+
+	for _ in 0..64 {
+		expand0(&mut state, &salt);
+		expand0(&mut state, &pass);
+	}
+
+	let mut cdata = [0_u32; PBKDF_HASH_SIZE / 4];
+
+	for (word, chunk) in cdata.iter_mut().zip(PBKDF_MESSAGE.chunks_exact(4)) {
+		*word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+	}
+
+	for _ in 0..64 {
+		for pair in cdata.chunks_exact_mut(2) {
+			let (l, r) = blowfish_encipher(&state, pair[0], pair[1]);
+			pair[0] = l;
+			pair[1] = r;
+		}
+	}
+
+	let mut out = [0_u8; PBKDF_HASH_SIZE];
+
+	for (chunk, word) in out.chunks_exact_mut(4).zip(&cdata) {
+		chunk.copy_from_slice(&word.to_le_bytes());
+	}
+
+	state.zero();
+	pass.fill(0);
+	black_box(&mut pass);
+	salt.fill(0);
+	black_box(&mut salt);
+	cdata.fill(0);
+	black_box(&mut cdata);
+
+	out
+}
+
+/// Derives `output.len()` bytes from a passphrase and salt with `bcrypt_pbkdf`, matching OpenBSD’s implementation. OpenSSH stores the salt and rounds – 16 by default – in the private key, and derives the cipher’s key and IV together as one output.
+///
+/// Unlike PBKDF2, the output blocks are interleaved: byte `i` of block `b` goes to `output[i * stride + b]`, so all of the output depends on every round.
+pub fn bcrypt_pbkdf(pass: &[u8], salt: &[u8], rounds: u32, output: &mut [u8]) -> Result<(), InvalidPbkdfParameters> {
+	if rounds == 0 || pass.is_empty() || salt.is_empty() || salt.len() > 1 << 20 || output.is_empty() || output.len() > PBKDF_OUTPUT_SIZE_MAX {
+		return Err(InvalidPbkdfParameters);
+	}
+
+	let stride = output.len().div_ceil(PBKDF_HASH_SIZE);
+	let amount = output.len().div_ceil(stride);
+
+	let mut sha2pass = sha512(&[pass]);
+
+	for block in 0..stride {
+		let count = (block as u32 + 1).to_be_bytes();
+		let mut hasher = Sha512::new();
+		hasher.update(salt);
+		hasher.update(&count);
+		let mut sha2salt = hasher.finish();
+
+		let mut tmpout = pbkdf_hash(&sha2pass, &sha2salt);
+		let mut out = tmpout;
+
+		for _ in 1..rounds {
+			sha2salt = sha512(&[&tmpout]);
+			tmpout = pbkdf_hash(&sha2pass, &sha2salt);
+
+			for (o, t) in out.iter_mut().zip(&tmpout) {
+				*o ^= t;
+			}
+		}
+
+		for (i, &b) in out[..amount].iter().enumerate() {
+			if let Some(dest) = output.get_mut(i * stride + block) {
+				*dest = b;
+			}
+		}
+
+		sha2salt.fill(0);
+		black_box(&mut sha2salt);
+		tmpout.fill(0);
+		black_box(&mut tmpout);
+		out.fill(0);
+		black_box(&mut out);
+	}
+
+	sha2pass.fill(0);
+	black_box(&mut sha2pass);
+
+	Ok(())
+}
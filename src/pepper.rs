@@ -0,0 +1,93 @@
+//! Loading a pepper – a secret mixed into hashes, like the one [`hash_api_token`](crate::preset::hash_api_token) takes – from somewhere safer than an environment variable, which child processes inherit and crash reporters collect.
+
+use core::fmt;
+use core::hint::black_box;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::vec::Vec;
+
+use super::key_policy::strip_newline;
+
+/// A pepper, zeroed when dropped.
+pub struct Pepper {
+	bytes: Vec<u8>,
+}
+
+impl Pepper {
+	/// Takes ownership of pepper bytes.
+	pub fn new(bytes: Vec<u8>) -> Self {
+		Self { bytes }
+	}
+
+	/// The pepper.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes
+	}
+}
+
+impl Drop for Pepper {
+	fn drop(&mut self) {
+		self.bytes.fill(0);
+		black_box(&mut self.bytes);
+	}
+}
+
+impl fmt::Debug for Pepper {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("Pepper { .. }")
+	}
+}
+
+/// Somewhere a pepper is stored. Implement it for a platform keychain or credential manager, or a secrets service, to load the pepper from there; [`SealedFile`] is the implementation that needs no platform support.
+pub trait PepperSource {
+	/// Loads the pepper.
+	fn load_pepper(&self) -> io::Result<Pepper>;
+}
+
+impl<T: PepperSource + ?Sized> PepperSource for &T {
+	fn load_pepper(&self) -> io::Result<Pepper> {
+		(**self).load_pepper()
+	}
+}
+
+/// A pepper stored in a file only its owner can read, like one a secrets manager mounts into a container. A single line ending at the end of the file is ignored.
+#[derive(Clone, Debug)]
+pub struct SealedFile {
+	path: PathBuf,
+}
+
+impl SealedFile {
+	/// A pepper stored at `path`.
+	pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+		Self { path: path.into() }
+	}
+}
+
+impl PepperSource for SealedFile {
+	/// Loads the pepper, failing if the file is empty or, on Unix, if its group or other users have any access to it – a pepper anyone else can read isn’t secret.
+	fn load_pepper(&self) -> io::Result<Pepper> {
+		let mut file = File::open(&self.path)?;
+
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+
+			if file.metadata()?.permissions().mode() & 0o077 != 0 {
+				return Err(io::Error::new(io::ErrorKind::PermissionDenied, std::format!("{} is accessible to other users", self.path.display())));
+			}
+		}
+
+		let mut pepper = Pepper::new(Vec::new());
+		file.read_to_end(&mut pepper.bytes)?;
+
+		let len = strip_newline(&pepper.bytes).len();
+		pepper.bytes.truncate(len);
+
+		if pepper.bytes.is_empty() {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, std::format!("{} is empty", self.path.display())));
+		}
+
+		Ok(pepper)
+	}
+}
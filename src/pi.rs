@@ -0,0 +1,94 @@
+/// The number of 32-bit words of π’s fractional part that make up the Blowfish tables.
+const TABLE_WORDS: usize = 18 + 4 * 256;
+
+/// Extra words of precision below the last table word, absorbing the rounding error of every truncated division.
+const GUARD_WORDS: usize = 2;
+
+/// A fixed-point number with one word of integer part, big-endian.
+const WORDS: usize = 1 + TABLE_WORDS + GUARD_WORDS;
+
+/// Divides `x` by `d` in place, given that the words of `x` before `start` are zero.
+const fn div_small(x: &mut [u32; WORDS], d: u32, start: usize) {
+	let mut remainder = 0_u64;
+	let mut i = start;
+
+	while i < WORDS {
+		let n = remainder << 32 | x[i] as u64;
+		x[i] = (n / d as u64) as u32;
+		remainder = n % d as u64;
+		i += 1;
+	}
+}
+
+/// Adds `m * x` to `sum`, or subtracts it if `negative`, given that the words of `x` before `start` are zero.
+const fn add_multiple(sum: &mut [u32; WORDS], x: &[u32; WORDS], m: u32, negative: bool, start: usize) {
+	let mut carry = 0_u64;
+	let mut i = WORDS;
+
+	while i > 0 && (i > start || carry != 0) {
+		i -= 1;
+
+		let product = x[i] as u64 * m as u64 + carry;
+		let (word, overflow) = if negative {
+			sum[i].overflowing_sub(product as u32)
+		} else {
+			sum[i].overflowing_add(product as u32)
+		};
+
+		sum[i] = word;
+		carry = (product >> 32) + overflow as u64;
+	}
+}
+
+/// Adds `m * arctan(1/n)` to `sum`, or subtracts it if `negative`, using the Taylor series 1/n − 1/(3n³) + 1/(5n⁵) − ….
+const fn add_arctan_inv(sum: &mut [u32; WORDS], m: u32, n: u32, negative: bool) {
+	// x = 1/n^(2k+1)
+	let mut x = [0_u32; WORDS];
+	x[0] = 1;
+	div_small(&mut x, n, 0);
+
+	// the index of the first nonzero word of x
+	let mut start = 1;
+	let mut k = 0_u32;
+
+	while start < WORDS {
+		let mut term = x;
+		div_small(&mut term, 2 * k + 1, start);
+		add_multiple(sum, &term, m, negative != (k % 2 == 1), start);
+
+		div_small(&mut x, n * n, start);
+
+		while start < WORDS && x[start] == 0 {
+			start += 1;
+		}
+
+		k += 1;
+	}
+}
+
+/// Generates the initial Blowfish P-array and S-boxes – the first 8336 hexadecimal digits of π’s fractional part – from scratch, using Machin’s formula π = 16 arctan(1/5) − 4 arctan(1/239).
+///
+/// This gives an independent check of the tables bcrypt embeds, and is what the `runtime-tables` feature uses instead of embedding them. It takes around 13 KiB of stack and about ten million word operations.
+pub const fn pi_tables() -> ([u32; 18], [[u32; 256]; 4]) {
+	let mut pi = [0_u32; WORDS];
+	add_arctan_inv(&mut pi, 16, 5, false);
+	add_arctan_inv(&mut pi, 4, 239, true);
+
+	let mut p = [0_u32; 18];
+	let mut s = [[0_u32; 256]; 4];
+	let mut i = 0;
+
+	while i < 18 {
+		p[i] = pi[1 + i];
+		i += 1;
+	}
+
+	let mut i = 0;
+
+	while i < 4 * 256 {
+		s[i / 256][i % 256] = pi[1 + 18 + i];
+		i += 1;
+	}
+
+	(p, s)
+}
@@ -0,0 +1,83 @@
+//! Password policies checked before hashing, so enforcing them and hashing happen at one boundary – and a rejected password never costs a hash.
+
+use core::fmt;
+
+use super::{BcryptError, Salt, WorkFactor};
+use super::mcf::{self, HashString};
+
+/// A check a password has to pass before it’s hashed: a minimum length, a breach list lookup, a strength estimate.
+pub trait PasswordPolicy {
+	/// Why a password was rejected.
+	type Rejection;
+
+	/// Checks a password.
+	fn check(&self, key: &[u8]) -> Result<(), Self::Rejection>;
+}
+
+/// Any function from a password to a result is a policy.
+impl<F: Fn(&[u8]) -> Result<(), R>, R> PasswordPolicy for F {
+	type Rejection = R;
+
+	fn check(&self, key: &[u8]) -> Result<(), R> {
+		self(key)
+	}
+}
+
+/// A policy requiring at least this many bytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct MinimumLength(pub usize);
+
+/// The rejection from [`MinimumLength`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TooShort {
+	pub minimum: usize,
+}
+
+impl fmt::Display for TooShort {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "password shorter than {} bytes", self.minimum)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TooShort {}
+
+impl PasswordPolicy for MinimumLength {
+	type Rejection = TooShort;
+
+	fn check(&self, key: &[u8]) -> Result<(), TooShort> {
+		if key.len() < self.0 {
+			Err(TooShort { minimum: self.0 })
+		} else {
+			Ok(())
+		}
+	}
+}
+
+/// An error from [`hash_with_policy`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PolicyError<R> {
+	/// The policy rejected the password.
+	Rejected(R),
+
+	/// The policy accepted the password, but bcrypt can’t hash it.
+	Bcrypt(BcryptError),
+}
+
+impl<R: fmt::Display> fmt::Display for PolicyError<R> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PolicyError::Rejected(rejection) => rejection.fmt(f),
+			PolicyError::Bcrypt(err) => err.fmt(f),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<R: fmt::Debug + fmt::Display> std::error::Error for PolicyError<R> {}
+
+/// [`mcf::hash`], if `policy` accepts the password.
+pub fn hash_with_policy<P: PasswordPolicy + ?Sized>(policy: &P, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<HashString, PolicyError<P::Rejection>> {
+	policy.check(key).map_err(PolicyError::Rejected)?;
+	mcf::hash(key, salt, work_factor).map_err(PolicyError::Bcrypt)
+}
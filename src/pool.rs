@@ -0,0 +1,259 @@
+use core::fmt;
+use core::hint::black_box;
+use core::num::NonZeroUsize;
+use std::boxed::Box;
+use std::io;
+use std::sync::{Arc, Mutex, mpsc};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use std::vec::Vec;
+
+use super::{BcryptError, KEY_SIZE_MAX, check_key};
+use super::mcf::{self, HashString};
+use super::observer::{NoopObserver, Observer, POOL_IN_FLIGHT, POOL_QUEUE_DEPTH, POOL_SERVICE_DURATION, POOL_WAIT_DURATION};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// How a [`VerifierPool`]’s threads are set up.
+#[derive(Clone, Debug)]
+pub struct PoolOptions {
+	/// The number of worker threads.
+	pub threads: NonZeroUsize,
+
+	/// A niceness to give each worker thread, from -20 (highest priority) to 19 (lowest). Only supported on Linux and Android, where niceness is per-thread; raising priority needs privileges.
+	pub niceness: Option<i32>,
+
+	/// CPU cores to pin the workers to, assigned round-robin. Only supported on Linux and Android.
+	pub cores: Vec<usize>,
+}
+
+impl PoolOptions {
+	/// Options for `threads` workers at the default priority, unpinned.
+	pub fn new(threads: NonZeroUsize) -> Self {
+		Self {
+			threads,
+			niceness: None,
+			cores: Vec::new(),
+		}
+	}
+}
+
+/// A fixed set of threads that run verifications, so the number of hashes running at once – and the CPU they take from everything else on the host – is bounded.
+///
+/// Lowering the workers’ priority with [`PoolOptions::niceness`], or pinning them to some cores with [`PoolOptions::cores`], keeps a background job like a rehash campaign from competing with latency-sensitive threads.
+///
+/// A pool created with [`with_observer`](Self::with_observer) reports its queue depth and in-flight count as gauges, and each verification’s time queued and time running as measurements, under the `POOL_` names in [`observer`](crate::observer).
+pub struct VerifierPool {
+	sender: Option<mpsc::Sender<Job>>,
+	workers: Vec<JoinHandle<()>>,
+	stats: Arc<Stats>,
+}
+
+struct Stats {
+	observer: Box<dyn Observer + Send + Sync>,
+	queued: AtomicUsize,
+	in_flight: AtomicUsize,
+}
+
+impl Stats {
+	/// Adds `change` to a gauge, wrapping so that adding `usize::MAX` subtracts 1, and reports the new value.
+	fn update(&self, gauge: &AtomicUsize, name: &'static str, change: usize) {
+		let value = gauge.fetch_add(change, Ordering::Relaxed).wrapping_add(change);
+		self.observer.gauge(name, value);
+	}
+}
+
+/// A verification submitted to a [`VerifierPool`].
+#[derive(Debug)]
+#[must_use]
+pub struct PendingVerify {
+	receiver: mpsc::Receiver<Result<bool, BcryptError>>,
+}
+
+impl PendingVerify {
+	/// Blocks until the verification is done.
+	pub fn wait(self) -> Result<bool, BcryptError> {
+		self.receiver.recv().expect("verifier pool worker panicked")
+	}
+}
+
+impl VerifierPool {
+	/// Starts the worker threads. Fails if a thread can’t be started or set up as requested.
+	pub fn new(options: PoolOptions) -> io::Result<Self> {
+		Self::with_observer(options, NoopObserver)
+	}
+
+	/// [`new`](Self::new), reporting metrics to `observer`.
+	pub fn with_observer<O: Observer + Send + Sync + 'static>(options: PoolOptions, observer: O) -> io::Result<Self> {
+		let (sender, receiver) = mpsc::channel::<Job>();
+		let receiver = Arc::new(Mutex::new(receiver));
+		let (setup_sender, setup_receiver) = mpsc::channel();
+		let mut pool = Self {
+			sender: Some(sender),
+			workers: Vec::with_capacity(options.threads.get()),
+			stats: Arc::new(Stats {
+				observer: Box::new(observer),
+				queued: AtomicUsize::new(0),
+				in_flight: AtomicUsize::new(0),
+			}),
+		};
+
+		for i in 0..options.threads.get() {
+			let receiver = Arc::clone(&receiver);
+			let setup_sender = setup_sender.clone();
+			let niceness = options.niceness;
+			let cpu = if options.cores.is_empty() { None } else { Some(options.cores[i % options.cores.len()]) };
+
+			let worker = thread::Builder::new()
+				.name(std::format!("bcrypt-verifier-{i}"))
+				.spawn(move || {
+					let setup = set_up_thread(niceness, cpu);
+					let ok = setup.is_ok();
+					let _ = setup_sender.send(setup);
+
+					if ok {
+						work(&receiver);
+					}
+				})?;
+
+			pool.workers.push(worker);
+		}
+
+		for _ in 0..options.threads.get() {
+			setup_receiver.recv().expect("verifier pool worker panicked")?;
+		}
+
+		Ok(pool)
+	}
+
+	/// Queues a verification of `key` against `hash_string`. The key is copied, and the copy zeroed once it’s been hashed.
+	pub fn submit(&self, key: &[u8], hash_string: &HashString) -> PendingVerify {
+		let (result_sender, receiver) = mpsc::sync_channel(1);
+
+		if let Err(err) = check_key(key) {
+			let _ = result_sender.send(Err(err));
+			return PendingVerify { receiver };
+		}
+
+		let mut key_copy = [0_u8; KEY_SIZE_MAX];
+		let key_len = key.len();
+		key_copy[..key_len].copy_from_slice(key);
+		let hash_string = *hash_string;
+		let stats = Arc::clone(&self.stats);
+		let queued_at = Instant::now();
+
+		self.stats.update(&self.stats.queued, POOL_QUEUE_DEPTH, 1);
+
+		self.sender.as_ref().unwrap().send(Box::new(move || {
+			let started_at = Instant::now();
+			stats.update(&stats.queued, POOL_QUEUE_DEPTH, usize::MAX);
+			stats.update(&stats.in_flight, POOL_IN_FLIGHT, 1);
+			stats.observer.observe(POOL_WAIT_DURATION, started_at - queued_at);
+
+			let result = mcf::verify(&key_copy[..key_len], &hash_string);
+			key_copy.fill(0);
+			black_box(&mut key_copy);
+
+			stats.observer.observe(POOL_SERVICE_DURATION, started_at.elapsed());
+			stats.update(&stats.in_flight, POOL_IN_FLIGHT, usize::MAX);
+			let _ = result_sender.send(result);
+		})).expect("verifier pool workers exited");
+
+		PendingVerify { receiver }
+	}
+
+	/// Verifies `key` against `hash_string` on a worker thread, blocking until it’s done.
+	pub fn verify(&self, key: &[u8], hash_string: &HashString) -> Result<bool, BcryptError> {
+		self.submit(key, hash_string).wait()
+	}
+
+	/// The number of verifications waiting for a worker.
+	pub fn queue_depth(&self) -> usize {
+		self.stats.queued.load(Ordering::Relaxed)
+	}
+
+	/// The number of verifications running.
+	pub fn in_flight(&self) -> usize {
+		self.stats.in_flight.load(Ordering::Relaxed)
+	}
+}
+
+impl fmt::Debug for VerifierPool {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("VerifierPool")
+			.field("workers", &self.workers.len())
+			.field("queue_depth", &self.queue_depth())
+			.field("in_flight", &self.in_flight())
+			.finish_non_exhaustive()
+	}
+}
+
+impl Drop for VerifierPool {
+	/// Finishes the queued verifications, then stops the workers.
+	fn drop(&mut self) {
+		self.sender = None;
+
+		for worker in self.workers.drain(..) {
+			let _ = worker.join();
+		}
+	}
+}
+
+fn work(receiver: &Mutex<mpsc::Receiver<Job>>) {
+	loop {
+		let job = receiver.lock().unwrap().recv();
+
+		match job {
+			Ok(job) => job(),
+			Err(_) => break,
+		}
+	}
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[allow(unsafe_code)]
+fn set_up_thread(niceness: Option<i32>, cpu: Option<usize>) -> io::Result<()> {
+	use std::os::raw::{c_int, c_uint};
+
+	const PRIO_PROCESS: c_int = 0;
+	const CPU_SET_WORDS: usize = 16;
+
+	extern "C" {
+		fn setpriority(which: c_int, who: c_uint, prio: c_int) -> c_int;
+		fn sched_setaffinity(pid: c_int, cpusetsize: usize, mask: *const u64) -> c_int;
+	}
+
+	if let Some(niceness) = niceness {
+		// SAFETY: plain FFI call; on Linux, `who` 0 with `PRIO_PROCESS` means the calling thread
+		if unsafe { setpriority(PRIO_PROCESS, 0, niceness) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+	}
+
+	if let Some(cpu) = cpu {
+		let mut mask = [0_u64; CPU_SET_WORDS];
+
+		if cpu >= CPU_SET_WORDS * 64 {
+			return Err(io::Error::new(io::ErrorKind::InvalidInput, "CPU core number out of range"));
+		}
+
+		mask[cpu / 64] |= 1 << (cpu % 64);
+
+		// SAFETY: `mask` is a valid `cpu_set_t` of the given size, and pid 0 means the calling thread
+		if unsafe { sched_setaffinity(0, core::mem::size_of_val(&mask), mask.as_ptr()) } != 0 {
+			return Err(io::Error::last_os_error());
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn set_up_thread(niceness: Option<i32>, cpu: Option<usize>) -> io::Result<()> {
+	if niceness.is_some() || cpu.is_some() {
+		return Err(io::Error::new(io::ErrorKind::Unsupported, "thread priority and pinning are only supported on Linux"));
+	}
+
+	Ok(())
+}
@@ -0,0 +1,178 @@
+//! Ready-made combinations of normalization, policy, and work factor for secrets that aren’t passwords.
+
+use core::fmt;
+use core::hint::black_box;
+
+use super::{KEY_SIZE_MAX, Salt, WorkFactor, base64};
+use super::mcf::{self, HashString};
+use super::sha512::hmac;
+
+/// The work factor recommended for recovery codes. A generated code carries far more entropy than a typical password, so a cheaper hash protects it as well; this keeps an account with ten unused codes from costing ten full-price hashes per attempt.
+pub const RECOVERY_CODE_WORK_FACTOR: WorkFactor = WorkFactor::EXP8;
+
+/// An error normalizing a recovery code.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RecoveryCodeError {
+	/// The code had nothing left after removing separators.
+	Empty,
+
+	/// The code was longer than bcrypt accepts after removing separators.
+	TooLong,
+
+	/// The code contained a control character or a character outside ASCII. Generated codes never do, and folding look-alike characters from the rest of Unicode would need normalization tables this crate doesn’t carry, so they’re rejected rather than compared inconsistently.
+	InvalidCharacter,
+}
+
+impl fmt::Display for RecoveryCodeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			RecoveryCodeError::Empty => "recovery code is empty",
+			RecoveryCodeError::TooLong => "recovery code too long",
+			RecoveryCodeError::InvalidCharacter => "recovery code contains an invalid character",
+		})
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RecoveryCodeError {}
+
+/// A normalized recovery code, zeroed when dropped.
+pub struct NormalizedCode {
+	bytes: [u8; KEY_SIZE_MAX],
+	len: usize,
+}
+
+impl NormalizedCode {
+	/// The normalized code.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes[..self.len]
+	}
+}
+
+impl Drop for NormalizedCode {
+	fn drop(&mut self) {
+		self.bytes.fill(0);
+		black_box(&mut self.bytes);
+	}
+}
+
+impl fmt::Debug for NormalizedCode {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("NormalizedCode { .. }")
+	}
+}
+
+/// Normalizes a recovery code the way a user might type it: spaces, tabs, line breaks, and hyphens are removed, and letters are uppercased, so `abcd-efgh` and `ABCD EFGH` are the same code.
+pub fn normalize_recovery_code(code: &str) -> Result<NormalizedCode, RecoveryCodeError> {
+	let mut normalized = NormalizedCode {
+		bytes: [0; KEY_SIZE_MAX],
+		len: 0,
+	};
+
+	for b in code.bytes() {
+		if b.is_ascii_whitespace() || b == b'-' {
+			continue;
+		}
+
+		if !b.is_ascii() || b.is_ascii_control() {
+			return Err(RecoveryCodeError::InvalidCharacter);
+		}
+
+		if normalized.len == KEY_SIZE_MAX {
+			return Err(RecoveryCodeError::TooLong);
+		}
+
+		normalized.bytes[normalized.len] = b.to_ascii_uppercase();
+		normalized.len += 1;
+	}
+
+	if normalized.len == 0 {
+		return Err(RecoveryCodeError::Empty);
+	}
+
+	Ok(normalized)
+}
+
+// A normalized code is at most 72 bytes with no control characters, so bcrypt never rejects it.
+
+/// Normalizes and hashes a recovery code at [`RECOVERY_CODE_WORK_FACTOR`].
+pub fn hash_recovery_code(code: &str, salt: &Salt) -> Result<HashString, RecoveryCodeError> {
+	let normalized = normalize_recovery_code(code)?;
+	Ok(mcf::hash(normalized.as_bytes(), salt, RECOVERY_CODE_WORK_FACTOR).unwrap())
+}
+
+/// Normalizes a recovery code and checks whether it matches a hash string.
+pub fn verify_recovery_code(code: &str, hash_string: &HashString) -> Result<bool, RecoveryCodeError> {
+	let normalized = normalize_recovery_code(code)?;
+	Ok(mcf::verify(normalized.as_bytes(), hash_string).unwrap())
+}
+
+/// The work factor for API tokens. Tokens are random and peppered, so guessing one is hopeless at any work factor; the hash only has to stop a leaked database from being used directly, and a cheap one keeps verification fast enough for every request.
+pub const API_TOKEN_WORK_FACTOR: WorkFactor = WorkFactor::EXP6;
+
+/// The fewest bytes an API token can have: 128 bits of randomness in hexadecimal.
+pub const API_TOKEN_LENGTH_MIN: usize = 32;
+
+/// The most bytes an API token can have.
+pub const API_TOKEN_LENGTH_MAX: usize = 1024;
+
+/// The number of bytes of the peppered HMAC that are hashed: as many as fit in bcrypt’s key once encoded.
+const API_TOKEN_MAC_SIZE: usize = 48;
+
+/// An error from the API token preset.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ApiTokenError {
+	/// The token was shorter than [`API_TOKEN_LENGTH_MIN`].
+	TooShort,
+
+	/// The token was longer than [`API_TOKEN_LENGTH_MAX`].
+	TooLong,
+}
+
+impl fmt::Display for ApiTokenError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match self {
+			ApiTokenError::TooShort => "API token too short",
+			ApiTokenError::TooLong => "API token too long",
+		})
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ApiTokenError {}
+
+/// HMAC-SHA-512 of the token keyed by the pepper, encoded in bcrypt’s base64 so it’s a valid key of any token length.
+fn peppered_token(pepper: &[u8], token: &[u8]) -> Result<[u8; base64::encoded_len(API_TOKEN_MAC_SIZE)], ApiTokenError> {
+	if token.len() < API_TOKEN_LENGTH_MIN {
+		return Err(ApiTokenError::TooShort);
+	}
+
+	if token.len() > API_TOKEN_LENGTH_MAX {
+		return Err(ApiTokenError::TooLong);
+	}
+
+	let mut mac = hmac(pepper, &[b"bcrypt-only API token\0", token]);
+	let mut key = [0_u8; base64::encoded_len(API_TOKEN_MAC_SIZE)];
+	base64::encode(&mac[..API_TOKEN_MAC_SIZE], &mut key);
+	mac.fill(0);
+	black_box(&mut mac);
+	Ok(key)
+}
+
+/// Hashes an API token at [`API_TOKEN_WORK_FACTOR`], after mixing in a secret `pepper` kept outside the database, so the stored hashes are useless without it.
+pub fn hash_api_token(pepper: &[u8], token: &[u8], salt: &Salt) -> Result<HashString, ApiTokenError> {
+	let mut key = peppered_token(pepper, token)?;
+	let hash_string = mcf::hash(&key, salt, API_TOKEN_WORK_FACTOR).unwrap();
+	key.fill(0);
+	black_box(&mut key);
+	Ok(hash_string)
+}
+
+/// Checks whether an API token matches a hash string from [`hash_api_token`] with the same pepper.
+pub fn verify_api_token(pepper: &[u8], token: &[u8], hash_string: &HashString) -> Result<bool, ApiTokenError> {
+	let mut key = peppered_token(pepper, token)?;
+	let matches = mcf::verify(&key, hash_string).unwrap();
+	key.fill(0);
+	black_box(&mut key);
+	Ok(matches)
+}
@@ -0,0 +1,57 @@
+//! A Python module with the `bcrypt` package’s `gensalt`, `hashpw`, and `checkpw`, through PyO3. Build with `PYO3_BUILD_EXTENSION_MODULE=1 cargo rustc --lib --features python --release --crate-type cdylib`, and install the library as `bcrypt_only` with the platform’s extension module suffix.
+
+use core::str;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::string::ToString;
+
+use super::mcf::{self, HashString, SETTING_SIZE, Setting, Version};
+use super::rng::OsRng;
+use super::{BcryptError, HASH_SIZE, Salt, WorkFactor};
+
+fn value_error(err: BcryptError) -> PyErr {
+	PyValueError::new_err(err.to_string())
+}
+
+/// Generates a `$2b$` or `$2a$` setting with a salt from the operating system. Raises `ValueError` if `rounds` isn’t 4 to 31.
+#[pyfunction]
+#[pyo3(signature = (rounds = 12, prefix = &b"2b"[..]))]
+fn gensalt<'py>(py: Python<'py>, rounds: u32, prefix: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+	let work_factor = WorkFactor::exp(rounds).ok_or_else(|| PyValueError::new_err("Invalid rounds"))?;
+	let version = match prefix {
+		b"2a" => Version::V2a,
+		b"2b" => Version::V2b,
+		_ => return Err(PyValueError::new_err("Supported prefixes are b'2a' or b'2b'")),
+	};
+	let hash_string = HashString::new(version, work_factor, &Salt::generate(OsRng), &[0; HASH_SIZE]);
+	Ok(PyBytes::new(py, &hash_string.as_bytes()[..SETTING_SIZE]))
+}
+
+/// Hashes a password with the setting at the start of `salt`, which can be a whole hash string. Raises `ValueError` if the setting isn’t valid, or the password is longer than 72 bytes or contains a NUL byte.
+#[pyfunction]
+fn hashpw<'py>(py: Python<'py>, password: &[u8], salt: &[u8]) -> PyResult<Bound<'py, PyBytes>> {
+	let setting = salt.get(..SETTING_SIZE)
+		.and_then(|setting| str::from_utf8(setting).ok())
+		.and_then(|setting| Setting::parse(setting).ok())
+		.ok_or_else(|| PyValueError::new_err("Invalid salt"))?;
+	let hash_string = py.detach(|| setting.hash(password)).map_err(value_error)?;
+	Ok(PyBytes::new(py, hash_string.as_bytes()))
+}
+
+/// Checks a password against a hash string in constant time. Raises `ValueError` if the hash string isn’t valid, or the password is longer than 72 bytes or contains a NUL byte.
+#[pyfunction]
+fn checkpw(py: Python<'_>, password: &[u8], hashed_password: &[u8]) -> PyResult<bool> {
+	let hash_string = str::from_utf8(hashed_password).ok()
+		.and_then(|hashed_password| HashString::parse(hashed_password).ok())
+		.ok_or_else(|| PyValueError::new_err("Invalid salt"))?;
+	py.detach(|| mcf::verify(password, &hash_string)).map_err(value_error)
+}
+
+#[pymodule]
+pub(crate) fn bcrypt_only(module: &Bound<'_, PyModule>) -> PyResult<()> {
+	module.add_function(wrap_pyfunction!(gensalt, module)?)?;
+	module.add_function(wrap_pyfunction!(hashpw, module)?)?;
+	module.add_function(wrap_pyfunction!(checkpw, module)?)?;
+	Ok(())
+}
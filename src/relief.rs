@@ -0,0 +1,156 @@
+//! Server relief: the client runs the expensive bcrypt, and the server only stores and checks a cheap keyed hash of its result.
+//!
+//! The server gives the client a record’s work factor and salt; the client sends back [`client_hash`] of the password, encoded with [`encode_client_hash`]; the server checks it with [`ServerRecord::verify`]. The client hash is a password equivalent in transit, so it needs the same protection as a password would. A leaked record and server key only allow guessing at the cost of bcrypt per guess, but a leaked record alone – without the key – allows no guessing at all.
+//!
+//! Records are serialized as `$bcrypt-relief$`, a two-digit work factor, `$`, then the salt and a 32-byte HMAC-SHA-512 tag in bcrypt’s base64.
+
+use core::fmt;
+use core::hint::black_box;
+use core::str::{self, FromStr};
+
+use super::{BcryptError, HASH_SIZE, SALT_SIZE, Salt, WorkFactor, base64, bcrypt_runtime};
+use super::mcf::{ParseError, SALT_STRING_SIZE};
+use super::sha512::hmac;
+
+const PREFIX: &str = "$bcrypt-relief$";
+
+/// Separates the tag from HMACs computed with the same key for other purposes.
+const CONTEXT: &[u8] = b"bcrypt-only server relief\0";
+
+/// The number of bytes in a server record’s tag.
+pub const TAG_SIZE: usize = 32;
+
+/// The length of a client hash in bcrypt’s base64.
+pub const CLIENT_HASH_STRING_SIZE: usize = base64::encoded_len(HASH_SIZE);
+
+/// The length of a serialized server record.
+pub const RECORD_STRING_SIZE: usize = PREFIX.len() + 3 + SALT_STRING_SIZE + base64::encoded_len(TAG_SIZE);
+
+/// Computes the hash the client sends in place of its password. This is [`bcrypt`](crate::bcrypt), under a name that says which side runs it.
+pub fn client_hash(key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+	bcrypt_runtime(key, salt, work_factor)
+}
+
+/// Encodes a client hash in bcrypt’s base64, for sending to the server.
+pub fn encode_client_hash(hash: &[u8; HASH_SIZE]) -> [u8; CLIENT_HASH_STRING_SIZE] {
+	let mut chars = [0_u8; CLIENT_HASH_STRING_SIZE];
+	base64::encode(hash, &mut chars);
+	chars
+}
+
+/// Decodes a client hash encoded by [`encode_client_hash`].
+pub fn decode_client_hash(s: &str) -> Result<[u8; HASH_SIZE], ParseError> {
+	let s = s.as_bytes();
+
+	if s.len() != CLIENT_HASH_STRING_SIZE {
+		return Err(ParseError::Length);
+	}
+
+	let mut hash = [0_u8; HASH_SIZE];
+
+	if base64::decode(s, &mut hash).is_none() {
+		return Err(ParseError::Encoding);
+	}
+
+	if !base64::is_canonical(s) {
+		return Err(ParseError::NonCanonical);
+	}
+
+	Ok(hash)
+}
+
+/// What the server stores for one password: the parameters the client needs, and a keyed hash of the client’s result.
+#[derive(Clone, Debug)]
+pub struct ServerRecord {
+	work_factor: WorkFactor,
+	salt: Salt,
+	tag: [u8; TAG_SIZE],
+}
+
+impl ServerRecord {
+	/// Creates a record from a client hash computed with `work_factor` and `salt`, keyed by the server’s secret `server_key`.
+	pub fn new(server_key: &[u8], work_factor: WorkFactor, salt: &Salt, client_hash: &[u8; HASH_SIZE]) -> Self {
+		Self {
+			work_factor,
+			salt: salt.clone(),
+			tag: tag(server_key, work_factor, salt, client_hash),
+		}
+	}
+
+	/// Checks whether a client hash matches this record, comparing the tags in constant time.
+	pub fn verify(&self, server_key: &[u8], client_hash: &[u8; HASH_SIZE]) -> bool {
+		let expected = tag(server_key, self.work_factor, &self.salt, client_hash);
+		let mut difference = 0_u8;
+
+		for (x, y) in self.tag.iter().zip(&expected) {
+			difference |= black_box(x ^ y);
+		}
+
+		difference == 0
+	}
+
+	/// The work factor the client hashes with.
+	pub const fn work_factor(&self) -> WorkFactor {
+		self.work_factor
+	}
+
+	/// The salt the client hashes with.
+	pub fn salt(&self) -> Salt {
+		self.salt.clone()
+	}
+
+	/// Parses a record serialized with [`Display`](fmt::Display).
+	pub fn parse(s: &str) -> Result<Self, ParseError> {
+		if s.len() != RECORD_STRING_SIZE {
+			return Err(ParseError::Length);
+		}
+
+		let rest = s.strip_prefix(PREFIX).ok_or(ParseError::Version)?.as_bytes();
+
+		if !rest[0].is_ascii_digit() || !rest[1].is_ascii_digit() || rest[2] != b'$' {
+			return Err(ParseError::WorkFactor);
+		}
+
+		let work_factor = WorkFactor::exp(((rest[0] - b'0') * 10 + (rest[1] - b'0')) as u32).ok_or(ParseError::WorkFactor)?;
+		let (salt_chars, tag_chars) = rest[3..].split_at(SALT_STRING_SIZE);
+		let mut salt = [0_u8; SALT_SIZE];
+		let mut tag = [0_u8; TAG_SIZE];
+
+		if base64::decode(salt_chars, &mut salt).is_none() || base64::decode(tag_chars, &mut tag).is_none() {
+			return Err(ParseError::Encoding);
+		}
+
+		if !base64::is_canonical(salt_chars) || !base64::is_canonical(tag_chars) {
+			return Err(ParseError::NonCanonical);
+		}
+
+		Ok(Self {
+			work_factor,
+			salt: Salt::from_bytes(&salt),
+			tag,
+		})
+	}
+}
+
+fn tag(server_key: &[u8], work_factor: WorkFactor, salt: &Salt, client_hash: &[u8; HASH_SIZE]) -> [u8; TAG_SIZE] {
+	let mac = hmac(server_key, &[CONTEXT, &[work_factor.log_rounds() as u8], &salt.to_bytes(), client_hash]);
+	let mut tag = [0_u8; TAG_SIZE];
+	tag.copy_from_slice(&mac[..TAG_SIZE]);
+	tag
+}
+
+impl FromStr for ServerRecord {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, ParseError> {
+		Self::parse(s)
+	}
+}
+
+impl fmt::Display for ServerRecord {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let mut tag_chars = [0_u8; base64::encoded_len(TAG_SIZE)];
+		base64::encode(&self.tag, &mut tag_chars);
+		write!(f, "{}{:02}${}{}", PREFIX, self.work_factor.log_rounds(), self.salt, str::from_utf8(&tag_chars).unwrap())
+	}
+}
@@ -0,0 +1,79 @@
+use super::{SALT_SIZE, Salt};
+
+/// A source of random bytes for generating salts. It has to be cryptographically secure – salts from a predictable source defeat their purpose.
+pub trait Rng {
+	/// Fills `dest` with random bytes.
+	fn fill_bytes(&mut self, dest: &mut [u8]);
+}
+
+impl<R: Rng + ?Sized> Rng for &mut R {
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		(**self).fill_bytes(dest);
+	}
+}
+
+/// The operating system’s random number generator: `/dev/urandom` on Unix, `BCryptGenRandom` with the system’s preferred generator on Windows, and the host’s `random_get` through wasi-libc’s `getentropy` on WASI.
+#[cfg(all(feature = "std", any(unix, windows, target_os = "wasi")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsRng;
+
+#[cfg(all(feature = "std", unix))]
+impl Rng for OsRng {
+	/// Panics if `/dev/urandom` can’t be read.
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		use std::fs::File;
+		use std::io::Read;
+
+		File::open("/dev/urandom")
+			.and_then(|mut f| f.read_exact(dest))
+			.expect("failed to read /dev/urandom");
+	}
+}
+
+#[cfg(all(feature = "std", windows))]
+#[allow(unsafe_code)]
+impl Rng for OsRng {
+	/// Panics if `BCryptGenRandom` fails.
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		const BCRYPT_USE_SYSTEM_PREFERRED_RNG: u32 = 0x0000_0002;
+
+		#[link(name = "bcrypt")]
+		extern "system" {
+			fn BCryptGenRandom(algorithm: *mut core::ffi::c_void, buffer: *mut u8, length: u32, flags: u32) -> i32;
+		}
+
+		// BCryptGenRandom takes a 32-bit length.
+		for chunk in dest.chunks_mut(u32::MAX as usize) {
+			// SAFETY: `chunk` is valid for writes of `chunk.len()` bytes, which fits in a `u32`, and a null algorithm handle is allowed with BCRYPT_USE_SYSTEM_PREFERRED_RNG.
+			let status = unsafe { BCryptGenRandom(core::ptr::null_mut(), chunk.as_mut_ptr(), chunk.len() as u32, BCRYPT_USE_SYSTEM_PREFERRED_RNG) };
+			assert!(status >= 0, "BCryptGenRandom failed");
+		}
+	}
+}
+
+#[cfg(all(feature = "std", target_os = "wasi"))]
+#[allow(unsafe_code)]
+impl Rng for OsRng {
+	/// Panics if `getentropy` fails.
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		extern "C" {
+			fn getentropy(buffer: *mut core::ffi::c_void, length: usize) -> i32;
+		}
+
+		// getentropy fills at most 256 bytes at a time.
+		for chunk in dest.chunks_mut(256) {
+			// SAFETY: `chunk` is valid for writes of `chunk.len()` bytes, which is within getentropy’s limit.
+			let status = unsafe { getentropy(chunk.as_mut_ptr().cast(), chunk.len()) };
+			assert!(status == 0, "getentropy failed");
+		}
+	}
+}
+
+impl Salt {
+	/// Generates a salt from a random number generator.
+	pub fn generate<R: Rng>(mut rng: R) -> Self {
+		let mut bytes = [0_u8; SALT_SIZE];
+		rng.fill_bytes(&mut bytes);
+		Self::from_bytes(&bytes)
+	}
+}
@@ -0,0 +1,22 @@
+//! Counting the expensive rounds hashing actually runs, so assurance tests can check that a configured work factor reaches the core – that nothing on the way passed the number of rounds where a logarithm was expected, or dropped the work factor for a default.
+
+use core::cell::Cell;
+
+std::thread_local! {
+	static ROUNDS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Adds to the calling thread’s count of expensive rounds run.
+pub(crate) fn record(rounds: u64) {
+	ROUNDS.with(|count| count.set(count.get().wrapping_add(rounds)));
+}
+
+/// Runs `f`, returning its result and the number of bcrypt’s expensive rounds that hashing on the calling thread ran during it. Calls can be nested.
+///
+/// Everything in this crate that hashes is counted, except calls to [`bcrypt`](crate::bcrypt) itself: it’s a `const fn`, which can’t record anything. Hashes run on other threads – like a [`VerifierPool`](crate::VerifierPool)’s – are counted on those threads.
+pub fn count_rounds<T, F: FnOnce() -> T>(f: F) -> (T, u64) {
+	let before = ROUNDS.with(Cell::get);
+	let result = f();
+	let after = ROUNDS.with(Cell::get);
+	(result, after.wrapping_sub(before))
+}
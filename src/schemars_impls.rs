@@ -0,0 +1,65 @@
+//! `JsonSchema` for the `schemars` feature, describing the `serde` feature’s human-readable forms.
+
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use std::borrow::Cow;
+
+use super::mcf::HashString;
+use super::{Salt, WorkFactor};
+
+/// An integer from 4 to 31.
+impl JsonSchema for WorkFactor {
+	fn schema_name() -> Cow<'static, str> {
+		"WorkFactor".into()
+	}
+
+	fn schema_id() -> Cow<'static, str> {
+		"bcrypt_only::WorkFactor".into()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		json_schema!({
+			"type": "integer",
+			"minimum": 4,
+			"maximum": 31,
+			"description": "A bcrypt work factor: the base-2 logarithm of the number of rounds.",
+		})
+	}
+}
+
+/// 22 characters of bcrypt base64, with the unused bits of the last one zero.
+impl JsonSchema for Salt {
+	fn schema_name() -> Cow<'static, str> {
+		"Salt".into()
+	}
+
+	fn schema_id() -> Cow<'static, str> {
+		"bcrypt_only::Salt".into()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		json_schema!({
+			"type": "string",
+			"pattern": "^[./A-Za-z0-9]{21}[.Oeu]$",
+			"description": "A bcrypt salt, in bcrypt base64.",
+		})
+	}
+}
+
+/// A 60-character `$2a$`, `$2b$`, or `$2y$` hash string, in the canonical form [`HashString::parse`] accepts.
+impl JsonSchema for HashString {
+	fn schema_name() -> Cow<'static, str> {
+		"HashString".into()
+	}
+
+	fn schema_id() -> Cow<'static, str> {
+		"bcrypt_only::mcf::HashString".into()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		json_schema!({
+			"type": "string",
+			"pattern": "^\\$2[aby]\\$(0[4-9]|[12][0-9]|3[01])\\$[./A-Za-z0-9]{21}[.Oeu][./A-Za-z0-9]{30}[.CGKOSWaeimquy26]$",
+			"description": "A bcrypt hash string.",
+		})
+	}
+}
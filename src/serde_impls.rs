@@ -0,0 +1,137 @@
+//! `Serialize` and `Deserialize` for the `serde` feature: strings in human-readable formats like JSON, and fixed-size byte arrays, without length prefixes, in binary ones like postcard.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+use core::str::{self, FromStr};
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Unexpected, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+use super::mcf::{COMPACT_SIZE, HashString, SALT_STRING_SIZE};
+use super::{SALT_SIZE, Salt, WorkFactor, base64};
+
+/// Parses a string with `T`’s [`FromStr`], reporting its error without echoing the input.
+struct ParseVisitor<T> {
+	expecting: &'static str,
+	parsed: PhantomData<T>,
+}
+
+impl<T> ParseVisitor<T> {
+	const fn new(expecting: &'static str) -> Self {
+		Self { expecting, parsed: PhantomData }
+	}
+}
+
+impl<T: FromStr> Visitor<'_> for ParseVisitor<T>
+where
+	T::Err: fmt::Display,
+{
+	type Value = T;
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.expecting)
+	}
+
+	fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+		s.parse().map_err(E::custom)
+	}
+}
+
+/// Serializes bytes as a tuple, which binary formats write without a length, where `serialize_bytes` would add one. serde only implements this for arrays of up to 32 bytes.
+fn serialize_array<S: Serializer, const N: usize>(bytes: &[u8; N], serializer: S) -> Result<S::Ok, S::Error> {
+	let mut tuple = serializer.serialize_tuple(N)?;
+
+	for byte in bytes {
+		tuple.serialize_element(byte)?;
+	}
+
+	tuple.end()
+}
+
+/// Reads bytes written by [`serialize_array`].
+struct ArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ArrayVisitor<N> {
+	type Value = [u8; N];
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{} bytes", N)
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[u8; N], A::Error> {
+		let mut bytes = [0_u8; N];
+
+		for (i, byte) in bytes.iter_mut().enumerate() {
+			*byte = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?;
+		}
+
+		Ok(bytes)
+	}
+}
+
+fn deserialize_array<'de, D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error> {
+	deserializer.deserialize_tuple(N, ArrayVisitor)
+}
+
+/// The salt’s 22 characters of bcrypt base64, as in a hash string, or its 16 bytes.
+impl Serialize for Salt {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		if serializer.is_human_readable() {
+			let mut chars = [0_u8; SALT_STRING_SIZE];
+			base64::encode(&self.to_bytes(), &mut chars);
+			serializer.serialize_str(str::from_utf8(&chars).unwrap())
+		} else {
+			serialize_array(&self.to_bytes(), serializer)
+		}
+	}
+}
+
+/// Validates a string as strictly as [`HashString::parse`](super::mcf::HashString::parse) does a hash string’s salt. Any 16 bytes are a salt.
+impl<'de> Deserialize<'de> for Salt {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		if deserializer.is_human_readable() {
+			deserializer.deserialize_str(ParseVisitor::new("a bcrypt salt"))
+		} else {
+			deserialize_array::<D, SALT_SIZE>(deserializer).map(|bytes| Salt::from_bytes(&bytes))
+		}
+	}
+}
+
+/// The work factor’s base-2 exponent, as an integer.
+impl Serialize for WorkFactor {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_u32(self.log_rounds())
+	}
+}
+
+/// Validates the exponent as [`WorkFactor::exp`] does, so a cost outside 4 to 31 in configuration fails when it’s loaded.
+impl<'de> Deserialize<'de> for WorkFactor {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let log_rounds = u32::deserialize(deserializer)?;
+		WorkFactor::try_from(log_rounds).map_err(|_| de::Error::invalid_value(Unexpected::Unsigned(log_rounds.into()), &"a bcrypt work factor from 4 to 31"))
+	}
+}
+
+/// The canonical hash string, or its 41-byte [compact form](HashString::to_compact).
+impl Serialize for HashString {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		if serializer.is_human_readable() {
+			serializer.serialize_str(self.as_str())
+		} else {
+			serialize_array(&self.to_compact(), serializer)
+		}
+	}
+}
+
+/// Validates a string with [`HashString::parse`], and bytes with [`HashString::from_compact`].
+impl<'de> Deserialize<'de> for HashString {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		if deserializer.is_human_readable() {
+			deserializer.deserialize_str(ParseVisitor::new("a bcrypt hash string"))
+		} else {
+			let bytes = deserialize_array::<D, COMPACT_SIZE>(deserializer)?;
+			HashString::from_compact(&bytes).map_err(de::Error::custom)
+		}
+	}
+}
@@ -0,0 +1,165 @@
+use core::fmt;
+use core::hint::black_box;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use super::{BcryptError, BlowfishContext, HASH_SIZE, KEY_SIZE_MAX, Salt, WorkFactor, bcrypt_finish, bcrypt_round, bcrypt_setup, check_key, record_rounds, runtime_initial_state};
+
+/// A bcrypt hash in progress, for running the expensive rounds a bounded number at a time – to feed a watchdog, or yield to an executor, between them.
+///
+/// The state is about 4 KiB, so it can be large to keep on a small stack. It holds a copy of the key and the state derived from it, both zeroed when it’s dropped.
+pub struct BcryptState {
+	state: BlowfishContext,
+	key: [u8; KEY_SIZE_MAX],
+	key_len: usize,
+	salt: Salt,
+	remaining_rounds: u64,
+	hash: Option<[u8; HASH_SIZE]>,
+}
+
+impl BcryptState {
+	/// Starts hashing a key and salt with bcrypt according to a work factor, doing the cheap setup but none of the expensive rounds. Fails for the same keys as [`bcrypt`](crate::bcrypt).
+	pub fn new(key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<Self, BcryptError> {
+		let mut state = Self::resume(key, salt, runtime_initial_state(), work_factor.linear_rounds().into())?;
+		bcrypt_setup(&mut state.state, key, salt);
+		Ok(state)
+	}
+
+	/// Continues a hash from `state`, with `remaining_rounds` expensive rounds still to run – up to 2⁶⁴ − 1 of them, more than any work factor gives.
+	pub(crate) fn resume(key: &[u8], salt: &Salt, state: BlowfishContext, remaining_rounds: u64) -> Result<Self, BcryptError> {
+		check_key(key)?;
+
+		let mut resumed = Self {
+			state,
+			key: [0; KEY_SIZE_MAX],
+			key_len: key.len(),
+			salt: salt.clone(),
+			remaining_rounds,
+			hash: None,
+		};
+
+		resumed.key[..key.len()].copy_from_slice(key);
+		Ok(resumed)
+	}
+
+	/// Runs up to `rounds` more expensive rounds. Returns the hash once all of them have run, and on every call after that.
+	pub fn run(&mut self, rounds: u32) -> Option<[u8; HASH_SIZE]> {
+		let rounds = u64::from(rounds).min(self.remaining_rounds);
+
+		for _ in 0..rounds {
+			bcrypt_round(&mut self.state, &self.key[..self.key_len], &self.salt);
+		}
+
+		record_rounds(rounds);
+
+		self.remaining_rounds -= rounds;
+
+		if self.remaining_rounds == 0 && self.hash.is_none() {
+			self.hash = Some(bcrypt_finish(&self.state));
+		}
+
+		self.hash
+	}
+
+	/// Runs the remaining rounds `rounds_per_call` at a time, calling `between` with the number of rounds still to run after each chunk that leaves some – for a superloop or cooperative scheduler to service other work – and returns the hash.
+	pub fn finish_with<F: FnMut(u64)>(&mut self, rounds_per_call: u32, mut between: F) -> [u8; HASH_SIZE] {
+		let rounds_per_call = rounds_per_call.max(1);
+
+		loop {
+			if let Some(hash) = self.run(rounds_per_call) {
+				return hash;
+			}
+
+			between(self.remaining_rounds());
+		}
+	}
+
+	/// The number of expensive rounds left to run.
+	pub const fn remaining_rounds(&self) -> u64 {
+		self.remaining_rounds
+	}
+
+	/// The Blowfish state the rounds run on.
+	#[cfg(feature = "unstable")]
+	pub(crate) const fn blowfish_state(&self) -> &BlowfishContext {
+		&self.state
+	}
+}
+
+impl Drop for BcryptState {
+	fn drop(&mut self) {
+		self.state.zero();
+		self.key.fill(0);
+		black_box(&mut self.key);
+	}
+}
+
+impl fmt::Debug for BcryptState {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("BcryptState")
+			.field("remaining_rounds", &self.remaining_rounds)
+			.finish_non_exhaustive()
+	}
+}
+
+/// A future hashing with a [`BcryptState`] a chunk of rounds at a time, awaiting a yield future from `yield_now` between chunks – for a single-threaded event loop, like a browser’s under wasm-bindgen-futures, to run other work in between.
+///
+/// What the yield future waits for decides what gets to run. In a browser, it has to resolve on a macrotask, like a `setTimeout(0)` timer or a `MessageChannel` message, for rendering and input to run between chunks; a future that resolves right away, or only wakes its task, leaves it on the microtask queue, which the browser drains before doing anything else. The yield future has to be [`Unpin`]; pin one that isn’t with `Box::pin`.
+///
+/// After each chunk that leaves rounds to run, the future calls its progress callback with the number remaining, then yields.
+pub struct HashFuture<F, Y, S> {
+	state: BcryptState,
+	rounds_per_poll: u32,
+	progress: F,
+	yield_now: Y,
+	yielding: Option<S>,
+}
+
+impl<F: FnMut(u64), Y: FnMut() -> S, S: Future<Output = ()> + Unpin> HashFuture<F, Y, S> {
+	/// Runs `state` `rounds_per_poll` rounds at a time, calling `progress` and awaiting a future from `yield_now` between chunks.
+	pub fn new(state: BcryptState, rounds_per_poll: u32, progress: F, yield_now: Y) -> Self {
+		Self {
+			state,
+			rounds_per_poll: rounds_per_poll.max(1),
+			progress,
+			yield_now,
+			yielding: None,
+		}
+	}
+}
+
+impl<F: FnMut(u64) + Unpin, Y: FnMut() -> S + Unpin, S: Future<Output = ()> + Unpin> Future for HashFuture<F, Y, S> {
+	type Output = [u8; HASH_SIZE];
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<[u8; HASH_SIZE]> {
+		let this = self.get_mut();
+
+		loop {
+			if let Some(yielding) = &mut this.yielding {
+				if Pin::new(yielding).poll(cx).is_pending() {
+					return Poll::Pending;
+				}
+
+				this.yielding = None;
+			}
+
+			match this.state.run(this.rounds_per_poll) {
+				Some(hash) => return Poll::Ready(hash),
+				None => {
+					(this.progress)(this.state.remaining_rounds());
+					this.yielding = Some((this.yield_now)());
+				}
+			}
+		}
+	}
+}
+
+impl<F, Y, S> fmt::Debug for HashFuture<F, Y, S> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("HashFuture")
+			.field("state", &self.state)
+			.field("rounds_per_poll", &self.rounds_per_poll)
+			.finish_non_exhaustive()
+	}
+}
@@ -0,0 +1,114 @@
+use core::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+use super::{Salt, WorkFactor};
+use super::mcf;
+
+/// A load to replay with [`simulate_load`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Simulation {
+	/// The work factor to hash and verify at.
+	pub work_factor: WorkFactor,
+
+	/// The number of operations running at once – the size of the verifier pool being tried.
+	pub concurrency: NonZeroUsize,
+
+	/// The total number of operations.
+	pub operations: u64,
+
+	/// Hashes, as for registrations and password changes, out of every `hashes + verifies` operations.
+	pub hashes: u32,
+
+	/// Verifications, as for logins, out of every `hashes + verifies` operations. With both zero, every operation is a verification.
+	pub verifies: u32,
+}
+
+/// The result of [`simulate_load`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SimulationReport {
+	/// The number of hashes run.
+	pub hashes: u64,
+
+	/// The number of verifications run.
+	pub verifies: u64,
+
+	/// The wall-clock time the simulation took.
+	pub elapsed: Duration,
+
+	latencies: Vec<Duration>,
+}
+
+impl SimulationReport {
+	/// The sustained number of operations completed per second.
+	pub fn per_second(&self) -> f64 {
+		(self.hashes + self.verifies) as f64 / self.elapsed.as_secs_f64()
+	}
+
+	/// The latency that `percentile` percent of operations took no longer than, by the nearest-rank method: `percentile(50.0)` is the median and `percentile(100.0)` the slowest. `None` if no operations ran.
+	pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+		let rank = (percentile.clamp(0.0, 100.0) / 100.0 * self.latencies.len() as f64).ceil() as usize;
+		self.latencies.get(rank.max(1) - 1).copied()
+	}
+}
+
+/// Replays a mix of hashes and verifications on `concurrency` threads and reports throughput and latency percentiles, to check before rolling out a work factor that the verifier pool it’ll run on keeps up with the expected logins.
+///
+/// Each operation’s latency is the time it took to run, not counting time queued, so it’s the latency of a login only while the pool keeps up; a pool that doesn’t shows as throughput below the expected rate of logins.
+pub fn simulate_load(simulation: &Simulation) -> SimulationReport {
+	let work_factor = simulation.work_factor;
+	let hashes = u64::from(simulation.hashes);
+	let cycle = (hashes + u64::from(simulation.verifies)).max(1);
+	let stored = mcf::hash(b"simulated login", &Salt::from_bytes(&[0; 16]), work_factor).unwrap();
+	let next = AtomicU64::new(0);
+
+	let start = Instant::now();
+
+	let results: Vec<(Vec<Duration>, u64)> = thread::scope(|scope| {
+		let workers: Vec<_> = (0..simulation.concurrency.get()).map(|_| {
+			scope.spawn(|| {
+				let mut latencies = Vec::new();
+				let mut hash_count = 0;
+
+				loop {
+					let i = next.fetch_add(1, Ordering::Relaxed);
+
+					if i >= simulation.operations {
+						break (latencies, hash_count);
+					}
+
+					let operation_start = Instant::now();
+
+					if i % cycle < hashes {
+						let mut salt_bytes = [0_u8; 16];
+						salt_bytes[..8].copy_from_slice(&i.to_be_bytes());
+						mcf::hash(b"simulated registration", &Salt::from_bytes(&salt_bytes), work_factor).unwrap();
+						hash_count += 1;
+					} else {
+						assert!(mcf::verify(b"simulated login", &stored).unwrap());
+					}
+
+					latencies.push(operation_start.elapsed());
+				}
+			})
+		}).collect();
+
+		workers.into_iter()
+			.map(|w| w.join().unwrap())
+			.collect()
+	});
+
+	let elapsed = start.elapsed();
+	let hash_count: u64 = results.iter().map(|(_, hashes)| hashes).sum();
+	let mut latencies: Vec<Duration> = results.into_iter().flat_map(|(latencies, _)| latencies).collect();
+	latencies.sort_unstable();
+
+	SimulationReport {
+		hashes: hash_count,
+		verifies: latencies.len() as u64 - hash_count,
+		elapsed,
+		latencies,
+	}
+}
@@ -0,0 +1,51 @@
+//! `Type`, `Encode`, and `Decode` for the `sqlx` feature, for every database whose driver maps `str` and `[u8]`.
+
+use core::convert::TryFrom;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type, ValueRef};
+use std::string::String;
+
+use super::mcf::{COMPACT_SIZE, HashString, ParseError};
+
+/// A text column, like `TEXT` or `VARCHAR(60)`. Binary columns, like `BYTEA` or `BLOB`, are compatible too, holding the hash string’s [compact form](HashString::to_compact).
+impl<DB: Database> Type<DB> for HashString
+where
+	str: Type<DB>,
+	[u8]: Type<DB>,
+{
+	fn type_info() -> DB::TypeInfo {
+		<str as Type<DB>>::type_info()
+	}
+
+	fn compatible(ty: &DB::TypeInfo) -> bool {
+		<str as Type<DB>>::compatible(ty) || <[u8] as Type<DB>>::compatible(ty)
+	}
+}
+
+/// The hash string, as text. Bind [`to_compact`](HashString::to_compact)’s bytes to write a binary column.
+impl<'q, DB: Database> Encode<'q, DB> for HashString
+where
+	String: Encode<'q, DB>,
+{
+	fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+		String::from(self.as_str()).encode(buf)
+	}
+}
+
+/// Validates text with [`HashString::parse`], and bytes with [`HashString::from_compact`].
+impl<'r, DB: Database> Decode<'r, DB> for HashString
+where
+	str: Type<DB>,
+	&'r str: Decode<'r, DB>,
+	&'r [u8]: Decode<'r, DB>,
+{
+	fn decode(value: DB::ValueRef<'r>) -> Result<Self, BoxDynError> {
+		if <str as Type<DB>>::compatible(&value.type_info()) {
+			Ok(HashString::parse(<&str>::decode(value)?)?)
+		} else {
+			let bytes = <&[u8; COMPACT_SIZE]>::try_from(<&[u8]>::decode(value)?).map_err(|_| ParseError::Length)?;
+			Ok(HashString::from_compact(bytes)?)
+		}
+	}
+}
@@ -0,0 +1,95 @@
+//! [proptest](https://docs.rs/proptest) strategies for the values this crate’s API takes, so property tests of code built on it don’t each have to describe them.
+
+use std::string::String;
+use std::vec::Vec;
+
+use proptest::prelude::*;
+use proptest::sample::{Index, select};
+
+use super::base64::ALPHABET;
+use super::mcf::{HASH_STRING_SIZE, HashString, ParseError, SETTING_SIZE, Version};
+use super::{HASH_SIZE, KEY_SIZE_MAX, SALT_SIZE, Salt, WorkFactor};
+
+/// Printable ASCII outside bcrypt’s base64 alphabet.
+const NOT_BASE64: &[u8] = b" !\"#$%&'()*+,-:;<=>?@[\\]^_`{|}~";
+
+/// Any salt.
+pub fn salt() -> impl Strategy<Value = Salt> {
+	any::<[u8; SALT_SIZE]>().prop_map(|bytes| Salt::from_bytes(&bytes))
+}
+
+/// Work factors from 4 to `max`. Each step up doubles the time a hash takes, so most tests want a small `max`.
+pub fn work_factor(max: WorkFactor) -> impl Strategy<Value = WorkFactor> {
+	(4..=max.log_rounds()).prop_map(|log_rounds| WorkFactor::exp(log_rounds).unwrap())
+}
+
+/// Keys bcrypt accepts: up to 72 bytes, none of them 0. The lengths at the edges – empty, one byte, and 71 and 72 bytes – come up as often as all the others together.
+pub fn key() -> impl Strategy<Value = Vec<u8>> {
+	let len = prop_oneof![
+		1 => Just(0),
+		1 => Just(1),
+		1 => Just(KEY_SIZE_MAX - 1),
+		1 => Just(KEY_SIZE_MAX),
+		4 => 0..=KEY_SIZE_MAX,
+	];
+
+	len.prop_flat_map(|len| prop::collection::vec(1..=u8::MAX, len))
+}
+
+/// Keys bcrypt rejects: longer than 72 bytes, from 73, or containing a 0 byte.
+pub fn rejected_key() -> impl Strategy<Value = Vec<u8>> {
+	let too_long = prop_oneof![Just(KEY_SIZE_MAX + 1), KEY_SIZE_MAX + 1..=4 * KEY_SIZE_MAX]
+		.prop_flat_map(|len| prop::collection::vec(1..=u8::MAX, len));
+	let zero_byte = (prop::collection::vec(any::<u8>(), 1..=KEY_SIZE_MAX), any::<Index>()).prop_map(|(mut key, i)| {
+		let len = key.len();
+		key[i.index(len)] = 0;
+		key
+	});
+
+	prop_oneof![too_long, zero_byte]
+}
+
+/// Well-formed hash strings of every version, with work factors from 4 to `max`. Their hashes are random, so no key matches them; verifying one still costs its work factor.
+pub fn hash_string(max: WorkFactor) -> impl Strategy<Value = HashString> {
+	let version = select([Version::V2a, Version::V2b, Version::V2y].as_slice());
+
+	(version, work_factor(max), salt(), any::<[u8; HASH_SIZE]>())
+		.prop_map(|(version, work_factor, salt, hash)| HashString::new(version, work_factor, &salt, &hash))
+}
+
+/// Strings that are a [`hash_string`] corrupted in one place, so that [`HashString::parse`] fails with `error`.
+pub fn corrupted_hash_string(error: ParseError, max: WorkFactor) -> BoxedStrategy<String> {
+	let corruption: BoxedStrategy<(usize, u8)> = match error {
+		ParseError::Length => {
+			return (hash_string(max), prop_oneof![0..HASH_STRING_SIZE, HASH_STRING_SIZE + 1..=2 * HASH_STRING_SIZE])
+				.prop_map(|(hash_string, len)| hash_string.chars().cycle().take(len).collect())
+				.boxed();
+		}
+		ParseError::Version => prop_oneof![
+			(Just(0), select(ALPHABET.as_slice())),
+			(Just(1), select(b"013456789".as_slice())),
+			(Just(2), select(b"cdefghijklmnopqrstuvwxzABY$".as_slice())),
+			(Just(3), select(ALPHABET.as_slice())),
+		].boxed(),
+		ParseError::WorkFactor => prop_oneof![
+			(4..=5_usize, select(b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz$".as_slice())),
+			(Just(6), select(ALPHABET.as_slice())),
+			// A first digit of 4 or more is always out of range.
+			(Just(4), select(b"456789".as_slice())),
+		].boxed(),
+		ParseError::Encoding => (7..HASH_STRING_SIZE, select(NOT_BASE64)).boxed(),
+		// The salt’s last character carries 2 bits, leaving its low 4 unused, and the hash’s carries 4, leaving its low 2.
+		ParseError::NonCanonical => prop_oneof![
+			(Just(SETTING_SIZE - 1), (0..64_usize).prop_filter("canonical", |sextet| sextet & 0xf != 0).prop_map(|sextet| ALPHABET[sextet])),
+			(Just(HASH_STRING_SIZE - 1), (0..64_usize).prop_filter("canonical", |sextet| sextet & 0x3 != 0).prop_map(|sextet| ALPHABET[sextet])),
+		].boxed(),
+	};
+
+	(hash_string(max), corruption)
+		.prop_map(|(hash_string, (i, b))| {
+			let mut bytes = hash_string.as_bytes().to_vec();
+			bytes[i] = b;
+			String::from_utf8(bytes).unwrap()
+		})
+		.boxed()
+}
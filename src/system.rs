@@ -0,0 +1,55 @@
+//! A backend calling the system’s bcrypt through libxcrypt’s `crypt_rn`, as an independent oracle for differential testing. It allocates, isn’t fast, and depends on the system library supporting `$2b$`, so it isn’t meant for anything else.
+
+#![allow(unsafe_code)]
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::vec;
+
+use super::backend::BcryptBackend;
+use super::{BcryptError, HASH_SIZE, KEY_SIZE_MAX, Salt, WorkFactor, base64, check_key};
+
+/// `sizeof(struct crypt_data)` in libxcrypt.
+const CRYPT_DATA_SIZE: usize = 32768;
+
+#[link(name = "crypt")]
+extern "C" {
+	fn crypt_rn(phrase: *const c_char, setting: *const c_char, data: *mut c_void, size: c_int) -> *mut c_char;
+}
+
+/// The system’s bcrypt implementation, through libxcrypt.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemCrypt;
+
+impl BcryptBackend for SystemCrypt {
+	fn name(&self) -> &'static str {
+		"system-crypt"
+	}
+
+	fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		check_key(key)?;
+
+		let mut phrase = [0_u8; KEY_SIZE_MAX + 1];
+		phrase[..key.len()].copy_from_slice(key);
+
+		let mut setting = *b"$2b$00$......................\0";
+		setting[4] = b'0' + (work_factor.log_rounds() / 10) as u8;
+		setting[5] = b'0' + (work_factor.log_rounds() % 10) as u8;
+		base64::encode(&salt.to_bytes(), &mut setting[7..29]);
+
+		let mut data = vec![0_u8; CRYPT_DATA_SIZE];
+
+		// SAFETY: `phrase` and `setting` are NUL-terminated, and `data` is a writable buffer of the size passed. The result, if not null, points into `data`.
+		let hashed = unsafe {
+			let result = crypt_rn(phrase.as_ptr().cast(), setting.as_ptr().cast(), data.as_mut_ptr().cast(), CRYPT_DATA_SIZE as c_int);
+			assert!(!result.is_null(), "system crypt_rn failed");
+			CStr::from_ptr(result).to_bytes()
+		};
+
+		assert!(hashed.len() == 60 && hashed[..29] == setting[..29], "unexpected system crypt_rn output");
+
+		let mut hash = [0_u8; HASH_SIZE];
+		base64::decode(&hashed[29..], &mut hash).expect("invalid system crypt_rn output");
+		Ok(hash)
+	}
+}
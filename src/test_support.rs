@@ -0,0 +1,204 @@
+//! Deterministic stand-ins for randomness and time, so integration tests of code using this crate get stable salts, hashes, and timings.
+
+use core::cell::Cell;
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+use super::Salt;
+use super::clock::Clock;
+use super::rng::Rng;
+
+/// ChaCha20 seeded with a fixed key: a cryptographically secure generator whose output is entirely determined by its seed. Only suitable for tests – the point of a salt is that it isn’t reproducible.
+#[derive(Clone, Debug)]
+pub struct DeterministicRng {
+	key: [u32; 8],
+	counter: u64,
+	block: [u8; 64],
+	used: usize,
+}
+
+const fn quarter_round(x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+	x[a] = x[a].wrapping_add(x[b]);
+	x[d] = (x[d] ^ x[a]).rotate_left(16);
+	x[c] = x[c].wrapping_add(x[d]);
+	x[b] = (x[b] ^ x[c]).rotate_left(12);
+	x[a] = x[a].wrapping_add(x[b]);
+	x[d] = (x[d] ^ x[a]).rotate_left(8);
+	x[c] = x[c].wrapping_add(x[d]);
+	x[b] = (x[b] ^ x[c]).rotate_left(7);
+}
+
+impl DeterministicRng {
+	/// Creates a generator from a 32-byte seed.
+	pub fn from_seed(seed: [u8; 32]) -> Self {
+		let mut key = [0_u32; 8];
+
+		for (k, b) in key.iter_mut().zip(seed.chunks_exact(4)) {
+			*k = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+		}
+
+		Self {
+			key,
+			counter: 0,
+			block: [0; 64],
+			used: 64,
+		}
+	}
+
+	/// Creates a generator from a number, for convenience.
+	pub fn new(seed: u64) -> Self {
+		let mut bytes = [0_u8; 32];
+		bytes[..8].copy_from_slice(&seed.to_le_bytes());
+		Self::from_seed(bytes)
+	}
+
+	fn refill(&mut self) {
+		let mut state = [
+			0x61707865, 0x3320646e, 0x79622d32, 0x6b206574,
+			self.key[0], self.key[1], self.key[2], self.key[3],
+			self.key[4], self.key[5], self.key[6], self.key[7],
+			self.counter as u32, (self.counter >> 32) as u32, 0, 0,
+		];
+		let initial = state;
+
+		for _ in 0..10 {
+			quarter_round(&mut state, 0, 4, 8, 12);
+			quarter_round(&mut state, 1, 5, 9, 13);
+			quarter_round(&mut state, 2, 6, 10, 14);
+			quarter_round(&mut state, 3, 7, 11, 15);
+			quarter_round(&mut state, 0, 5, 10, 15);
+			quarter_round(&mut state, 1, 6, 11, 12);
+			quarter_round(&mut state, 2, 7, 8, 13);
+			quarter_round(&mut state, 3, 4, 9, 14);
+		}
+
+		for (b, (w, i)) in self.block.chunks_exact_mut(4).zip(state.iter().zip(&initial)) {
+			b.copy_from_slice(&w.wrapping_add(*i).to_le_bytes());
+		}
+
+		self.counter += 1;
+		self.used = 0;
+	}
+}
+
+impl Rng for DeterministicRng {
+	fn fill_bytes(&mut self, dest: &mut [u8]) {
+		for b in dest {
+			if self.used == self.block.len() {
+				self.refill();
+			}
+
+			*b = self.block[self.used];
+			self.used += 1;
+		}
+	}
+}
+
+/// A clock that only moves when told to, plus a fixed step every time it’s read – which makes anything timing a loop see each iteration take exactly that step. Sleeping on it advances it without waiting.
+///
+/// The default clock steps by a millisecond, so code waiting for time to pass still finishes.
+#[derive(Debug)]
+pub struct FakeClock {
+	now: Cell<Duration>,
+	step: Duration,
+}
+
+impl FakeClock {
+	/// Creates a clock at zero that advances by `step` each time it’s read.
+	pub fn new(step: Duration) -> Self {
+		Self {
+			now: Cell::new(Duration::ZERO),
+			step,
+		}
+	}
+
+	/// Moves the clock forward.
+	pub fn advance(&self, by: Duration) {
+		self.now.set(self.now.get() + by);
+	}
+}
+
+impl Default for FakeClock {
+	fn default() -> Self {
+		Self::new(Duration::from_millis(1))
+	}
+}
+
+impl Clock for FakeClock {
+	fn now(&self) -> Duration {
+		let now = self.now.get();
+		self.now.set(now + self.step);
+		now
+	}
+
+	fn sleep(&self, duration: Duration) {
+		self.advance(duration);
+	}
+}
+
+/// An error from [`SaltReuseDetector::check`]: a salt that had been seen before.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct ReusedSalt {
+	/// The reused salt.
+	pub salt: Salt,
+
+	/// How many salts had been checked before this one.
+	pub checked: usize,
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for ReusedSalt {
+	fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+		write!(f, "salt {} reused after {} salts; is the random number generator seeded with a fixed value?", self.salt, self.checked)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReusedSalt {}
+
+/// Remembers every salt it’s shown and reports repeats, to catch broken random number generator wiring – a test generator or a fixed seed shipped by accident – in tests or staging before it reaches production.
+///
+/// With a working generator, a repeat among 16-byte salts is vanishingly unlikely, so any repeat means something is wrong. Every salt is kept, at a few dozen bytes each, so this is meant for test runs and staging environments rather than long-running production services.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct SaltReuseDetector {
+	seen: std::sync::Mutex<std::collections::HashSet<[u8; super::SALT_SIZE]>>,
+}
+
+#[cfg(feature = "std")]
+impl SaltReuseDetector {
+	/// Creates a detector that hasn’t seen any salts.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Records a salt, failing if it’s been recorded before.
+	pub fn check(&self, salt: &Salt) -> Result<(), ReusedSalt> {
+		let mut seen = self.seen.lock().unwrap();
+		let checked = seen.len();
+
+		if seen.insert(salt.to_bytes()) {
+			Ok(())
+		} else {
+			Err(ReusedSalt { salt: salt.clone(), checked })
+		}
+	}
+
+	/// Generates a salt with [`Salt::generate`] and checks it.
+	pub fn generate<R: Rng>(&self, rng: R) -> Result<Salt, ReusedSalt> {
+		let salt = Salt::generate(rng);
+		self.check(&salt)?;
+		Ok(salt)
+	}
+
+	/// The number of distinct salts recorded.
+	pub fn len(&self) -> usize {
+		self.seen.lock().unwrap().len()
+	}
+
+	/// Whether no salts have been recorded.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+}
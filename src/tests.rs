@@ -1,47 +1,2566 @@
 extern crate std;
 
+use core::convert::TryFrom;
 use core::hash::Hasher;
 use std::collections::hash_map::DefaultHasher;
+#[cfg(feature = "mcf")]
+use std::string::ToString;
 
-use super::{Salt, WorkFactor, bcrypt};
-use super::BcryptError::{Length, ZeroByte};
+use super::{Context, Salt, WorkFactor, bcrypt, bcrypt_with_context};
+use super::BcryptError::{Empty, Length, ZeroByte};
+
+type TestVector = (&'static [u8], u32, &'static [u8; 16], &'static [u8; 23]);
 
 #[test]
 fn pyca_test_vectors() {
-	let test_vectors: [(&[u8], u32, &[u8; 16], &[u8; 23]); 26] = include!("pyca-test-vectors.in");
+	let test_vectors: [TestVector; 26] = include!("pyca-test-vectors.in");
 
 	for &(key, log_rounds, salt, expected_hash) in &test_vectors {
 		assert_eq!(bcrypt(key, &Salt::from_bytes(salt), WorkFactor::exp(log_rounds).unwrap()), Ok(*expected_hash));
 	}
 }
 
+// with runtime-generated tables, this would spend minutes generating them during const evaluation
+#[cfg(not(feature = "runtime-tables"))]
 #[test]
-fn invalid_inputs() {
-	let salt = Salt::from_bytes(&[0; 16]);
-	let work_factor = WorkFactor::exp(4).unwrap();
-	assert_eq!(bcrypt(&[1; 73], &salt, work_factor), Err(Length));
-	assert_eq!(bcrypt(b"f\0o", &salt, work_factor), Err(ZeroByte));
+fn const_evaluation() {
+	// a single hash at the lowest work factor exceeds the default const evaluation step limit
+	#[allow(long_running_const_eval)]
+	const HASH: Result<[u8; 23], super::BcryptError> = bcrypt(b"Kk4DQuMMfZL9o", &Salt::from_bytes(b"\x79\x76\x2b\xe9\x97\x0f\x5b\xe7\x3a\xc7\x7c\x0e\x4f\x0a\x38\x51"), WorkFactor::EXP4);
+	assert_eq!(HASH, Ok(*b"\xdb\x8f\x03\x60\xd2\xaa\x48\xe1\x41\x55\x98\xbb\xc1\xb5\xc0\xd9\x10\x30\x43\xea\x39\x68\x6a"));
 }
 
 #[test]
-fn salt_round_trip() {
-	for i in 0..2048 {
-		let mut bytes = [0_u8; 16];
+fn reused_context() {
+	let test_vectors: [TestVector; 26] = include!("pyca-test-vectors.in");
+	let mut context = Context::new();
 
-		let mut s = DefaultHasher::new();
-		s.write_u32(i);
-		bytes[0..8].copy_from_slice(&s.finish().to_ne_bytes());
-		s.write_u32(i);
-		bytes[8..16].copy_from_slice(&s.finish().to_ne_bytes());
+	for &(key, log_rounds, salt, expected_hash) in &test_vectors {
+		assert_eq!(bcrypt_with_context(key, &Salt::from_bytes(salt), WorkFactor::exp(log_rounds).unwrap(), &mut context), Ok(*expected_hash));
+		assert!(context.state.p.iter().chain(context.state.s.iter().flatten()).all(|&word| word == 0));
+	}
+}
 
-		assert_eq!(Salt::from_bytes(&bytes).to_bytes(), bytes);
+#[test]
+fn pi_tables() {
+	let (p, s) = super::pi_tables();
+	assert_eq!(p, super::BLOWFISH_INITIAL.p);
+	assert_eq!(s, super::BLOWFISH_INITIAL.s);
+}
+
+#[test]
+fn portable_backend_batch() {
+	use super::backend::{BcryptBackend, DefaultBackend, HashJob};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let jobs = [
+		HashJob { key: b"one", salt: &salt, work_factor: WorkFactor::EXP4 },
+		HashJob { key: b"t\0o", salt: &salt, work_factor: WorkFactor::EXP4 },
+		HashJob { key: b"three", salt: &salt, work_factor: WorkFactor::EXP5 },
+	];
+	let mut results = [Err(Length); 3];
+	DefaultBackend::default().hash_batch(&jobs, &mut results);
+
+	for (job, result) in jobs.iter().zip(&results) {
+		assert_eq!(*result, bcrypt(job.key, job.salt, job.work_factor));
 	}
+
+	assert_eq!(results[1], Err(ZeroByte));
 }
 
+#[cfg(feature = "system-crypt")]
 #[test]
-fn work_factors() {
-	assert_eq!(WorkFactor::exp(3), None);
-	assert_eq!(WorkFactor::exp(4).map(|f| f.log_rounds()), Some(4));
-	assert_eq!(WorkFactor::exp(31).map(|f| f.linear_rounds()), Some(2147483648));
-	assert_eq!(WorkFactor::exp(32), None);
+fn system_crypt_differential() {
+	use super::backend::{Portable, SystemCrypt, compare, conformance};
+
+	if let Some(mismatch) = compare(Portable, SystemCrypt, 64, 0x6263_7279_7074) {
+		panic!("backends disagree on key {:x?}: {:?}", mismatch.key(), mismatch);
+	}
+
+	assert!(conformance(&SystemCrypt).is_none());
+}
+
+#[cfg(feature = "c-reference")]
+#[test]
+fn c_reference_differential() {
+	use super::backend::{CReference, Portable, compare, conformance};
+
+	if let Some(mismatch) = compare(Portable, CReference, 64, 0x6f_7065_6e62_7364) {
+		panic!("backends disagree on key {:x?}: {:?}", mismatch.key(), mismatch);
+	}
+
+	assert!(conformance(&CReference).is_none());
+}
+
+// Reports how long each implementation takes, which `--nocapture` shows.
+#[cfg(feature = "bcrypt-crate")]
+#[test]
+fn bcrypt_crate_differential() {
+	use super::backend::{BcryptBackend, BcryptCrate, Portable, compare, conformance};
+	use super::mcf::{self, HashString, Version};
+	use std::time::{Duration, Instant};
+
+	if let Some(mismatch) = compare(Portable, BcryptCrate, 64, 0x6273_6372_7970) {
+		panic!("backends disagree on key {:x?}: {:?}", mismatch.key(), mismatch);
+	}
+
+	assert!(conformance(&BcryptCrate).is_none());
+
+	// Each accepts the other’s hash strings, in every version.
+	for len in [0, 1, 8, 71, 72] {
+		let key: [u8; 72] = core::array::from_fn(|i| (i * 37 + len) as u8 | 0x80);
+		let key = &key[..len];
+		let salt = [len as u8; 16];
+
+		let ours = mcf::hash(key, &Salt::from_bytes(&salt), WorkFactor::EXP4).unwrap();
+
+		for version in [Version::V2a, Version::V2b, Version::V2y] {
+			let hash_string = HashString::new(version, ours.work_factor(), &ours.salt(), &ours.hash());
+			assert!(bcrypt_reference::verify(key, &hash_string).unwrap());
+			assert!(!bcrypt_reference::verify(b"wrong", &hash_string).unwrap());
+		}
+
+		let theirs = bcrypt_reference::hash_with_salt(key, 4, salt).unwrap();
+		assert_eq!(theirs.format_for_version(bcrypt_reference::Version::TwoB), ours.as_str());
+
+		for version in [bcrypt_reference::Version::TwoA, bcrypt_reference::Version::TwoB, bcrypt_reference::Version::TwoY] {
+			let hash_string = HashString::parse(&theirs.format_for_version(version)).unwrap();
+			assert!(mcf::verify(key, &hash_string).unwrap());
+			assert!(!mcf::verify(b"wrong", &hash_string).unwrap());
+		}
+	}
+
+	let time = |backend: &dyn BcryptBackend| -> Duration {
+		let salt = Salt::from_bytes(&[0x5a; 16]);
+		let start = Instant::now();
+
+		for _ in 0..8 {
+			backend.hash(b"password", &salt, WorkFactor::exp(8).unwrap()).unwrap();
+		}
+
+		start.elapsed()
+	};
+
+	let ours = time(&Portable);
+	let theirs = time(&BcryptCrate);
+	std::println!("8 hashes at cost 8: bcrypt-only {:?}, bcrypt crate {:?}, ratio {:.2}", ours, theirs, theirs.as_secs_f64() / ours.as_secs_f64());
+}
+
+#[test]
+fn backend_conformance() {
+	use super::{BcryptError, HASH_SIZE};
+	use super::backend::{BcryptBackend, ConformanceFailure, Portable, conformance};
+
+	struct Truncating;
+
+	impl BcryptBackend for Truncating {
+		fn name(&self) -> &'static str {
+			"truncating"
+		}
+
+		fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+			bcrypt(&key[..key.len().min(71)], salt, work_factor)
+		}
+	}
+
+	assert!(conformance(&Portable).is_none());
+	assert!(matches!(conformance(&Truncating), Some(ConformanceFailure::KnownAnswer { index: 1, .. })));
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn injected_backend() {
+	use core::sync::atomic::{AtomicUsize, Ordering};
+	use super::{BcryptError, HASH_SIZE};
+	use super::backend::{BcryptBackend, Portable};
+	use super::mcf;
+
+	struct Counting(AtomicUsize);
+
+	impl BcryptBackend for Counting {
+		fn name(&self) -> &'static str {
+			"counting"
+		}
+
+		fn hash(&self, key: &[u8], salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+			self.0.fetch_add(1, Ordering::Relaxed);
+			Portable.hash(key, salt, work_factor)
+		}
+	}
+
+	static BACKEND: Counting = Counting(AtomicUsize::new(0));
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash_string = mcf::hash_with_backend(&BACKEND, b"password", &salt, WorkFactor::EXP4).unwrap();
+
+	assert_eq!(hash_string.as_str(), mcf::hash(b"password", &salt, WorkFactor::EXP4).unwrap().as_str());
+	assert_eq!(mcf::verify_with_backend(&BACKEND, b"password", &hash_string), Ok(true));
+	assert_eq!(mcf::verify_with_backend(&BACKEND, b"wrong", &hash_string), Ok(false));
+	assert_eq!(BACKEND.0.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn base64_round_trip() {
+	use super::base64;
+
+	let bytes = *b"\x00\x10\x83\x10\x51\x87\x20\x92\x8b\x30\xd3\x8f\x41\x14\x93\x51\x55\x97\x61\x96\x9b\x71\xd7";
+	let mut encoded = [0_u8; 31];
+	base64::encode(&bytes, &mut encoded);
+	assert_eq!(&encoded, b"./ABCDEFGHIJKLMNOPQRSTUVWXYZaba");
+
+	let mut decoded = [0_u8; 23];
+	assert_eq!(base64::decode(&encoded, &mut decoded), Some(()));
+	assert_eq!(decoded, bytes);
+	assert!(base64::is_canonical(&encoded));
+
+	assert!(!base64::is_canonical(b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabb"));
+	assert_eq!(base64::decode(b"./ABCDEFGHIJKLMNOPQRSTUVWXYZab", &mut decoded), None);
+	assert_eq!(base64::decode(b"./ABCDEFGHIJKLMNOPQRSTUVWXYZab$", &mut decoded), None);
+
+	let alphabet = b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+	for c in 0..=255_u8 {
+		let mut byte = [0_u8; 1];
+		let expected = alphabet.iter().position(|&a| a == c).map(|sextet| (sextet as u8) << 2);
+		assert_eq!(base64::decode(&[c, b'.'], &mut byte).map(|()| byte[0]), expected, "{:?}", c as char);
+		assert_eq!(base64::is_valid(&[c]), expected.is_some());
+	}
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn hash_strings() {
+	use super::mcf::{self, HashString, ParseError, Version};
+
+	let s = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm";
+	let parsed = HashString::parse(s).unwrap();
+	assert_eq!(parsed.as_str(), s);
+	assert_eq!(parsed.version(), Version::V2b);
+	assert_eq!(parsed.work_factor(), WorkFactor::EXP4);
+	assert_eq!(HashString::new(parsed.version(), parsed.work_factor(), &parsed.salt(), &parsed.hash()), parsed);
+	assert_eq!(mcf::hash(b"Kk4DQuMMfZL9o", &parsed.salt(), WorkFactor::EXP4), Ok(parsed));
+	assert_eq!(mcf::verify(b"Kk4DQuMMfZL9o", &parsed), Ok(true));
+	assert_eq!(mcf::verify(b"Kk4DQuMMfZL9O", &parsed), Ok(false));
+	assert_eq!(mcf::verify(b"Kk4DQuMMfZL9o", &s.replacen("2b", "2y", 1).parse().unwrap()), Ok(true));
+
+	let v2y: HashString = s.replacen("2b", "2y", 1).parse().unwrap();
+	let v2a: HashString = s.replacen("2b", "2a", 1).parse().unwrap();
+	assert_eq!(v2y.relabel_v2y(), parsed);
+	assert_eq!(parsed.relabel_v2y(), parsed);
+	assert_eq!(v2a.relabel_v2y(), v2a);
+
+	assert_eq!(HashString::parse(&s[..59]), Err(ParseError::Length));
+	assert_eq!(HashString::parse(&s.replacen("2b", "2x", 1)), Err(ParseError::Version));
+	assert_eq!(HashString::parse(&s.replacen("04", "03", 1)), Err(ParseError::WorkFactor));
+	assert_eq!(HashString::parse(&s.replacen("SO", "S!", 1)), Err(ParseError::Encoding));
+	assert_eq!(HashString::parse(&s.replacen("SO", "SP", 1)), Err(ParseError::NonCanonical));
+	assert_eq!(HashString::parse(&s.replacen("Em", "En", 1)), Err(ParseError::NonCanonical));
+
+	let compact = parsed.to_compact();
+	assert_eq!(&compact[..2], b"b\x04");
+	assert_eq!(&compact[2..18], &parsed.salt().to_bytes());
+	assert_eq!(HashString::from_compact(&compact), Ok(parsed));
+
+	let mut corrupted = compact;
+	corrupted[0] = b'x';
+	assert_eq!(HashString::from_compact(&corrupted), Err(ParseError::Version));
+	corrupted = compact;
+	corrupted[1] = 32;
+	assert_eq!(HashString::from_compact(&corrupted), Err(ParseError::WorkFactor));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn external_vectors() {
+	use super::vectors::{VectorError, check_pyca_vectors, check_vectors};
+
+	assert_eq!(check_pyca_vectors(include_str!("../test-gen/pyca-test-vectors.py").as_bytes()).unwrap(), 26);
+
+	let corrupted = "[\n\t# corrupted\n\t(b'Kk4DQuMMfZL9', b'$2b$04$cVWp4XaNU8a4v1uMRum2SO', b'$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm'),\n]\n";
+	assert!(matches!(check_pyca_vectors(corrupted.as_bytes()), Err(VectorError::Mismatch { line: 3 })));
+	let salt_mismatch = "[(b'Kk4DQuMMfZL9o', b'$2b$05$cVWp4XaNU8a4v1uMRum2SO', b'$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm')]";
+	assert!(matches!(check_pyca_vectors(salt_mismatch.as_bytes()), Err(VectorError::Mismatch { line: 1 })));
+	let escaped = "[(b'\\xa3', b'$2a$05$/OK.fbVrR/bpIqNJ5ianF.', b'$2a$05$/OK.fbVrR/bpIqNJ5ianF.Sa7shbm4.OzKpvFnX1pQLmQW96oUlCq')]";
+	assert_eq!(check_pyca_vectors(escaped.as_bytes()).unwrap(), 1);
+	assert!(matches!(check_pyca_vectors("[\n(b'Kk4DQuMMfZL9o', b'$2b$04$cVWp4XaNU8a4v1uMRum2SO')]".as_bytes()), Err(VectorError::Syntax { line: 2 })));
+	assert!(matches!(check_pyca_vectors("[(b'\\q', b'', b'')]".as_bytes()), Err(VectorError::Syntax { line: 1 })));
+	assert!(matches!(check_pyca_vectors("[]\n]".as_bytes()), Err(VectorError::Syntax { line: 2 })));
+
+	let line = "4b6b344451754d4d665a4c396f,$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm\n";
+	assert_eq!(check_vectors(line.as_bytes()).unwrap(), 1);
+	let corrupted = "\n# corrupted\n4b6b3444,$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm\n";
+	assert!(matches!(check_vectors(corrupted.as_bytes()), Err(VectorError::Mismatch { line: 3 })));
+	assert!(matches!(check_vectors("4b6b344,$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm".as_bytes()), Err(VectorError::Syntax { line: 1 })));
+}
+
+#[cfg(feature = "round-count")]
+#[test]
+fn round_count() {
+	use super::{BcryptState, Context, KeyBuf, bcrypt_with_context, count_rounds, mcf};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let (hash_string, rounds) = count_rounds(|| mcf::hash(b"rounds", &salt, WorkFactor::EXP5).unwrap());
+	assert_eq!(rounds, 32);
+	assert_eq!(count_rounds(|| mcf::verify(b"rounds", &hash_string)), (Ok(true), 32));
+
+	let ((_, inner), outer) = count_rounds(|| {
+		bcrypt_with_context(b"rounds", &salt, WorkFactor::EXP4, &mut Context::new()).unwrap();
+		count_rounds(|| KeyBuf::<8>::new().hash(&salt, WorkFactor::EXP6))
+	});
+	assert_eq!((inner, outer), (64, 80));
+
+	let mut state = BcryptState::new(b"rounds", &salt, WorkFactor::EXP5).unwrap();
+	assert_eq!(count_rounds(|| state.run(10)).1, 10);
+	assert_eq!(count_rounds(|| state.finish_with(10, |_| {})).1, 22);
+
+	// `bcrypt` is a `const fn`, so it can’t record its rounds.
+	assert_eq!(count_rounds(|| bcrypt(b"rounds", &salt, WorkFactor::EXP4)).1, 0);
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn tracing_events() {
+	use core::fmt::{self, Write};
+	use std::string::String;
+	use std::sync::Mutex;
+	use std::vec::Vec;
+	use tracing::field::{Field, Visit};
+	use tracing::span::{Attributes, Id, Record};
+	use tracing::{Event, Metadata, Subscriber};
+	use super::mcf;
+
+	/// Writes each field of a span or event as `name=value`.
+	struct Fields<'a>(&'a mut String);
+
+	impl Visit for Fields<'_> {
+		fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+			write!(self.0, " {}={:?}", field.name(), value).unwrap();
+		}
+
+		fn record_str(&mut self, field: &Field, value: &str) {
+			self.record_debug(field, &format_args!("{}", value));
+		}
+	}
+
+	/// Keeps the fields of every span and event, one line each.
+	#[derive(Default)]
+	struct Recorder(Mutex<Vec<String>>);
+
+	impl Recorder {
+		fn push(&self, kind: &str, record: impl FnOnce(&mut Fields)) {
+			let mut line = String::from(kind);
+			record(&mut Fields(&mut line));
+			self.0.lock().unwrap().push(line);
+		}
+	}
+
+	impl Subscriber for &'static Recorder {
+		fn enabled(&self, _metadata: &Metadata) -> bool {
+			true
+		}
+
+		fn new_span(&self, span: &Attributes) -> Id {
+			self.push("span", |fields| span.record(fields));
+			Id::from_u64(1)
+		}
+
+		fn record(&self, _span: &Id, values: &Record) {
+			self.push("record", |fields| values.record(fields));
+		}
+
+		fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+		fn event(&self, event: &Event) {
+			self.push("event", |fields| event.record(fields));
+		}
+
+		fn enter(&self, _span: &Id) {}
+
+		fn exit(&self, _span: &Id) {}
+	}
+
+	let recorder: &'static Recorder = std::boxed::Box::leak(std::boxed::Box::default());
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+
+	tracing::subscriber::with_default(recorder, || {
+		let hash_string = mcf::hash(b"traced", &salt, WorkFactor::EXP4).unwrap();
+		assert_eq!(mcf::verify(b"traced", &hash_string), Ok(true));
+		assert_eq!(mcf::verify(b"other", &hash_string), Ok(false));
+		assert_eq!(mcf::verify(b"a\0", &hash_string), Err(ZeroByte));
+	});
+
+	let lines = recorder.0.lock().unwrap();
+	let events: Vec<_> = lines.iter().filter(|line| line.starts_with("event")).map(|line| line.split(" duration_us=").next().unwrap()).collect();
+	assert_eq!(events, [
+		"event message=bcrypt finished outcome=ok",
+		"event message=bcrypt finished outcome=match",
+		"event message=bcrypt finished outcome=mismatch",
+		"event message=bcrypt finished outcome=error error_code=-2",
+	]);
+	assert_eq!(lines.iter().filter(|line| line.starts_with("span operation=hash cost=4 backend=portable")).count(), 1);
+	assert_eq!(lines.iter().filter(|line| line.starts_with("span operation=verify cost=4 backend=portable")).count(), 3);
+	assert!(lines.iter().any(|line| line == "record outcome=match"));
+}
+
+#[cfg(feature = "password-hash")]
+#[test]
+fn password_hash_traits() {
+	use password_hash::{Error, PasswordHasher, PasswordVerifier};
+	use super::Bcrypt;
+	use super::mcf::{self, HashString};
+
+	let hasher = Bcrypt { work_factor: WorkFactor::EXP4 };
+	let salt = [0x5a; 16];
+	let hash_string: HashString = hasher.hash_password_with_salt(b"traits", &salt).unwrap();
+	assert_eq!(hash_string, mcf::hash(b"traits", &Salt::from_bytes(&salt), WorkFactor::EXP4).unwrap());
+	assert!(hash_string.starts_with("$2b$04$"));
+
+	assert_eq!(hasher.verify_password(b"traits", &hash_string), Ok(()));
+	assert_eq!(Bcrypt { work_factor: WorkFactor::EXP5 }.verify_password(b"traits", &hash_string), Ok(()));
+	assert_eq!(hasher.verify_password(b"other", &hash_string), Err(Error::PasswordInvalid));
+	assert_eq!(hasher.verify_password(b"a\0", &hash_string), Err(Error::PasswordInvalid));
+
+	assert_eq!(hasher.hash_password_with_salt(b"traits", &salt[..15]).map(|_| ()), Err(Error::SaltInvalid));
+	assert_eq!(hasher.hash_password_with_salt(&[b'k'; 73], &salt).map(|_| ()), Err(Error::PasswordInvalid));
+
+	let verifier: &dyn PasswordVerifier<HashString> = &hasher;
+	assert_eq!(verifier.verify_password(b"traits", &hash_string), Ok(()));
+
+	// Stored strings, including the older versions, verify without converting them first.
+	let verifier: &dyn PasswordVerifier<str> = &hasher;
+	assert_eq!(verifier.verify_password(b"traits", &hash_string), Ok(()));
+	assert_eq!(verifier.verify_password(b"Kk4DQuMMfZL9o", "$2a$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"), Ok(()));
+	assert_eq!(verifier.verify_password(b"Kk4DQuMMfZL9o", "$2y$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"), Ok(()));
+	assert_eq!(verifier.verify_password(b"other", "$2y$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"), Err(Error::PasswordInvalid));
+	assert_eq!(verifier.verify_password(b"traits", "$argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHQ$aGFzaGhhc2hoYXNo"), Err(Error::Algorithm));
+	assert_eq!(verifier.verify_password(b"traits", "$2b$04$short"), Err(Error::EncodingInvalid));
+	assert_eq!(verifier.verify_password(b"traits", "$2x$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"), Err(Error::Algorithm));
+	assert_eq!(verifier.verify_password(b"traits", "$2b$32$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"), Err(Error::ParamInvalid { name: "cost" }));
+	assert_eq!(verifier.verify_password(b"traits", "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YE!"), Err(Error::EncodingInvalid));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_salt() {
+	use serde::{Deserialize, Deserializer, Serialize, Serializer};
+	use serde_test::{Configure, Token, assert_de_tokens_error, assert_tokens};
+	use std::string::ToString;
+	use std::vec::Vec;
+	use super::mcf::ParseError;
+
+	/// A salt compared by its bytes, which `Salt` itself doesn’t implement.
+	#[derive(Clone, Debug)]
+	struct Compared(Salt);
+
+	impl PartialEq for Compared {
+		fn eq(&self, other: &Self) -> bool {
+			self.0.to_bytes() == other.0.to_bytes()
+		}
+	}
+
+	impl Serialize for Compared {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			self.0.serialize(serializer)
+		}
+	}
+
+	impl<'de> Deserialize<'de> for Compared {
+		fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+			Salt::deserialize(deserializer).map(Compared)
+		}
+	}
+
+	let salt = Compared(Salt::from_bytes(b"\x79\x76\x2b\xe9\x97\x0f\x5b\xe7\x3a\xc7\x7c\x0e\x4f\x0a\x38\x51"));
+	assert_tokens(&salt.clone().readable(), &[Token::Str("cVWp4XaNU8a4v1uMRum2SO")]);
+
+	let mut tokens = std::vec![Token::Tuple { len: 16 }];
+	tokens.extend(salt.0.to_bytes().iter().map(|&byte| Token::U8(byte)));
+	tokens.push(Token::TupleEnd);
+	assert_tokens(&salt.compact(), &tokens);
+
+	assert_de_tokens_error::<serde_test::Readable<Salt>>(&[Token::Str("cVWp4XaNU8a4v1uMRum2S")], &ParseError::Length.to_string());
+	assert_de_tokens_error::<serde_test::Readable<Salt>>(&[Token::Str("cVWp4XaNU8a4v1uMRum2SP")], &ParseError::NonCanonical.to_string());
+	assert_de_tokens_error::<serde_test::Readable<Salt>>(&[Token::U32(4)], "invalid type: integer `4`, expected a bcrypt salt");
+
+	let short: Vec<_> = tokens[..16].iter().copied().chain([Token::TupleEnd]).collect();
+	assert_de_tokens_error::<serde_test::Compact<Salt>>(&short, "invalid length 15, expected 16 bytes");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_work_factor() {
+	use serde_test::{Token, assert_de_tokens, assert_de_tokens_error, assert_tokens};
+
+	assert_tokens(&WorkFactor::EXP4, &[Token::U32(4)]);
+	assert_tokens(&WorkFactor::EXP31, &[Token::U32(31)]);
+	assert_de_tokens(&WorkFactor::EXP12, &[Token::U8(12)]);
+	assert_de_tokens_error::<WorkFactor>(&[Token::U32(3)], "invalid value: integer `3`, expected a bcrypt work factor from 4 to 31");
+	assert_de_tokens_error::<WorkFactor>(&[Token::U32(32)], "invalid value: integer `32`, expected a bcrypt work factor from 4 to 31");
+	assert_de_tokens_error::<WorkFactor>(&[Token::I32(-1)], "invalid value: integer `-1`, expected u32");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_hash_string() {
+	use serde_test::{Configure, Readable, Token, assert_de_tokens_error, assert_tokens};
+	use std::string::ToString;
+	use super::mcf::{HashString, ParseError};
+
+	let hash_string = HashString::parse("$2y$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	assert_tokens(&hash_string.readable(), &[Token::Str("$2y$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm")]);
+
+	let mut tokens = std::vec![Token::Tuple { len: 41 }];
+	tokens.extend(hash_string.to_compact().iter().map(|&byte| Token::U8(byte)));
+	tokens.push(Token::TupleEnd);
+	assert_tokens(&hash_string.compact(), &tokens);
+
+	tokens[2] = Token::U8(32);
+	assert_de_tokens_error::<serde_test::Compact<HashString>>(&tokens, &ParseError::WorkFactor.to_string());
+
+	assert_de_tokens_error::<Readable<HashString>>(&[Token::Str("$2x$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm")], &ParseError::Version.to_string());
+	assert_de_tokens_error::<Readable<HashString>>(&[Token::Str("$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEn")], &ParseError::NonCanonical.to_string());
+	assert_de_tokens_error::<Readable<HashString>>(&[Token::Str("")], &ParseError::Length.to_string());
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary_values() {
+	use arbitrary::{Arbitrary, Unstructured};
+	use super::KeyPolicy;
+
+	let mut u = Unstructured::new(&[0x5a; 16]);
+	assert_eq!(Salt::arbitrary(&mut u).unwrap().to_bytes(), [0x5a; 16]);
+	assert!(u.is_empty());
+
+	// Every byte gives a valid work factor, and together they give all of them.
+	let mut seen = [false; 32];
+
+	for byte in 0..=u8::MAX {
+		let work_factor = WorkFactor::arbitrary(&mut Unstructured::new(&[byte])).unwrap();
+		seen[work_factor.log_rounds() as usize] = true;
+	}
+
+	assert_eq!(seen, core::array::from_fn(|i| i >= 4));
+	assert_eq!(WorkFactor::arbitrary(&mut Unstructured::new(&[])).unwrap(), WorkFactor::EXP4);
+
+	let policies: std::vec::Vec<_> = [[0, 0], [1, 0], [0, 1], [1, 1]].iter().map(|bytes| KeyPolicy::arbitrary(&mut Unstructured::new(bytes)).unwrap()).collect();
+	assert_eq!(policies, [
+		KeyPolicy::DEFAULT,
+		KeyPolicy { strip_newline: true, ..KeyPolicy::DEFAULT },
+		KeyPolicy { reject_empty: true, ..KeyPolicy::DEFAULT },
+		KeyPolicy { strip_newline: true, reject_empty: true },
+	]);
+}
+
+#[cfg(feature = "proptest")]
+#[test]
+fn proptest_strategies() {
+	use proptest::prelude::*;
+	use proptest::test_runner::TestRunner;
+	use super::KeyPolicy;
+	use super::mcf::{HashString, ParseError};
+	use super::strategies::*;
+
+	let mut runner = TestRunner::deterministic();
+
+	runner.run(&work_factor(WorkFactor::EXP6), |work_factor| {
+		prop_assert!((4..=6).contains(&work_factor.log_rounds()));
+		Ok(())
+	}).unwrap();
+
+	runner.run(&key(), |key| {
+		prop_assert_eq!(KeyPolicy::DEFAULT.prepare(&key), Ok(&key[..]));
+		Ok(())
+	}).unwrap();
+
+	runner.run(&rejected_key(), |key| {
+		prop_assert!(KeyPolicy::DEFAULT.prepare(&key).is_err());
+		Ok(())
+	}).unwrap();
+
+	runner.run(&hash_string(WorkFactor::EXP5), |hash_string| {
+		prop_assert_eq!(HashString::parse(&hash_string), Ok(hash_string));
+		prop_assert!(hash_string.work_factor() <= WorkFactor::EXP5);
+		Ok(())
+	}).unwrap();
+
+	for error in [ParseError::Length, ParseError::Version, ParseError::WorkFactor, ParseError::Encoding, ParseError::NonCanonical] {
+		runner.run(&corrupted_hash_string(error, WorkFactor::EXP31), |s| {
+			prop_assert_eq!(HashString::parse(&s), Err(error));
+			Ok(())
+		}).unwrap();
+	}
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn postcard_sizes() {
+	use super::mcf::HashString;
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash_string = HashString::parse("$2b$12$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	let mut buffer = [0_u8; 64];
+
+	assert_eq!(postcard::to_slice(&salt, &mut buffer).unwrap(), &[0x5a; 16]);
+	assert_eq!(postcard::from_bytes::<Salt>(&[0x5a; 16]).unwrap().to_bytes(), [0x5a; 16]);
+
+	for work_factor in [WorkFactor::EXP4, WorkFactor::EXP31] {
+		assert_eq!(postcard::to_slice(&work_factor, &mut buffer).unwrap(), &[work_factor.log_rounds() as u8]);
+	}
+
+	assert!(postcard::from_bytes::<WorkFactor>(&[32]).is_err());
+
+	let encoded = postcard::to_slice(&hash_string, &mut buffer).unwrap();
+	assert_eq!(encoded, &hash_string.to_compact());
+	assert_eq!(postcard::from_bytes::<HashString>(encoded), Ok(hash_string));
+	assert!(postcard::from_bytes::<HashString>(&[0; 41]).is_err());
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn borsh_encodings() {
+	use borsh::{BorshDeserialize, to_vec};
+	use super::mcf::HashString;
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash_string = HashString::parse("$2b$12$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+
+	assert_eq!(to_vec(&salt).unwrap(), [0x5a; 16]);
+	assert_eq!(Salt::try_from_slice(&[0x5a; 16]).unwrap().to_bytes(), [0x5a; 16]);
+
+	assert_eq!(to_vec(&WorkFactor::EXP12).unwrap(), [12]);
+	assert_eq!(WorkFactor::try_from_slice(&[31]).unwrap(), WorkFactor::EXP31);
+	assert_eq!(WorkFactor::try_from_slice(&[3]).unwrap_err().kind(), borsh::io::ErrorKind::InvalidData);
+
+	let encoded = to_vec(&hash_string).unwrap();
+	assert_eq!(encoded, hash_string.to_compact());
+	assert_eq!(HashString::try_from_slice(&encoded).unwrap(), hash_string);
+	assert_eq!(HashString::try_from_slice(&[0; 41]).unwrap_err().kind(), borsh::io::ErrorKind::InvalidData);
+	assert!(HashString::try_from_slice(&encoded[..40]).is_err());
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn json_schemas() {
+	use super::mcf::HashString;
+	use serde_json::json;
+
+	let work_factor = schemars::schema_for!(WorkFactor);
+	assert_eq!(work_factor.get("type"), Some(&json!("integer")));
+	assert_eq!(work_factor.get("minimum"), Some(&json!(WorkFactor::EXP4.log_rounds())));
+	assert_eq!(work_factor.get("maximum"), Some(&json!(WorkFactor::EXP31.log_rounds())));
+
+	// serde serializes both as the strings the patterns describe.
+	let hash_string = HashString::parse("$2b$12$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	assert_eq!(serde_json::to_value(hash_string).unwrap(), json!(hash_string.as_str()));
+	assert_eq!(serde_json::to_value(hash_string.salt()).unwrap(), json!("cVWp4XaNU8a4v1uMRum2SO"));
+
+	let salt = schemars::schema_for!(Salt);
+	assert_eq!(salt.get("type"), Some(&json!("string")));
+	assert_eq!(salt.get("pattern"), Some(&json!("^[./A-Za-z0-9]{21}[.Oeu]$")));
+
+	let schema = schemars::schema_for!(HashString);
+	assert_eq!(schema.get("type"), Some(&json!("string")));
+	assert!(schema.get("pattern").and_then(|pattern| pattern.as_str()).unwrap().starts_with("^\\$2[aby]\\$"));
+}
+
+// The error paths throw JavaScript errors, which only work on wasm32.
+#[cfg(feature = "wasm")]
+#[test]
+fn wasm_hash_and_verify() {
+	let hash_string = super::wasm::hash("password", 4).unwrap();
+	assert!(hash_string.starts_with("$2b$04$"));
+	assert!(super::wasm::verify("password", &hash_string).unwrap());
+	assert!(!super::wasm::verify("Password", &hash_string).unwrap());
+	assert_ne!(super::wasm::hash("password", 4).unwrap(), hash_string);
+	assert!(!super::wasm::verify("password", "$2y$04$cVWp4XaNU8a4v1uMRum2SOhW8EWE/.KjN6cgbM.7Ep4iFDNTNZu1q").unwrap());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn async_verifier() {
+	use super::{AsyncVerifier, AsyncVerify, BcryptError};
+	use super::mcf::{self, HashString};
+	use core::num::NonZeroUsize;
+	use std::sync::Arc;
+	use std::time::Duration;
+
+	let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+	let slow = mcf::hash(b"password", &Salt::from_bytes(&[0; 16]), WorkFactor::exp(10).unwrap()).unwrap();
+	let fast = HashString::parse("$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+
+	runtime.block_on(async {
+		let verifier = Arc::new(AsyncVerifier::new(NonZeroUsize::new(1).unwrap(), Duration::from_millis(1)));
+		assert_eq!(verifier.verify(b"password", &fast).await, Ok(AsyncVerify::Mismatch));
+		assert_eq!(verifier.verify(&[b'a'; 73], &fast).await, Err(BcryptError::Length));
+
+		// The first verification holds the only slot while it hashes, so the second gives up.
+		let first = tokio::spawn({
+			let verifier = Arc::clone(&verifier);
+			async move { verifier.verify(b"password", &slow).await }
+		});
+		tokio::task::yield_now().await;
+		assert_eq!(verifier.available(), 0);
+		assert_eq!(verifier.verify(b"password", &fast).await, Ok(AsyncVerify::QueueTimeout));
+
+		assert_eq!(first.await.unwrap(), Ok(AsyncVerify::Match));
+		assert_eq!(verifier.available(), 1);
+	});
+}
+
+// Each writes what its `core::fmt` impl does.
+#[cfg(feature = "ufmt")]
+#[test]
+fn ufmt_matches_fmt() {
+	use super::{BcryptError, InvalidWorkFactor, KeyBufError};
+	use super::mcf::{HashString, ParseError, Setting, Version, VerifyOutcome};
+	use core::convert::Infallible;
+	use std::format;
+	use std::string::String;
+
+	// ufmt only implements `uWrite` for `String` with its `std` feature.
+	struct Written(String);
+
+	impl ufmt::uWrite for Written {
+		type Error = Infallible;
+
+		fn write_str(&mut self, s: &str) -> Result<(), Infallible> {
+			self.0.push_str(s);
+			Ok(())
+		}
+	}
+
+	fn display<T: ufmt::uDisplay + ?Sized>(value: &T) -> String {
+		let mut written = Written(String::new());
+		ufmt::uwrite!(written, "{}", value).unwrap();
+		written.0
+	}
+
+	fn debug<T: ufmt::uDebug + ?Sized>(value: &T) -> String {
+		let mut written = Written(String::new());
+		ufmt::uwrite!(written, "{:?}", value).unwrap();
+		written.0
+	}
+
+	let hash_string = HashString::parse("$2b$12$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	let setting = Setting::parse("$2b$12$cVWp4XaNU8a4v1uMRum2SO").unwrap();
+
+	for err in [BcryptError::Length, BcryptError::ZeroByte, BcryptError::Empty] {
+		assert_eq!((display(&err), debug(&err)), (format!("{}", err), format!("{:?}", err)));
+	}
+
+	for err in [ParseError::Length, ParseError::Version, ParseError::WorkFactor, ParseError::Encoding, ParseError::NonCanonical] {
+		assert_eq!((display(&err), debug(&err)), (format!("{}", err), format!("{:?}", err)));
+	}
+
+	for err in [KeyBufError::Full, KeyBufError::ZeroByte] {
+		assert_eq!((display(&err), debug(&err)), (format!("{}", err), format!("{:?}", err)));
+	}
+
+	#[cfg(feature = "bcrypt-pbkdf")]
+	assert_eq!((display(&super::InvalidPbkdfParameters), debug(&super::InvalidPbkdfParameters)), (format!("{}", super::InvalidPbkdfParameters), format!("{:?}", super::InvalidPbkdfParameters)));
+
+	assert_eq!((display(&InvalidWorkFactor(32)), debug(&InvalidWorkFactor(32))), (format!("{}", InvalidWorkFactor(32)), format!("{:?}", InvalidWorkFactor(32))));
+	assert_eq!(display(&WorkFactor::EXP12), "12");
+	assert_eq!(debug(&WorkFactor::EXP12), format!("{:?}", WorkFactor::EXP12));
+	assert_eq!((display(&hash_string), debug(&hash_string)), (format!("{}", hash_string), format!("{:?}", hash_string)));
+	assert_eq!((display(&setting), debug(&setting)), (format!("{}", setting), format!("{:?}", setting)));
+	assert_eq!(display(&hash_string.salt()), format!("{}", hash_string.salt()));
+
+	for outcome in [
+		VerifyOutcome::Match,
+		VerifyOutcome::Mismatch,
+		VerifyOutcome::NeedsRehash { version: Version::V2a, work_factor: WorkFactor::EXP4 },
+		VerifyOutcome::LegacyQuirkMatch { policy: 1 },
+		VerifyOutcome::TimedOut,
+	] {
+		assert_eq!(debug(&outcome), format!("{:?}", outcome));
+	}
+}
+
+#[cfg(feature = "sqlx")]
+#[test]
+fn sqlx_columns() {
+	use super::mcf::HashString;
+	use sqlx::{Connection, SqliteConnection};
+
+	let hash_string = HashString::parse("$2b$12$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+	runtime.block_on(async {
+		let mut db = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+		sqlx::query("CREATE TABLE users (hash TEXT, compact BLOB)").execute(&mut db).await.unwrap();
+		sqlx::query("INSERT INTO users VALUES (?, ?)").bind(hash_string).bind(&hash_string.to_compact()[..]).execute(&mut db).await.unwrap();
+
+		let (text, compact): (HashString, HashString) = sqlx::query_as("SELECT hash, compact FROM users").fetch_one(&mut db).await.unwrap();
+		assert_eq!((text, compact), (hash_string, hash_string));
+
+		sqlx::query("UPDATE users SET hash = 'not a hash string', compact = x'00'").execute(&mut db).await.unwrap();
+		assert!(sqlx::query_scalar::<_, HashString>("SELECT hash FROM users").fetch_one(&mut db).await.is_err());
+		assert!(sqlx::query_scalar::<_, HashString>("SELECT compact FROM users").fetch_one(&mut db).await.is_err());
+	});
+}
+
+#[cfg(feature = "diesel")]
+#[test]
+fn diesel_columns() {
+	use super::mcf::HashString;
+	use diesel::prelude::*;
+	use diesel::sql_types::{Binary, Text};
+
+	let hash_string = HashString::parse("$2b$12$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	let mut db = SqliteConnection::establish(":memory:").unwrap();
+
+	let text: HashString = diesel::select(hash_string.into_sql::<Text>()).get_result(&mut db).unwrap();
+	assert_eq!(text, hash_string);
+
+	let compact: HashString = diesel::select(hash_string.to_compact().to_vec().into_sql::<Binary>()).get_result(&mut db).unwrap();
+	assert_eq!(compact, hash_string);
+
+	assert!(diesel::select("not a hash string".into_sql::<Text>()).get_result::<HashString>(&mut db).is_err());
+	assert!(diesel::select(std::vec![0_u8].into_sql::<Binary>()).get_result::<HashString>(&mut db).is_err());
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn python_module() {
+	use pyo3::prelude::*;
+	use pyo3::types::PyDict;
+
+	Python::initialize();
+	Python::attach(|py| {
+		let globals = PyDict::new(py);
+		globals.set_item("bcrypt_only", pyo3::wrap_pymodule!(super::python::bcrypt_only)(py)).unwrap();
+		let script = std::ffi::CString::new(r#"
+salt = bcrypt_only.gensalt(4)
+assert salt.startswith(b"$2b$04$") and len(salt) == 29
+hashed = bcrypt_only.hashpw(b"password", salt)
+assert hashed.startswith(salt) and len(hashed) == 60
+assert bcrypt_only.hashpw(b"password", hashed) == hashed
+assert bcrypt_only.checkpw(b"password", hashed)
+assert not bcrypt_only.checkpw(b"Password", hashed)
+assert bcrypt_only.gensalt(prefix=b"2a").startswith(b"$2a$12$")
+assert bcrypt_only.hashpw(b"password", b"$2y$04$cVWp4XaNU8a4v1uMRum2SO").startswith(b"$2y$04$")
+
+for call, error in [
+	(lambda: bcrypt_only.gensalt(3), ValueError),
+	(lambda: bcrypt_only.gensalt(prefix=b"2x"), ValueError),
+	(lambda: bcrypt_only.hashpw(b"pass\0word", salt), ValueError),
+	(lambda: bcrypt_only.hashpw(b"a" * 73, salt), ValueError),
+	(lambda: bcrypt_only.hashpw(b"password", b"$2b$04$"), ValueError),
+	(lambda: bcrypt_only.checkpw(b"password", b"not a hash string"), ValueError),
+	(lambda: bcrypt_only.hashpw("password", salt), TypeError),
+]:
+	try:
+		call()
+	except error:
+		pass
+	else:
+		raise AssertionError("no exception")
+"#).unwrap();
+		py.run(&script, Some(&globals), None).unwrap();
+	});
+}
+
+#[cfg(feature = "uniffi")]
+#[test]
+fn uniffi_api() {
+	use super::uniffi_api::{HashError, calibrate, hash, needs_rehash, verify};
+
+	let hash_string = hash("password".into(), 4).unwrap();
+	assert!(hash_string.starts_with("$2b$04$"));
+	assert!(verify("password".into(), hash_string.clone()).unwrap());
+	assert!(!verify("Password".into(), hash_string.clone()).unwrap());
+	assert!(verify("password".into(), "$2y$04$cVWp4XaNU8a4v1uMRum2SOhW8EWE/.KjN6cgbM.7Ep4iFDNTNZu1q".into()).is_ok());
+
+	assert!(!needs_rehash(hash_string.clone(), 4).unwrap());
+	assert!(needs_rehash(hash_string.clone(), 5).unwrap());
+	assert!(needs_rehash(hash_string.replacen("$2b$", "$2a$", 1), 4).unwrap());
+
+	assert!(matches!(hash("password".into(), 3), Err(HashError::InvalidCost)));
+	assert!(matches!(hash("pass\0word".into(), 4), Err(HashError::InvalidPassword(_))));
+	assert!(matches!(verify("password".into(), "not a hash string".into()), Err(HashError::InvalidHashString(_))));
+	assert!(matches!(needs_rehash(hash_string, 32), Err(HashError::InvalidCost)));
+	assert!((4..=31).contains(&calibrate(1).unwrap()));
+}
+
+#[cfg(feature = "node")]
+#[test]
+fn node_addon() {
+	use napi::bindgen_prelude::Either;
+	use super::node::{Rejection, compare_with, generate_setting, hash_with};
+
+	let salt = generate_setting(4).unwrap();
+	assert!(salt.as_str().starts_with("$2b$04$"));
+	assert!(matches!(generate_setting(3), Err(Rejection::InvalidRounds)));
+
+	let hash_string = hash_with(b"password", &Either::B(salt.as_str().into())).unwrap();
+	assert!(hash_string.as_str().starts_with(salt.as_str()));
+	assert_eq!(hash_with(b"password", &Either::B(hash_string.as_str().into())).unwrap(), hash_string);
+	assert!(hash_with(b"password", &Either::A(4)).unwrap().as_str().starts_with("$2b$04$"));
+	assert!(matches!(hash_with(b"password", &Either::A(32)), Err(Rejection::InvalidRounds)));
+	assert!(matches!(hash_with(b"password", &Either::B("$2b$04$".into())), Err(Rejection::InvalidSalt)));
+	assert!(matches!(hash_with(b"pass\0word", &Either::A(4)), Err(Rejection::Password(_))));
+
+	assert!(compare_with(b"password", hash_string.as_str()).unwrap());
+	assert!(!compare_with(b"Password", hash_string.as_str()).unwrap());
+	assert!(!compare_with(b"password", "not a hash string").unwrap());
+	assert!(matches!(compare_with(&[b'a'; 73], hash_string.as_str()), Err(Rejection::Password(_))));
+}
+
+#[cfg(feature = "hybrid-array")]
+#[test]
+fn hybrid_arrays() {
+	use hybrid_array::{Array, ArraySize};
+	use super::{HashArray, SaltArray};
+
+	/// Stands in for code generic over output sizes.
+	fn first<U: ArraySize>(bytes: &Array<u8, U>) -> u8 {
+		bytes[0]
+	}
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash = bcrypt(b"arrays", &salt, WorkFactor::EXP4).unwrap();
+
+	assert_eq!(first(&HashArray::from(hash)), hash[0]);
+	assert_eq!(HashArray::cast_from_core(&hash).as_slice(), &hash);
+	assert_eq!(SaltArray::from(&salt), Array([0x5a; 16]));
+	assert_eq!(Salt::from(&SaltArray::from(&salt)).to_bytes(), salt.to_bytes());
+}
+
+#[test]
+fn capabilities() {
+	use super::{Capabilities, capabilities};
+
+	let caps = capabilities();
+	assert!(caps.contains(Capabilities::BACKEND_PORTABLE));
+	assert_eq!(caps.contains(Capabilities::VERSION_2A | Capabilities::VERSION_2B | Capabilities::VERSION_2Y), cfg!(feature = "mcf"));
+	assert_eq!(caps.contains(Capabilities::VERSION_2A) || caps.contains(Capabilities::VERSION_2B) || caps.contains(Capabilities::VERSION_2Y), cfg!(feature = "mcf"));
+	assert_eq!(Capabilities::from_bits(caps.bits()), caps);
+	assert!(!Capabilities::empty().contains(Capabilities::VERSION_2A));
+
+	// Every feature of the library, with the flag that reports it.
+	let features = [
+		("mcf", "MCF", Capabilities::MCF, cfg!(feature = "mcf")),
+		("std", "STD", Capabilities::STD, cfg!(feature = "std")),
+		("capi", "CAPI", Capabilities::CAPI, cfg!(feature = "capi")),
+		("compact-errors", "COMPACT_ERRORS", Capabilities::COMPACT_ERRORS, cfg!(feature = "compact-errors")),
+		("bcrypt-pbkdf", "BCRYPT_PBKDF", Capabilities::BCRYPT_PBKDF, cfg!(feature = "bcrypt-pbkdf")),
+		("derived-salt", "DERIVED_SALT", Capabilities::DERIVED_SALT, cfg!(feature = "derived-salt")),
+		("unstable", "UNSTABLE", Capabilities::UNSTABLE, cfg!(feature = "unstable")),
+		("round-count", "ROUND_COUNT", Capabilities::ROUND_COUNT, cfg!(feature = "round-count")),
+		("tracing", "TRACING", Capabilities::TRACING, cfg!(feature = "tracing")),
+		("password-hash", "PASSWORD_HASH", Capabilities::PASSWORD_HASH, cfg!(feature = "password-hash")),
+		("serde", "SERDE", Capabilities::SERDE, cfg!(feature = "serde")),
+		("arbitrary", "ARBITRARY", Capabilities::ARBITRARY, cfg!(feature = "arbitrary")),
+		("proptest", "PROPTEST", Capabilities::PROPTEST, cfg!(feature = "proptest")),
+		("hybrid-array", "HYBRID_ARRAY", Capabilities::HYBRID_ARRAY, cfg!(feature = "hybrid-array")),
+		("borsh", "BORSH", Capabilities::BORSH, cfg!(feature = "borsh")),
+		("schemars", "SCHEMARS", Capabilities::SCHEMARS, cfg!(feature = "schemars")),
+		("wasm", "WASM", Capabilities::WASM, cfg!(feature = "wasm")),
+		("tokio", "TOKIO", Capabilities::TOKIO, cfg!(feature = "tokio")),
+		("ufmt", "UFMT", Capabilities::UFMT, cfg!(feature = "ufmt")),
+		("sqlx", "SQLX", Capabilities::SQLX, cfg!(feature = "sqlx")),
+		("diesel", "DIESEL", Capabilities::DIESEL, cfg!(feature = "diesel")),
+		("python", "PYTHON", Capabilities::PYTHON, cfg!(feature = "python")),
+		("uniffi", "UNIFFI", Capabilities::UNIFFI, cfg!(feature = "uniffi")),
+		("node", "NODE", Capabilities::NODE, cfg!(feature = "node")),
+		("test-support", "TEST_SUPPORT", Capabilities::TEST_SUPPORT, cfg!(feature = "test-support")),
+		("size-opt", "SIZE_OPT", Capabilities::SIZE_OPT, cfg!(feature = "size-opt")),
+		("ram-tables", "RAM_TABLES", Capabilities::RAM_TABLES, cfg!(feature = "ram-tables")),
+		("runtime-tables", "RUNTIME_TABLES", Capabilities::RUNTIME_TABLES, cfg!(feature = "runtime-tables")),
+		("c-reference", "BACKEND_C_REFERENCE", Capabilities::BACKEND_C_REFERENCE, cfg!(feature = "c-reference")),
+		("system-crypt", "BACKEND_SYSTEM_CRYPT", Capabilities::BACKEND_SYSTEM_CRYPT, cfg!(feature = "system-crypt")),
+		("bcrypt-crate", "BACKEND_BCRYPT_CRATE", Capabilities::BACKEND_BCRYPT_CRATE, cfg!(feature = "bcrypt-crate")),
+	];
+
+	// Features that only build binaries, leaving the library as it is.
+	let binary_features = ["default", "cli", "vector-gen"];
+
+	let manifest = include_str!("../Cargo.toml");
+	let feature_section = manifest.split("\n[features]\n").nth(1).unwrap().split("\n[").next().unwrap();
+
+	for line in feature_section.lines() {
+		if let Some((name, _)) = line.split_once(" = ") {
+			assert!(features.iter().any(|&(feature, ..)| feature == name) || binary_features.contains(&name), "feature {:?} has no capability flag", name);
+		}
+	}
+
+	let header = include_str!("../capi/bcrypt_only.h");
+	let others = [
+		("VERSION_2A", Capabilities::VERSION_2A),
+		("VERSION_2B", Capabilities::VERSION_2B),
+		("VERSION_2Y", Capabilities::VERSION_2Y),
+		("BACKEND_PORTABLE", Capabilities::BACKEND_PORTABLE),
+	];
+
+	for (feature, name, flag, enabled) in features {
+		assert_eq!(caps.contains(flag), enabled, "{}", feature);
+		assert!(header.contains(&std::format!("\n#define BCRYPT_ONLY_CAP_{} {:#x}\n", name, flag.bits())), "{}", name);
+	}
+
+	for (name, flag) in others {
+		assert!(header.contains(&std::format!("\n#define BCRYPT_ONLY_CAP_{} {:#x}\n", name, flag.bits())), "{}", name);
+	}
+}
+
+// Known answers at several work factors, through each high-level API, so a work factor that’s dropped or miscounted on the way to the core shows up as a wrong hash.
+#[cfg(feature = "mcf")]
+#[cfg_attr(feature = "capi", allow(unsafe_code))]
+#[test]
+fn work_factor_known_answers() {
+	use super::mcf::{self, HashString};
+
+	let vectors: [(&[u8], &str); 6] = [
+		(b"Kk4DQuMMfZL9o", "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"),
+		(b"U*U", "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW"),
+		(b"", "$2a$06$DCq7YPn5Rq63x1Lad4cll.TV4S6ytwfsfvkgY8jIucDrjc8deX1s."),
+		(b"a", "$2a$06$m0CrhHm10qJ3lXRY.5zDGO3rS2KdeeWLuGmsfGlMfOxih58VYVfxe"),
+		(b"", "$2a$08$HqWuK6/Ng6sg9gQzbLrgb.Tl.ZHfXLhvt/SgVyWhQqgqcZ7ZuUtye"),
+		(b"abc", "$2a$08$Ro0CUfOqk6cXEKf3dyaM7OhSCvnwM9s4wIX9JeLapehKK5YdLxKcm"),
+	];
+
+	for &(key, expected) in &vectors {
+		let expected = HashString::parse(expected).unwrap();
+		let hash_string = mcf::hash(key, &expected.salt(), expected.work_factor()).unwrap();
+
+		assert_eq!(hash_string.work_factor(), expected.work_factor());
+		assert_eq!(hash_string.hash(), expected.hash());
+		assert_eq!(mcf::verify(key, &expected), Ok(true));
+
+		#[cfg(feature = "capi")]
+		{
+			use super::capi::{BCRYPT_ONLY_HASH_BUFFER_SIZE, BCRYPT_ONLY_OK, bcrypt_only_hash};
+
+			let mut setting = [0_u8; mcf::SETTING_SIZE + 1];
+			setting[..mcf::SETTING_SIZE].copy_from_slice(&expected.as_str().as_bytes()[..mcf::SETTING_SIZE]);
+			let mut hash_string = [0_u8; BCRYPT_ONLY_HASH_BUFFER_SIZE];
+
+			// SAFETY: the buffer has the documented size, and the setting is NUL-terminated.
+			unsafe {
+				assert_eq!(bcrypt_only_hash(key.as_ptr(), key.len(), setting.as_ptr().cast(), hash_string.as_mut_ptr().cast()), BCRYPT_ONLY_OK);
+			}
+
+			assert_eq!(std::ffi::CStr::from_bytes_until_nul(&hash_string).unwrap().to_str(), Ok(expected.as_str()));
+		}
+	}
+}
+
+#[cfg(feature = "capi")]
+#[allow(unsafe_code)]
+#[test]
+fn capi() {
+	use super::capi::*;
+	use std::ffi::CStr;
+
+	let mut setting = [0_u8; BCRYPT_ONLY_SETTING_BUFFER_SIZE];
+	let mut hash_string = [0_u8; BCRYPT_ONLY_HASH_BUFFER_SIZE];
+	let random = *b"\x79\x76\x2b\xe9\x97\x0f\x5b\xe7\x3a\xc7\x7c\x0e\x4f\x0a\x38\x51";
+
+	// SAFETY: the buffers have the documented sizes, and the strings are NUL-terminated.
+	unsafe {
+		assert_eq!(bcrypt_only_gensalt(4, random.as_ptr(), setting.as_mut_ptr().cast()), BCRYPT_ONLY_OK);
+		assert_eq!(CStr::from_bytes_until_nul(&setting).unwrap().to_str(), Ok("$2b$04$cVWp4XaNU8a4v1uMRum2SO"));
+
+		assert_eq!(bcrypt_only_hash(b"Kk4DQuMMfZL9o".as_ptr(), 13, setting.as_ptr().cast(), hash_string.as_mut_ptr().cast()), BCRYPT_ONLY_OK);
+		assert_eq!(CStr::from_bytes_until_nul(&hash_string).unwrap().to_str(), Ok("$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"));
+
+		assert_eq!(bcrypt_only_verify(b"Kk4DQuMMfZL9o".as_ptr(), 13, hash_string.as_ptr().cast()), 1);
+		assert_eq!(bcrypt_only_verify(b"Kk4DQuMMfZL9O".as_ptr(), 13, hash_string.as_ptr().cast()), 0);
+		assert_eq!(bcrypt_only_verify(b"a\0".as_ptr(), 2, hash_string.as_ptr().cast()), BCRYPT_ONLY_ERR_KEY_ZERO_BYTE);
+		assert_eq!(bcrypt_only_verify(core::ptr::null(), 0, b"$2b$04$\0".as_ptr().cast()), BCRYPT_ONLY_ERR_PARSE);
+		assert_eq!(bcrypt_only_verify(core::ptr::null(), 1, hash_string.as_ptr().cast()), BCRYPT_ONLY_ERR_NULL);
+
+		assert_eq!(bcrypt_only_gensalt(32, random.as_ptr(), setting.as_mut_ptr().cast()), BCRYPT_ONLY_ERR_WORK_FACTOR);
+		assert_eq!(bcrypt_only_hash([0xaa; 73].as_ptr(), 73, b"$2y$04$cVWp4XaNU8a4v1uMRum2SO\0".as_ptr().cast(), hash_string.as_mut_ptr().cast()), BCRYPT_ONLY_ERR_KEY_LENGTH);
+		assert_eq!(bcrypt_only_hash(core::ptr::null(), 0, b"$2y$04$cVWp4XaNU8a4v1uMRum2SO\0".as_ptr().cast(), hash_string.as_mut_ptr().cast()), BCRYPT_ONLY_OK);
+		assert!(hash_string.starts_with(b"$2y$04$cVWp4XaNU8a4v1uMRum2SO"));
+
+		let mut session = core::mem::MaybeUninit::<Session>::uninit();
+		assert_eq!(bcrypt_only_session_create(session.as_mut_ptr(), b"Kk4DQuMMfZL9o".as_ptr(), 13, setting.as_ptr().cast()), BCRYPT_ONLY_OK);
+		assert_eq!(bcrypt_only_session_finish(session.as_mut_ptr(), hash_string.as_mut_ptr().cast()), BCRYPT_ONLY_ERR_INCOMPLETE);
+		assert_eq!(bcrypt_only_session_step(session.as_mut_ptr(), 15), 0);
+		assert_eq!(bcrypt_only_session_step(session.as_mut_ptr(), 1), 1);
+		assert_eq!(bcrypt_only_session_finish(session.as_mut_ptr(), hash_string.as_mut_ptr().cast()), BCRYPT_ONLY_OK);
+		assert_eq!(CStr::from_bytes_until_nul(&hash_string).unwrap().to_str(), Ok("$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"));
+		bcrypt_only_session_destroy(session.as_mut_ptr());
+	}
+}
+
+// The header is written by hand, so this checks it declares what `capi` defines: every constant with its value, every function with the C types of its Rust signature, and a session the same size.
+#[cfg(feature = "capi")]
+#[test]
+fn capi_header() {
+	use core::ffi::{c_char, c_int, c_uint};
+	use core::mem::{align_of, size_of};
+	use super::capi::*;
+
+	let header = include_str!("../capi/bcrypt_only.h");
+	let constants = [
+		("BCRYPT_ONLY_OK", BCRYPT_ONLY_OK as i64),
+		("BCRYPT_ONLY_ERR_KEY_LENGTH", BCRYPT_ONLY_ERR_KEY_LENGTH as i64),
+		("BCRYPT_ONLY_ERR_KEY_ZERO_BYTE", BCRYPT_ONLY_ERR_KEY_ZERO_BYTE as i64),
+		("BCRYPT_ONLY_ERR_PARSE", BCRYPT_ONLY_ERR_PARSE as i64),
+		("BCRYPT_ONLY_ERR_WORK_FACTOR", BCRYPT_ONLY_ERR_WORK_FACTOR as i64),
+		("BCRYPT_ONLY_ERR_NULL", BCRYPT_ONLY_ERR_NULL as i64),
+		("BCRYPT_ONLY_ERR_INCOMPLETE", BCRYPT_ONLY_ERR_INCOMPLETE as i64),
+		("BCRYPT_ONLY_SETTING_BUFFER_SIZE", BCRYPT_ONLY_SETTING_BUFFER_SIZE as i64),
+		("BCRYPT_ONLY_HASH_BUFFER_SIZE", BCRYPT_ONLY_HASH_BUFFER_SIZE as i64),
+		("BCRYPT_ONLY_SESSION_SIZE", BCRYPT_ONLY_SESSION_SIZE as i64),
+	];
+
+	for (name, value) in constants {
+		assert!(header.contains(&std::format!("\n#define {} {}\n", name, value)), "{}", name);
+	}
+
+	// The capability flags are checked by the `capabilities` test, and the include guard has no value.
+	for line in header.lines().filter(|line| line.starts_with("#define BCRYPT_ONLY_") && !line.starts_with("#define BCRYPT_ONLY_CAP_") && *line != "#define BCRYPT_ONLY_H") {
+		assert!(constants.iter().any(|&(name, _)| line.split(' ').nth(1) == Some(name)), "{:?} isn’t in capi", line);
+	}
+
+	// Each signature is checked against the function by the compiler, and against the header by the test.
+	let _: extern "C" fn() -> u64 = bcrypt_only_capabilities;
+	let _: unsafe extern "C" fn(c_uint, *const u8, *mut c_char) -> c_int = bcrypt_only_gensalt;
+	let _: unsafe extern "C" fn(*const u8, usize, *const c_char, *mut c_char) -> c_int = bcrypt_only_hash;
+	let _: unsafe extern "C" fn(*const u8, usize, *const c_char) -> c_int = bcrypt_only_verify;
+	let _: unsafe extern "C" fn(*mut Session, *const u8, usize, *const c_char) -> c_int = bcrypt_only_session_create;
+	let _: unsafe extern "C" fn(*mut Session, u32) -> c_int = bcrypt_only_session_step;
+	let _: unsafe extern "C" fn(*mut Session, *mut c_char) -> c_int = bcrypt_only_session_finish;
+	let _: unsafe extern "C" fn(*mut Session) = bcrypt_only_session_destroy;
+
+	let prototypes = [
+		"uint64_t bcrypt_only_capabilities(void);",
+		"int bcrypt_only_gensalt(unsigned int log_rounds, const uint8_t *random, char *output);",
+		"int bcrypt_only_hash(const uint8_t *key, size_t key_len, const char *setting, char *output);",
+		"int bcrypt_only_verify(const uint8_t *key, size_t key_len, const char *hash_string);",
+		"int bcrypt_only_session_create(bcrypt_only_session *session, const uint8_t *key, size_t key_len, const char *setting);",
+		"int bcrypt_only_session_step(bcrypt_only_session *session, uint32_t rounds);",
+		"int bcrypt_only_session_finish(bcrypt_only_session *session, char *output);",
+		"void bcrypt_only_session_destroy(bcrypt_only_session *session);",
+	];
+
+	for prototype in prototypes {
+		assert!(header.lines().any(|line| line == prototype), "{}", prototype);
+	}
+
+	assert_eq!(header.lines().filter(|line| line.contains(" bcrypt_only_") && line.ends_with(");")).count(), prototypes.len());
+
+	assert_eq!(BCRYPT_ONLY_SESSION_SIZE, 4608);
+	assert_eq!(size_of::<Session>(), BCRYPT_ONLY_SESSION_SIZE);
+	assert_eq!(align_of::<Session>(), align_of::<u64>());
+	assert!(header.contains("typedef struct {\n\tuint64_t opaque[BCRYPT_ONLY_SESSION_SIZE / 8];\n} bcrypt_only_session;\n"));
+}
+
+#[test]
+fn session() {
+	use super::BcryptState;
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let mut state = BcryptState::new(b"session", &salt, WorkFactor::EXP5).unwrap();
+	assert_eq!(state.remaining_rounds(), 32);
+	assert_eq!(state.run(0), None);
+	assert_eq!(state.run(31), None);
+	assert_eq!(state.remaining_rounds(), 1);
+
+	let hash = state.run(7);
+	assert_eq!(hash, bcrypt(b"session", &salt, WorkFactor::EXP5).ok());
+	assert_eq!(state.remaining_rounds(), 0);
+	assert_eq!(state.run(1), hash);
+
+	assert_eq!(BcryptState::new(b"a\0", &salt, WorkFactor::EXP5).map(|_| ()), Err(ZeroByte));
+}
+
+#[test]
+fn hash_future() {
+	use core::future::Future;
+	use core::pin::Pin;
+	use core::task::{Context, Poll, Waker};
+	use std::sync::Arc;
+	use std::task::Wake;
+	use super::{BcryptState, HashFuture};
+
+	/// A waker that does nothing, since the test polls in a loop anyway.
+	struct NoopWaker;
+
+	impl Wake for NoopWaker {
+		fn wake(self: Arc<Self>) {}
+	}
+
+	/// Pending on its first poll, like a timer that hasn’t fired.
+	struct YieldOnce(bool);
+
+	impl Future for YieldOnce {
+		type Output = ();
+
+		fn poll(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<()> {
+			if self.0 {
+				Poll::Ready(())
+			} else {
+				self.0 = true;
+				Poll::Pending
+			}
+		}
+	}
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let mut progress = std::vec::Vec::new();
+	let mut future = HashFuture::new(BcryptState::new(b"session", &salt, WorkFactor::EXP5).unwrap(), 10, |remaining| progress.push(remaining), || YieldOnce(false));
+	let waker = Waker::from(Arc::new(NoopWaker));
+	let mut cx = Context::from_waker(&waker);
+	let mut pending = 0;
+
+	let hash = loop {
+		match Pin::new(&mut future).poll(&mut cx) {
+			Poll::Ready(hash) => break hash,
+			Poll::Pending => pending += 1,
+		}
+	};
+
+	assert_eq!(Ok(hash), bcrypt(b"session", &salt, WorkFactor::EXP5));
+	assert_eq!(progress, [22, 12, 2]);
+	assert_eq!(pending, 3);
+}
+
+#[test]
+fn invalid_inputs() {
+	let salt = Salt::from_bytes(&[0; 16]);
+	let work_factor = WorkFactor::exp(4).unwrap();
+	assert_eq!(bcrypt(&[1; 73], &salt, work_factor), Err(Length));
+	assert_eq!(bcrypt(b"f\0o", &salt, work_factor), Err(ZeroByte));
+}
+
+#[test]
+fn salt_round_trip() {
+	for i in 0..2048 {
+		let mut bytes = [0_u8; 16];
+
+		let mut s = DefaultHasher::new();
+		s.write_u32(i);
+		bytes[0..8].copy_from_slice(&s.finish().to_ne_bytes());
+		s.write_u32(i);
+		bytes[8..16].copy_from_slice(&s.finish().to_ne_bytes());
+
+		assert_eq!(Salt::from_bytes(&bytes).to_bytes(), bytes);
+	}
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn salt_strings() {
+	use super::mcf::{HashString, ParseError};
+
+	let hash_string = HashString::parse("$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	assert_eq!(hash_string.salt().to_string(), "cVWp4XaNU8a4v1uMRum2SO");
+	assert_eq!("cVWp4XaNU8a4v1uMRum2SO".parse::<Salt>().unwrap().to_bytes(), hash_string.salt().to_bytes());
+
+	assert_eq!("cVWp4XaNU8a4v1uMRum2S".parse::<Salt>().map(|s| s.to_bytes()), Err(ParseError::Length));
+	assert_eq!("cVWp4XaNU8a4v1uMRum2S!".parse::<Salt>().map(|s| s.to_bytes()), Err(ParseError::Encoding));
+	assert_eq!("cVWp4XaNU8a4v1uMRum2SP".parse::<Salt>().map(|s| s.to_bytes()), Err(ParseError::NonCanonical));
+
+	for i in 0..=255_u8 {
+		let bytes: [u8; 16] = core::array::from_fn(|j| i.wrapping_mul(2 * j as u8 + 1) ^ j as u8);
+		assert_eq!(Salt::from_bytes(&bytes).to_string().parse::<Salt>().unwrap().to_bytes(), bytes);
+	}
+}
+
+#[test]
+fn work_factors() {
+	assert_eq!(WorkFactor::exp(3), None);
+	assert_eq!(WorkFactor::exp(4).map(|f| f.log_rounds()), Some(4));
+	assert_eq!(WorkFactor::exp(31).map(|f| f.linear_rounds()), Some(2147483648));
+	assert_eq!(WorkFactor::exp(32), None);
+
+	assert_eq!(WorkFactor::try_from(12), Ok(WorkFactor::EXP12));
+	assert_eq!(WorkFactor::try_from(32), Err(super::InvalidWorkFactor(32)));
+	assert_eq!(u32::from(WorkFactor::EXP31), 31);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn throughput() {
+	use core::num::NonZeroUsize;
+	use std::time::Duration;
+
+	let result = super::measure_throughput(WorkFactor::EXP4, Duration::from_millis(50), NonZeroUsize::new(2).unwrap());
+	assert!(result.hashes >= 2);
+	assert!(result.elapsed >= Duration::from_millis(50));
+	assert!(result.per_second() > 0.0);
+}
+
+#[test]
+fn observer() {
+	use core::cell::Cell;
+	use core::time::Duration;
+	use super::observer::{self, Observer, bcrypt_observed};
+
+	#[derive(Default)]
+	struct Counts {
+		hashes: Cell<u32>,
+		errors: Cell<u32>,
+		durations: Cell<u32>,
+	}
+
+	impl Observer for Counts {
+		fn increment(&self, name: &'static str) {
+			let counter = match name {
+				observer::HASHES => &self.hashes,
+				observer::ERRORS => &self.errors,
+				_ => panic!("unexpected counter {}", name),
+			};
+			counter.set(counter.get() + 1);
+		}
+
+		fn observe(&self, name: &'static str, _duration: Duration) {
+			assert_eq!(name, observer::HASH_DURATION);
+			self.durations.set(self.durations.get() + 1);
+		}
+	}
+
+	let counts = Counts::default();
+	let salt = Salt::from_bytes(&[0; 16]);
+	bcrypt_observed(b"foo", &salt, WorkFactor::EXP4, &counts).unwrap();
+	bcrypt_observed(b"f\0o", &salt, WorkFactor::EXP4, &counts).unwrap_err();
+
+	assert_eq!(counts.hashes.get(), 1);
+	assert_eq!(counts.errors.get(), 1);
+	assert_eq!(counts.durations.get(), if cfg!(feature = "std") { 1 } else { 0 });
+
+	#[cfg(feature = "mcf")]
+	{
+		use super::mcf;
+
+		let hash_string = mcf::hash_with_observer(b"foo", &salt, WorkFactor::EXP4, &counts).unwrap();
+		assert_eq!(hash_string, mcf::hash(b"foo", &salt, WorkFactor::EXP4).unwrap());
+		assert_eq!(mcf::verify_with_observer(b"foo", &hash_string, &counts), Ok(true));
+		assert_eq!(mcf::verify_with_observer(b"bar", &hash_string, &counts), Ok(false));
+		mcf::verify_with_observer(b"f\0o", &hash_string, &counts).unwrap_err();
+
+		assert_eq!(counts.hashes.get(), 4);
+		assert_eq!(counts.errors.get(), 2);
+	}
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn load_aware_cost() {
+	use std::time::Duration;
+	use super::LoadAwareCost;
+
+	let ms = Duration::from_millis;
+	assert!(LoadAwareCost::new(WorkFactor::EXP12, WorkFactor::EXP10, ms(100), ms(200)).is_none());
+
+	let cost = LoadAwareCost::new(WorkFactor::EXP12, WorkFactor::EXP10, ms(200), ms(100)).unwrap();
+	assert_eq!(cost.work_factor(), WorkFactor::EXP12);
+
+	cost.record(ms(150));
+	assert!(!cost.is_degraded());
+
+	cost.record(ms(1000));
+	assert_eq!(cost.work_factor(), WorkFactor::EXP10);
+
+	// between the thresholds: stays degraded
+	for _ in 0..32 {
+		cost.record(ms(150));
+	}
+	assert!(cost.is_degraded());
+
+	for _ in 0..32 {
+		cost.record(ms(50));
+	}
+	assert_eq!(cost.work_factor(), WorkFactor::EXP12);
+}
+
+#[cfg(feature = "test-support")]
+#[test]
+fn test_support() {
+	use core::time::Duration;
+	use super::{ClockStalled, calibrate};
+	use super::rng::Rng;
+	use super::test_support::{DeterministicRng, FakeClock};
+
+	// the first ChaCha20 block for an all-zero key and nonce
+	let mut block = [0_u8; 16];
+	DeterministicRng::from_seed([0; 32]).fill_bytes(&mut block);
+	assert_eq!(block, *b"\x76\xb8\xe0\xad\xa0\xf1\x3d\x90\x40\x5d\x6a\xe5\x53\x86\xbd\x28");
+
+	let a = Salt::generate(DeterministicRng::new(1)).to_bytes();
+	let b = Salt::generate(DeterministicRng::new(1)).to_bytes();
+	let c = Salt::generate(DeterministicRng::new(2)).to_bytes();
+	assert_eq!(a, b);
+	assert_ne!(a, c);
+
+	let ms = Duration::from_millis;
+	assert_eq!(calibrate(ms(100), FakeClock::new(ms(40))), Ok(WorkFactor::EXP5));
+	assert_eq!(calibrate(ms(100), FakeClock::new(ms(200))), Ok(WorkFactor::EXP4));
+	assert_eq!(calibrate(ms(1000), FakeClock::new(ms(250))), Ok(WorkFactor::EXP6));
+	assert_eq!(calibrate(ms(100), FakeClock::new(Duration::ZERO)), Err(ClockStalled));
+	assert_eq!(calibrate(ms(100), FakeClock::default()), Err(ClockStalled));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn calibrate_system_clock() {
+	use std::time::{Duration, Instant};
+	use super::calibrate;
+	use super::clock::SystemClock;
+
+	let target = Duration::from_millis(40);
+	let work_factor = calibrate(target, SystemClock::new()).unwrap();
+
+	let start = Instant::now();
+	super::bcrypt_runtime(b"calibration", &Salt::from_bytes(&[0; 16]), work_factor).unwrap();
+	assert!(work_factor == WorkFactor::EXP4 || start.elapsed() < target * 2);
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn sha512() {
+	use super::sha512::{hmac, sha512};
+
+	assert_eq!(sha512(&[b"abc"]), *b"\xdd\xaf\x35\xa1\x93\x61\x7a\xba\xcc\x41\x73\x49\xae\x20\x41\x31\x12\xe6\xfa\x4e\x89\xa9\x7e\xa2\x0a\x9e\xee\xe6\x4b\x55\xd3\x9a\x21\x92\x99\x2a\x27\x4f\xc1\xa8\x36\xba\x3c\x23\xa3\xfe\xeb\xbd\x45\x4d\x44\x23\x64\x3c\xe8\x0e\x2a\x9a\xc9\x4f\xa5\x4c\xa4\x9f");
+	assert_eq!(sha512(&[&[b'a'; 150], &[b'a'; 50]]), *b"\x4b\x11\x45\x9c\x33\xf5\x2a\x22\xee\x82\x36\x78\x27\x14\xc1\x50\xa3\xb2\xc6\x09\x94\xe9\xac\xee\x17\xfe\x68\x94\x7a\x3e\x67\x89\xf3\x1e\x76\x68\x39\x45\x92\xda\x7b\xef\x82\x7c\xdd\xca\x88\xc4\xe6\xf8\x6e\x4d\xf7\xed\x1a\xe6\xcb\xa7\x1f\x3e\x98\xfa\xee\x9f");
+
+	// RFC 4231, test cases 2 and 6
+	assert_eq!(hmac(b"Jefe", &[b"what do ya ", b"want for nothing?"]), *b"\x16\x4b\x7a\x7b\xfc\xf8\x19\xe2\xe3\x95\xfb\xe7\x3b\x56\xe0\xa3\x87\xbd\x64\x22\x2e\x83\x1f\xd6\x10\x27\x0c\xd7\xea\x25\x05\x54\x97\x58\xbf\x75\xc0\x5a\x99\x4a\x6d\x03\x4f\x65\xf8\xf0\xe6\xfd\xca\xea\xb1\xa3\x4d\x4a\x6b\x4b\x63\x6e\x07\x0a\x38\xbc\xe7\x37");
+	assert_eq!(hmac(&[0xaa; 131], &[b"Test Using Larger Than Block-Size Key - Hash Key First"]), *b"\x80\xb2\x42\x63\xc7\xc1\xa3\xeb\xb7\x14\x93\xc1\xdd\x7b\xe8\xb4\x9b\x46\xd1\xf4\x1b\x4a\xee\xc1\x12\x1b\x01\x37\x83\xf8\xf3\x52\x6b\x56\xd0\x37\xe0\x5f\x25\x98\xbd\x0f\xd2\x21\x5d\x6a\x1e\x52\x95\xe6\x4f\x73\xf6\x3f\x0a\xec\x8b\x91\x5a\x98\x5d\x78\x65\x98");
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn server_relief() {
+	use super::mcf::ParseError;
+	use super::relief::{ServerRecord, client_hash, decode_client_hash, encode_client_hash};
+
+	let server_key = b"server secret";
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash = client_hash(b"password", &salt, WorkFactor::EXP4).unwrap();
+	let record = ServerRecord::new(server_key, WorkFactor::EXP4, &salt, &hash);
+
+	let encoded = encode_client_hash(&hash);
+	assert_eq!(decode_client_hash(core::str::from_utf8(&encoded).unwrap()), Ok(hash));
+	assert_eq!(decode_client_hash("short"), Err(ParseError::Length));
+
+	let s = record.to_string();
+	assert!(s.starts_with("$bcrypt-relief$04$UjnYUjnYUjnYUjnYUjnYUe"));
+	let parsed: ServerRecord = s.parse().unwrap();
+	assert_eq!(parsed.to_string(), s);
+	assert_eq!(parsed.work_factor(), WorkFactor::EXP4);
+	assert_eq!(parsed.salt().to_bytes(), salt.to_bytes());
+
+	assert!(parsed.verify(server_key, &hash));
+	assert!(!parsed.verify(b"other secret", &hash));
+	assert!(!parsed.verify(server_key, &client_hash(b"Password", &salt, WorkFactor::EXP4).unwrap()));
+
+	assert_eq!(ServerRecord::parse(&s[1..]).map(|r| r.to_string()), Err(ParseError::Length));
+	assert_eq!(ServerRecord::parse(&s.replacen("relief", "belief", 1)).map(|r| r.to_string()), Err(ParseError::Version));
+	assert_eq!(ServerRecord::parse(&s.replacen("04", "32", 1)).map(|r| r.to_string()), Err(ParseError::WorkFactor));
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn wrapped_hashes() {
+	use super::mcf::{HashString, ParseError, Version};
+	use super::wrap::WrappedHash;
+
+	let inner = HashString::parse("$2y$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	let wrapped = WrappedHash::wrap(&inner, &Salt::from_bytes(&[0x5a; 16]), WorkFactor::EXP5);
+
+	let s = wrapped.to_string();
+	assert!(s.starts_with("$bcrypt-wrap$2y$04$cVWp4XaNU8a4v1uMRum2SO$05$UjnYUjnYUjnYUjnYUjnYUe"));
+	assert!(!s.contains("026BWLIoQMD"));
+	assert_eq!(s.parse(), Ok(wrapped));
+	assert_eq!(wrapped.inner_version(), Version::V2y);
+	assert_eq!(wrapped.inner_work_factor(), WorkFactor::EXP4);
+	assert_eq!(wrapped.outer_work_factor(), WorkFactor::EXP5);
+
+	assert_eq!(wrapped.unwrap(b"Kk4DQuMMfZL9o"), Ok(Some(inner)));
+	assert_eq!(wrapped.verify(b"Kk4DQuMMfZL9O"), Ok(false));
+	assert_eq!(wrapped.verify(b"K\0"), Err(ZeroByte));
+
+	assert_eq!(WrappedHash::parse(&s[1..]), Err(ParseError::Length));
+	assert_eq!(WrappedHash::parse(&s.replacen("wrap", "warp", 1)), Err(ParseError::Version));
+	assert_eq!(WrappedHash::parse(&s.replacen("$05$", "$03$", 1)), Err(ParseError::WorkFactor));
+	assert_eq!(WrappedHash::parse(&s.replacen("$2y$", "$2x$", 1)), Err(ParseError::Version));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verify_cache() {
+	use core::cell::Cell;
+	use std::time::{Duration, Instant};
+	use super::VerifyCache;
+	use super::clock::Clock;
+	use super::mcf;
+	use super::rng::Rng;
+
+	struct ManualClock(Cell<Duration>);
+
+	impl Clock for ManualClock {
+		fn now(&self) -> Duration {
+			self.0.get()
+		}
+	}
+
+	struct CountingRng(u8);
+
+	impl Rng for CountingRng {
+		fn fill_bytes(&mut self, dest: &mut [u8]) {
+			for b in dest {
+				self.0 = self.0.wrapping_add(1);
+				*b = self.0;
+			}
+		}
+	}
+
+	let clock = ManualClock(Cell::new(Duration::ZERO));
+	let cache = VerifyCache::new(CountingRng(0), &clock, Duration::from_secs(60), 2);
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash_string = mcf::hash(b"hunter2", &salt, WorkFactor::EXP8).unwrap();
+	let other = mcf::hash(b"hunter3", &salt, WorkFactor::EXP8).unwrap();
+
+	let timed = |key: &[u8], hash_string| {
+		let start = Instant::now();
+		let result = cache.verify(key, hash_string);
+		(result, start.elapsed())
+	};
+
+	let (first, uncached) = timed(b"hunter2", &hash_string);
+	let (second, cached) = timed(b"hunter2", &hash_string);
+	assert_eq!((first, second), (Ok(true), Ok(true)));
+	assert!(cached * 4 < uncached);
+
+	// mismatches aren’t cached, and a cached key doesn’t match a different hash
+	assert_eq!(cache.verify(b"hunter3", &hash_string), Ok(false));
+	assert_eq!(cache.verify(b"hunter2", &other), Ok(false));
+	assert_eq!(cache.verify(b"hunter\0", &hash_string), Err(ZeroByte));
+
+	clock.0.set(Duration::from_secs(61));
+	let (expired, recomputed) = timed(b"hunter2", &hash_string);
+	assert_eq!(expired, Ok(true));
+	assert!(cached * 4 < recomputed);
+
+	cache.clear();
+	assert!(std::format!("{cache:?}").starts_with("VerifyCache { ttl: 60s, capacity: 2"));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn throttle() {
+	use core::cell::Cell;
+	use std::time::Duration;
+	use super::{Throttle, ThrottledVerify};
+	use super::clock::Clock;
+	use super::mcf;
+
+	struct ManualClock(Cell<Duration>);
+
+	impl Clock for ManualClock {
+		fn now(&self) -> Duration {
+			self.0.get()
+		}
+	}
+
+	let clock = ManualClock(Cell::new(Duration::ZERO));
+	let throttle = Throttle::new(&clock, 2, Duration::from_secs(10));
+	let hash_string = mcf::hash(b"hunter2", &Salt::from_bytes(&[0x5a; 16]), WorkFactor::EXP4).unwrap();
+
+	assert_eq!(throttle.verify("alice", b"wrong", &hash_string), Ok(ThrottledVerify::Mismatch));
+	assert_eq!(throttle.verify("alice", b"wrong", &hash_string), Ok(ThrottledVerify::Mismatch));
+	assert_eq!(throttle.verify("alice", b"hunter2", &hash_string), Ok(ThrottledVerify::Throttled { retry_after: Duration::from_secs(10) }));
+	assert_eq!(throttle.verify("bob", b"hunter2", &hash_string), Ok(ThrottledVerify::Match));
+
+	clock.0.set(Duration::from_secs(4));
+	assert_eq!(throttle.verify("alice", b"wrong", &hash_string), Ok(ThrottledVerify::Throttled { retry_after: Duration::from_secs(6) }));
+
+	clock.0.set(Duration::from_secs(10));
+	assert_eq!(throttle.verify("alice", b"hunter2", &hash_string), Ok(ThrottledVerify::Match));
+
+	// a match refills the bucket
+	assert_eq!(throttle.verify("alice", b"wrong", &hash_string), Ok(ThrottledVerify::Mismatch));
+	assert_eq!(throttle.verify("alice", b"wrong", &hash_string), Ok(ThrottledVerify::Mismatch));
+	assert!(matches!(throttle.verify("alice", b"wrong", &hash_string), Ok(ThrottledVerify::Throttled { .. })));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verifier_pool() {
+	use core::num::NonZeroUsize;
+	use super::{PoolOptions, VerifierPool};
+	use super::mcf;
+
+	let hash_string = mcf::hash(b"hunter2", &Salt::from_bytes(&[0x5a; 16]), WorkFactor::EXP4).unwrap();
+	let mut options = PoolOptions::new(NonZeroUsize::new(2).unwrap());
+
+	if cfg!(target_os = "linux") {
+		options.niceness = Some(10);
+		options.cores = std::vec![0];
+	}
+
+	let pool = VerifierPool::new(options).unwrap();
+	let pending: std::vec::Vec<_> = (0..6).map(|i| pool.submit(if i % 2 == 0 { b"hunter2" } else { b"hunter3" }, &hash_string)).collect();
+
+	for (i, pending) in pending.into_iter().enumerate() {
+		assert_eq!(pending.wait(), Ok(i % 2 == 0));
+	}
+
+	assert_eq!(pool.verify(&[1; 73], &hash_string), Err(Length));
+
+	if cfg!(target_os = "linux") {
+		let mut options = PoolOptions::new(NonZeroUsize::new(1).unwrap());
+		options.cores = std::vec![1 << 20];
+		assert!(VerifierPool::new(options).is_err());
+	}
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verifier_pool_metrics() {
+	use core::num::NonZeroUsize;
+	use std::sync::{Arc, Mutex};
+	use std::time::Duration;
+	use std::vec::Vec;
+	use super::{PoolOptions, VerifierPool};
+	use super::mcf;
+	use super::observer::{self, Observer};
+
+	#[derive(Default)]
+	struct Recorder {
+		gauges: Mutex<Vec<(&'static str, usize)>>,
+		durations: Mutex<Vec<&'static str>>,
+	}
+
+	impl Observer for Arc<Recorder> {
+		fn observe(&self, name: &'static str, _duration: Duration) {
+			self.durations.lock().unwrap().push(name);
+		}
+
+		fn gauge(&self, name: &'static str, value: usize) {
+			self.gauges.lock().unwrap().push((name, value));
+		}
+	}
+
+	let recorder = Arc::new(Recorder::default());
+	let hash_string = mcf::hash(b"hunter2", &Salt::from_bytes(&[0x5a; 16]), WorkFactor::EXP4).unwrap();
+	let pool = VerifierPool::with_observer(PoolOptions::new(NonZeroUsize::new(1).unwrap()), Arc::clone(&recorder)).unwrap();
+
+	let pending: Vec<_> = (0..3).map(|_| pool.submit(b"hunter2", &hash_string)).collect();
+
+	for pending in pending {
+		assert_eq!(pending.wait(), Ok(true));
+	}
+
+	drop(pool);
+
+	let gauges = recorder.gauges.lock().unwrap();
+	let max = |name| gauges.iter().filter(|&&(n, _)| n == name).map(|&(_, v)| v).max();
+	assert_eq!(max(observer::POOL_IN_FLIGHT), Some(1));
+	assert!(max(observer::POOL_QUEUE_DEPTH) >= Some(1));
+	assert_eq!(gauges.iter().rev().find(|&&(n, _)| n == observer::POOL_IN_FLIGHT), Some(&(observer::POOL_IN_FLIGHT, 0)));
+
+	let durations = recorder.durations.lock().unwrap();
+	assert_eq!(durations.iter().filter(|&&n| n == observer::POOL_WAIT_DURATION).count(), 3);
+	assert_eq!(durations.iter().filter(|&&n| n == observer::POOL_SERVICE_DURATION).count(), 3);
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn verify_with_deadline() {
+	use core::cell::Cell;
+	use core::time::Duration;
+	use super::clock::Clock;
+	use super::mcf::{self, VerifyOutcome};
+
+	// advances a millisecond each time it’s read
+	struct StepClock(Cell<Duration>);
+
+	impl Clock for StepClock {
+		fn now(&self) -> Duration {
+			let now = self.0.get();
+			self.0.set(now + Duration::from_millis(1));
+			now
+		}
+	}
+
+	let hash_string = mcf::hash(b"hunter2", &Salt::from_bytes(&[0x5a; 16]), WorkFactor::EXP6).unwrap();
+	let clock = StepClock(Cell::new(Duration::ZERO));
+	let ms = Duration::from_millis;
+
+	assert_eq!(mcf::verify_with_deadline(b"hunter2", &hash_string, &clock, ms(1000)), Ok(VerifyOutcome::Match));
+	assert_eq!(mcf::verify_with_deadline(b"hunter3", &hash_string, &clock, ms(2000)), Ok(VerifyOutcome::Mismatch));
+
+	// 64 rounds are checked against the clock after every 16, so a deadline 2 reads away times out
+	let now = clock.0.get();
+	assert_eq!(mcf::verify_with_deadline(b"hunter2", &hash_string, &clock, now + ms(1)), Ok(VerifyOutcome::TimedOut));
+	assert_eq!(clock.0.get(), now + ms(2));
+
+	assert_eq!(mcf::verify_with_deadline(b"hunter\0", &hash_string, &clock, ms(0)), Err(ZeroByte));
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn mcf_strings() {
+	use super::mcf::{HashString, McfString};
+
+	const PARSED: McfString = match HashString::parse("$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm") {
+		Ok(hash_string) => hash_string,
+		Err(_) => panic!(),
+	};
+	const AS_STR: &str = PARSED.as_str();
+
+	assert_eq!(AS_STR.len(), 60);
+	assert!(PARSED.starts_with("$2b$04$"));
+	assert_eq!(&PARSED[7..29], "cVWp4XaNU8a4v1uMRum2SO");
+	assert_eq!(AsRef::<str>::as_ref(&PARSED), AS_STR);
+	assert_eq!(PARSED.to_string(), AS_STR);
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn settings() {
+	use super::mcf::{HashString, ParseError, Setting, Version};
+
+	const SALT: Salt = Salt::from_bytes(&[0x5a; 16]);
+	const BUILT: Setting = Setting::new(WorkFactor::EXP10, &SALT);
+	const PARSED: Setting = match Setting::parse("$2y$04$cVWp4XaNU8a4v1uMRum2SO") {
+		Ok(setting) => setting,
+		Err(_) => panic!("invalid setting"),
+	};
+
+	assert_eq!(BUILT.as_str(), "$2b$10$UjnYUjnYUjnYUjnYUjnYUe");
+	assert_eq!(BUILT.to_string().parse(), Ok(BUILT));
+	assert_eq!(BUILT.work_factor(), WorkFactor::EXP10);
+	assert_eq!(BUILT.salt().to_bytes(), [0x5a; 16]);
+
+	let hash_string = HashString::parse("$2y$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	assert_eq!(PARSED.version(), Version::V2y);
+	assert_eq!(hash_string.setting(), PARSED);
+	assert_eq!(PARSED.hash(b"Kk4DQuMMfZL9o"), Ok(hash_string));
+
+	assert_eq!(Setting::parse("$2b$04$cVWp4XaNU8a4v1uMRum2S"), Err(ParseError::Length));
+	assert_eq!(Setting::parse("$2x$04$cVWp4XaNU8a4v1uMRum2SO"), Err(ParseError::Version));
+	assert_eq!(Setting::parse("$2b$32$cVWp4XaNU8a4v1uMRum2SO"), Err(ParseError::WorkFactor));
+	assert_eq!(Setting::parse("$2b$04$cVWp4XaNU8a4v1uMRum2SP"), Err(ParseError::NonCanonical));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn configured_work_factor() {
+	use super::{WorkFactorConfigError, resolve_work_factor, work_factor_from_env};
+
+	let allowed = WorkFactor::EXP10..=WorkFactor::EXP16;
+	assert_eq!(resolve_work_factor(None, WorkFactor::EXP12, allowed.clone()), Ok(WorkFactor::EXP12));
+	assert_eq!(resolve_work_factor(Some(" 13\n"), WorkFactor::EXP12, allowed.clone()), Ok(WorkFactor::EXP13));
+	assert_eq!(resolve_work_factor(Some("twelve"), WorkFactor::EXP12, allowed.clone()), Err(WorkFactorConfigError::Syntax));
+	assert_eq!(resolve_work_factor(Some(""), WorkFactor::EXP12, allowed.clone()), Err(WorkFactorConfigError::Syntax));
+	assert_eq!(resolve_work_factor(Some("+12"), WorkFactor::EXP12, allowed.clone()), Err(WorkFactorConfigError::Syntax));
+	assert_eq!(resolve_work_factor(Some("4"), WorkFactor::EXP12, allowed.clone()), Err(WorkFactorConfigError::OutOfRange { value: 4, allowed: allowed.clone() }));
+	assert_eq!(resolve_work_factor(Some("99999999999"), WorkFactor::EXP12, allowed.clone()).unwrap_err().to_string(), "bcrypt work factor 4294967295 outside 10 to 16");
+
+	assert_eq!(work_factor_from_env("BCRYPT_ONLY_TEST_UNSET_WORK_FACTOR", WorkFactor::EXP11, allowed), Ok(WorkFactor::EXP11));
+}
+
+#[cfg(all(feature = "test-support", feature = "std"))]
+#[test]
+fn salt_reuse_detector() {
+	use super::test_support::{DeterministicRng, SaltReuseDetector};
+
+	let detector = SaltReuseDetector::new();
+	let mut rng = DeterministicRng::new(1);
+	assert!(detector.is_empty());
+
+	for _ in 0..100 {
+		detector.generate(&mut rng).unwrap();
+	}
+
+	assert_eq!(detector.len(), 100);
+
+	// a generator reseeded with the same value repeats its salts
+	let reused = detector.generate(DeterministicRng::new(1)).unwrap_err();
+	assert_eq!(reused.checked, 100);
+	assert!(reused.to_string().starts_with("salt "));
+	assert_eq!(detector.len(), 100);
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn password_policies() {
+	use super::policy::{MinimumLength, PolicyError, TooShort, hash_with_policy};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let not_password = |key: &[u8]| if key == b"password" { Err("common password") } else { Ok(()) };
+
+	assert_eq!(hash_with_policy(&MinimumLength(8), b"short", &salt, WorkFactor::EXP4), Err(PolicyError::Rejected(TooShort { minimum: 8 })));
+	assert_eq!(hash_with_policy(&MinimumLength(8), &[1; 73], &salt, WorkFactor::EXP4), Err(PolicyError::Bcrypt(Length)));
+	assert_eq!(hash_with_policy(&not_password, b"password", &salt, WorkFactor::EXP4), Err(PolicyError::Rejected("common password")));
+	assert_eq!(hash_with_policy(&not_password, b"hunter2", &salt, WorkFactor::EXP4), Ok(super::mcf::hash(b"hunter2", &salt, WorkFactor::EXP4).unwrap()));
+	assert_eq!(PolicyError::<TooShort>::Rejected(TooShort { minimum: 8 }).to_string(), "password shorter than 8 bytes");
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn recovery_codes() {
+	use super::preset::{RECOVERY_CODE_WORK_FACTOR, RecoveryCodeError, hash_recovery_code, normalize_recovery_code, verify_recovery_code};
+
+	assert_eq!(normalize_recovery_code(" abcd-ef12\tGH\n").unwrap().as_bytes(), b"ABCDEF12GH");
+	assert_eq!(normalize_recovery_code(" - ").map(|_| ()), Err(RecoveryCodeError::Empty));
+	assert_eq!(normalize_recovery_code("abcd\0efgh").map(|_| ()), Err(RecoveryCodeError::InvalidCharacter));
+	assert_eq!(normalize_recovery_code("ＡＢＣＤ").map(|_| ()), Err(RecoveryCodeError::InvalidCharacter));
+	assert_eq!(normalize_recovery_code(&"a-".repeat(73)).map(|_| ()), Err(RecoveryCodeError::TooLong));
+	assert!(normalize_recovery_code(&"a-".repeat(72)).is_ok());
+
+	let hash_string = hash_recovery_code("ABCD-EFGH-2345", &Salt::from_bytes(&[0x5a; 16])).unwrap();
+	assert_eq!(hash_string.work_factor(), RECOVERY_CODE_WORK_FACTOR);
+	assert_eq!(verify_recovery_code("abcd efgh 2345", &hash_string), Ok(true));
+	assert_eq!(verify_recovery_code("abcd-efgh-2346", &hash_string), Ok(false));
+	assert_eq!(verify_recovery_code("", &hash_string), Err(RecoveryCodeError::Empty));
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn api_tokens() {
+	use super::preset::{API_TOKEN_WORK_FACTOR, ApiTokenError, hash_api_token, verify_api_token};
+
+	let token = b"0123456789abcdef0123456789abcdef\0 any bytes at all";
+	let hash_string = hash_api_token(b"pepper", token, &Salt::from_bytes(&[0x5a; 16])).unwrap();
+	assert_eq!(hash_string.work_factor(), API_TOKEN_WORK_FACTOR);
+	assert_eq!(verify_api_token(b"pepper", token, &hash_string), Ok(true));
+	assert_eq!(verify_api_token(b"peppe", token, &hash_string), Ok(false));
+	assert_eq!(verify_api_token(b"pepper", &token[..40], &hash_string), Ok(false));
+	assert_eq!(super::mcf::verify(token, &hash_string), Err(ZeroByte));
+
+	assert_eq!(hash_api_token(b"pepper", &[b'a'; 31], &Salt::from_bytes(&[0; 16])), Err(ApiTokenError::TooShort));
+	assert_eq!(verify_api_token(b"pepper", &[b'a'; 1025], &hash_string), Err(ApiTokenError::TooLong));
+}
+
+/// Counts allocations made on each thread, so a test can check that the code it calls doesn’t allocate while other tests run in parallel.
+struct CountingAllocator;
+
+std::thread_local! {
+	static ALLOCATIONS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+#[allow(unsafe_code)]
+// SAFETY: defers everything to the system allocator
+unsafe impl core::alloc::GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
+		let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+		unsafe { std::alloc::System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: core::alloc::Layout) {
+		unsafe { std::alloc::System.dealloc(ptr, layout) }
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[cfg(feature = "mcf")]
+#[test]
+fn no_allocation() {
+	use super::BcryptState;
+	use super::mcf::{self, HashString, Setting};
+
+	let allocations = || ALLOCATIONS.with(|count| count.get());
+	let before = allocations();
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash = bcrypt(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+	let mut context = Context::new();
+	assert_eq!(bcrypt_with_context(b"hunter2", &salt, WorkFactor::EXP4, &mut context), Ok(hash));
+
+	let mut state = BcryptState::new(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+	while state.run(3).is_none() {}
+
+	let hash_string = HashString::parse("$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm").unwrap();
+	assert_eq!(mcf::verify(b"Kk4DQuMMfZL9o", &hash_string), Ok(true));
+	assert_eq!(mcf::hash(b"Kk4DQuMMfZL9o", &hash_string.salt(), WorkFactor::EXP4), Ok(hash_string));
+	assert_eq!(Setting::new(WorkFactor::EXP4, &salt).hash(b"hunter2").map(|h| h.hash()), Ok(hash));
+	assert_eq!(bcrypt(&[1; 73], &salt, WorkFactor::EXP4), Err(Length));
+
+	assert_eq!(allocations(), before);
+
+	// the counter does count
+	drop(std::vec![0_u8; 1]);
+	assert_eq!(allocations(), before + 1);
+}
+
+const STACK_PAINT: u8 = 0xa5;
+
+#[inline(never)]
+fn paint_stack<const N: usize>() -> usize {
+	let mut region = [STACK_PAINT; N];
+	core::hint::black_box(&mut region);
+	region.as_ptr() as usize
+}
+
+#[inline(never)]
+fn call_measured<R, F: FnOnce() -> R>(f: F) -> R {
+	core::hint::black_box(f())
+}
+
+/// Counts the painted bytes left at the far end of the region, which nothing has written over since it was painted.
+#[inline(never)]
+#[allow(unsafe_code)]
+fn count_unused_stack<const N: usize>(region: usize) -> usize {
+	let mut unused = 0;
+
+	while unused < N {
+		// SAFETY: not guaranteed by the language – the region belonged to a frame that has returned – but it’s stack memory below this frame that stays mapped, which is what stack painting relies on everywhere
+		if unsafe { core::ptr::read_volatile((region + unused) as *const u8) } != STACK_PAINT {
+			break;
+		}
+
+		unused += 1;
+	}
+
+	unused
+}
+
+/// Measures the peak stack used by `f`, up to `N` bytes, by stack painting: the `N` bytes below the current frame are filled with a pattern, `f` runs, and whatever of the pattern `f` overwrote is the stack it used. Returns `f`’s result and the number of bytes.
+///
+/// Reading the painted region back reads the stack of frames that have returned, which the language doesn’t define, so this stays a test of this crate’s own stack use rather than a public API. It assumes a stack that grows downward, and it’s approximate – the frames of the helpers themselves add a little.
+fn measure_stack<const N: usize, R, F: FnOnce() -> R>(f: F) -> (R, usize) {
+	let region = paint_stack::<N>();
+	let result = call_measured(f);
+	let unused = count_unused_stack::<N>(region);
+	(result, N - unused)
+}
+
+#[test]
+fn stack_usage() {
+	use super::BcryptState;
+
+	// `bcrypt` keeps its 4 KiB of state on the stack; `BcryptState` is moved out of `new` into the caller
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let ((), used) = measure_stack::<{ 256 * 1024 }, _, _>(|| {
+		bcrypt(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+	});
+	assert!(used > 4096 && used < 256 * 1024, "{}", used);
+
+	let ((), used) = measure_stack::<{ 256 * 1024 }, _, _>(|| {
+		let mut state = BcryptState::new(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+		while state.run(3).is_none() {}
+	});
+	assert!(used > 4096 && used < 256 * 1024, "{}", used);
+
+	let ((), used) = measure_stack::<4096, _, _>(|| ());
+	assert!(used < 1024, "{}", used);
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn custom_message() {
+	use super::unstable::bcrypt_with_message;
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let standard = bcrypt_with_message(b"hunter2", &salt, WorkFactor::EXP4, b"OrpheanBeholderScryDoubt").unwrap();
+	assert_eq!(standard[..23], bcrypt(b"hunter2", &salt, WorkFactor::EXP4).unwrap());
+
+	let custom = bcrypt_with_message(b"hunter2", &salt, WorkFactor::EXP4, b"OxychromaticBlowfishSwat").unwrap();
+	assert_ne!(custom, standard);
+	assert_eq!(bcrypt_with_message(b"hunter\0", &salt, WorkFactor::EXP4, &[0; 24]), Err(ZeroByte));
+}
+
+#[test]
+fn key_buffers() {
+	use super::{KeyBuf, KeyBufError};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let mut key = KeyBuf::<8>::new();
+	assert!(key.is_empty());
+
+	for &b in b"hunter2x" {
+		key.push(b).unwrap();
+	}
+
+	key.pop();
+	assert_eq!(key.as_bytes(), b"hunter2");
+	assert_eq!(key.push(0), Err(KeyBufError::ZeroByte));
+	assert_eq!(key.extend_from_slice(b"ab"), Err(KeyBufError::Full));
+	assert_eq!(key.len(), 7);
+	assert_eq!(key.hash(&salt, WorkFactor::EXP4), bcrypt(b"hunter2", &salt, WorkFactor::EXP4).unwrap());
+
+	let mut key = KeyBuf::<72>::default();
+	key.extend_from_slice(&[b'a'; 72]).unwrap();
+	assert_eq!(key.push(b'a'), Err(KeyBufError::Full));
+	assert_eq!(key.hash(&salt, WorkFactor::EXP4), bcrypt(&[b'a'; 72], &salt, WorkFactor::EXP4).unwrap());
+
+	key.clear();
+	assert_eq!(key.as_bytes(), b"");
+	assert_eq!(KeyBuf::<0>::new().hash(&salt, WorkFactor::EXP4), bcrypt(b"", &salt, WorkFactor::EXP4).unwrap());
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn lenient_parsing() {
+	use super::mcf::{HashString, ParseError};
+
+	let canonical = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm";
+	let expected = HashString::parse(canonical);
+	assert!(expected.is_ok());
+
+	for messy in [
+		canonical,
+		"  $2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm\r\n",
+		"{CRYPT}$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm",
+		"{blf-crypt}$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm\n",
+		"$$2b$$04$$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm",
+	] {
+		assert_eq!(HashString::parse_lenient(messy), expected, "{:?}", messy);
+	}
+
+	assert_eq!(HashString::parse_lenient("{SSHA}$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"), Err(ParseError::Length));
+	assert_eq!(HashString::parse_lenient("$2b$04$cVWp4XaNU8a4v1uMRum2SO 026BWLIoQMD/TXg5uZV.0P.uO8m3YEm"), Err(ParseError::Length));
+	assert_eq!(HashString::parse_lenient(""), Err(ParseError::Length));
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn verify_each() {
+	use super::mcf::{self, HashString, Version};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let right = mcf::hash(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+	let wrong = mcf::hash(b"hunter3", &salt, WorkFactor::EXP4).unwrap();
+	let relabel = |version| HashString::parse(&right.replacen("2b", version, 1)).unwrap();
+	let hash_strings = [relabel("2y"), wrong, right, relabel("2a")];
+	let expected = [Some(Version::V2y), None, Some(Version::V2b), Some(Version::V2a)];
+
+	assert!(mcf::verify_each(b"hunter2", &hash_strings).unwrap().eq(expected));
+	assert_eq!(mcf::verify_each(b"hunter\x002", &hash_strings).err(), Some(ZeroByte));
+	assert_eq!(mcf::verify_each(b"hunter2", &[]).unwrap().count(), 0);
+
+	#[cfg(feature = "std")]
+	{
+		assert_eq!(mcf::verify_each_parallel(b"hunter2", &hash_strings).unwrap(), expected);
+		assert_eq!(mcf::verify_each_parallel(b"hunter2", &[]).unwrap(), []);
+		assert_eq!(mcf::verify_each_parallel(&[b'a'; 73], &hash_strings), Err(Length));
+	}
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn bulk_validation() {
+	use super::mcf::{self, ParseError};
+
+	let s = "$2b$04$cVWp4XaNU8a4v1uMRum2SO026BWLIoQMD/TXg5uZV.0P.uO8m3YEm";
+	assert_eq!(mcf::validate(s.as_bytes()), Ok(()));
+	assert_eq!(mcf::validate(s.replacen("SO", "S\u{e9}", 1).as_bytes()), Err(ParseError::Length));
+	assert_eq!(mcf::validate(&s.as_bytes()[..59]), Err(ParseError::Length));
+
+	let mut bad_byte = s.as_bytes().to_vec();
+	bad_byte[40] = 0xff;
+	assert_eq!(mcf::validate(&bad_byte), Err(ParseError::Encoding));
+
+	let dump = std::format!("{s}\n{}\r\n\n{s}\r\n{}\n{s}\n", s.replacen("SO", "SP", 1), s.replacen("04", "99", 1));
+	assert!(mcf::invalid_lines(dump.as_bytes()).eq([(2, ParseError::NonCanonical), (3, ParseError::Length), (5, ParseError::WorkFactor)]));
+	assert_eq!(mcf::invalid_lines(s.as_bytes()).count(), 0);
+	assert_eq!(mcf::invalid_lines(b"").count(), 0);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sealed_file_peppers() {
+	use super::{PepperSource, SealedFile};
+
+	let path = std::env::temp_dir().join(std::format!("bcrypt-only-pepper-{}", std::process::id()));
+	std::fs::write(&path, b"correct horse\r\n").unwrap();
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+
+		std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+		assert_eq!(SealedFile::new(&path).load_pepper().unwrap_err().kind(), std::io::ErrorKind::PermissionDenied);
+		std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+	}
+
+	let pepper = SealedFile::new(&path).load_pepper().unwrap();
+	assert_eq!(pepper.as_bytes(), b"correct horse");
+	assert_eq!(std::format!("{:?}", pepper), "Pepper { .. }");
+
+	std::fs::write(&path, b"\n").unwrap();
+	assert_eq!(SealedFile::new(&path).load_pepper().unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+	std::fs::remove_file(&path).unwrap();
+	assert_eq!(SealedFile::new(&path).load_pepper().unwrap_err().kind(), std::io::ErrorKind::NotFound);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn key_files() {
+	use super::{KeyFileError, KeyPolicy, hash_key_file, read_key_file, verify_key_file};
+
+	let path = std::env::temp_dir().join(std::format!("bcrypt-only-key-file-{}", std::process::id()));
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let strip = KeyPolicy { strip_newline: true, ..KeyPolicy::DEFAULT };
+	let keep = KeyPolicy::DEFAULT;
+
+	std::fs::write(&path, b"machine secret\r\n").unwrap();
+	assert_eq!(read_key_file(&path, &strip).unwrap().as_bytes(), b"machine secret");
+	assert_eq!(read_key_file(&path, &keep).unwrap().as_bytes(), b"machine secret\r\n");
+
+	let hash_string = hash_key_file(&path, &strip, &salt, WorkFactor::EXP4).unwrap();
+	assert_eq!(hash_string, super::mcf::hash(b"machine secret", &salt, WorkFactor::EXP4).unwrap());
+	assert!(verify_key_file(&path, &strip, &hash_string).unwrap());
+	assert!(!verify_key_file(&path, &keep, &hash_string).unwrap());
+
+	std::fs::write(&path, [b'k'; 72]).unwrap();
+	assert!(read_key_file(&path, &keep).is_ok());
+
+	std::fs::write(&path, b"\n\n").unwrap();
+	assert_eq!(read_key_file(&path, &strip).unwrap().as_bytes(), b"\n");
+
+	std::fs::write(&path, b"\n").unwrap();
+	assert!(matches!(read_key_file(&path, &KeyPolicy { reject_empty: true, ..strip }), Err(KeyFileError::Bcrypt(Empty))));
+
+	let mut long = std::vec![b'k'; 72];
+	long.extend_from_slice(b"\r\n");
+	std::fs::write(&path, &long).unwrap();
+	assert!(read_key_file(&path, &strip).is_ok());
+	assert!(matches!(read_key_file(&path, &keep), Err(KeyFileError::Bcrypt(Length))));
+
+	std::fs::write(&path, [b'k'; 4096]).unwrap();
+	assert!(matches!(read_key_file(&path, &strip), Err(KeyFileError::Bcrypt(Length))));
+
+	std::fs::write(&path, b"nul\0byte").unwrap();
+	assert!(matches!(hash_key_file(&path, &strip, &salt, WorkFactor::EXP4), Err(KeyFileError::Bcrypt(ZeroByte))));
+
+	std::fs::remove_file(&path).unwrap();
+	assert!(matches!(read_key_file(&path, &strip), Err(KeyFileError::Io(_))));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn password_reading() {
+	use super::{PASSWORD_SIZE_MAX, read_password};
+
+	let mut input = "secret\r\nother\nlast".as_bytes();
+	assert_eq!(&*read_password(&mut input).unwrap(), b"secret");
+	assert_eq!(&*read_password(&mut input).unwrap(), b"other");
+	assert_eq!(&*read_password(&mut input).unwrap(), b"last");
+	assert_eq!(read_password(&mut input).unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+
+	let mut long = std::vec![b'p'; PASSWORD_SIZE_MAX];
+	long.extend_from_slice(b"\r\n");
+	assert_eq!(read_password(&mut &long[..]).unwrap().len(), PASSWORD_SIZE_MAX);
+
+	let endless = std::vec![b'p'; 100 * PASSWORD_SIZE_MAX];
+	let mut input = &endless[..];
+	assert_eq!(read_password(&mut input).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+	assert_eq!(input.len(), endless.len() - PASSWORD_SIZE_MAX - 2);
+	assert_eq!(std::format!("{:?}", read_password(&mut "x\n".as_bytes()).unwrap()), "Password { .. }");
+}
+
+#[test]
+fn key_policies() {
+	use super::KeyPolicy;
+
+	let strip = KeyPolicy { strip_newline: true, ..KeyPolicy::DEFAULT };
+	assert_eq!(KeyPolicy::DEFAULT, KeyPolicy::default());
+	assert_eq!(KeyPolicy::DEFAULT.prepare(b"secret\n"), Ok(&b"secret\n"[..]));
+	assert_eq!(strip.prepare(b"secret\n"), Ok(&b"secret"[..]));
+	assert_eq!(strip.prepare(b"secret\r\n"), Ok(&b"secret"[..]));
+	assert_eq!(strip.prepare(b"secret\n\n"), Ok(&b"secret\n"[..]));
+	assert_eq!(strip.prepare(b"secret\r"), Ok(&b"secret\r"[..]));
+	assert_eq!(strip.prepare(b"\n"), Ok(&b""[..]));
+	assert_eq!(strip.prepare(b"nul\0\n"), Err(ZeroByte));
+
+	let mut long = [b'k'; 74];
+	long[72..].copy_from_slice(b"\r\n");
+	assert_eq!(strip.prepare(&long), Ok(&long[..72]));
+	assert_eq!(KeyPolicy::DEFAULT.prepare(&long), Err(Length));
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	assert_eq!(strip.bcrypt(b"secret\r\n", &salt, WorkFactor::EXP4), bcrypt(b"secret", &salt, WorkFactor::EXP4));
+
+	let reject_empty = KeyPolicy { strip_newline: true, reject_empty: true };
+	assert_eq!(reject_empty.prepare(b""), Err(Empty));
+	assert_eq!(reject_empty.prepare(b"\r\n"), Err(Empty));
+	assert_eq!(reject_empty.prepare(b"\n\n"), Ok(&b"\n"[..]));
+	assert_eq!(reject_empty.bcrypt(b"", &salt, WorkFactor::EXP4), Err(Empty));
+	assert_eq!(KeyPolicy::DEFAULT.prepare(b""), Ok(&b""[..]));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn os_str_passwords() {
+	use std::ffi::OsStr;
+
+	use super::Password;
+	use super::password::encode_wtf8;
+
+	assert_eq!(&*Password::from_os_str(OsStr::new("pässword")).unwrap(), "pässword".as_bytes());
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::ffi::OsStrExt;
+		assert_eq!(&*Password::from_os_str(OsStr::from_bytes(b"p\xe4ssword")).unwrap(), b"p\xe4ssword");
+	}
+
+	let wide: std::vec::Vec<u16> = "pässword 🔑".encode_utf16().collect();
+	assert_eq!(&*encode_wtf8(wide), "pässword 🔑".as_bytes());
+	assert_eq!(&*encode_wtf8([0x61, 0xd83d, 0x62, 0xdd11]), b"a\xed\xa0\xbdb\xed\xb4\x91");
+}
+
+#[test]
+fn interop_constants() {
+	use super::{BCRYPT_MESSAGE, BCRYPT_MESSAGE_ENCRYPTIONS, truncate_ciphertext};
+
+	assert_eq!(&BCRYPT_MESSAGE, b"OrpheanBeholderScryDoubt");
+	assert_eq!(BCRYPT_MESSAGE_ENCRYPTIONS, 64);
+	assert_eq!(truncate_ciphertext(&BCRYPT_MESSAGE), *b"OrpheanBeholderScryDoub");
+
+	#[cfg(feature = "unstable")]
+	{
+		let salt = Salt::from_bytes(&[0x5a; 16]);
+		let ciphertext = super::unstable::bcrypt_with_message(b"hunter2", &salt, WorkFactor::EXP4, &BCRYPT_MESSAGE).unwrap();
+		assert_eq!(truncate_ciphertext(&ciphertext), bcrypt(b"hunter2", &salt, WorkFactor::EXP4).unwrap());
+	}
+}
+
+#[test]
+fn split_batches() {
+	use super::backend::{BcryptBackend, HashJob, Join, Portable, SplitBatch, conformance};
+
+	/// Runs the other half on a thread, standing in for a second core.
+	struct ThreadJoin;
+
+	impl Join for ThreadJoin {
+		fn join(&self, other: &mut (dyn FnMut() + Send), this: &mut dyn FnMut()) {
+			std::thread::scope(|scope| {
+				let other = scope.spawn(other);
+				this();
+				other.join().unwrap();
+			});
+		}
+	}
+
+	let backend = SplitBatch { backend: Portable, join: ThreadJoin };
+	assert_eq!(backend.name(), "portable");
+	assert!(conformance(&backend).is_none());
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let keys: [&[u8]; 5] = [b"one", b"two", b"t\0ree", b"four", b"five"];
+	let jobs = keys.map(|key| HashJob { key, salt: &salt, work_factor: WorkFactor::EXP4 });
+	let mut results = [Err(Length); 5];
+	backend.hash_batch(&jobs, &mut results);
+
+	for (job, result) in jobs.iter().zip(&results) {
+		assert_eq!(*result, bcrypt(job.key, job.salt, job.work_factor));
+	}
+
+	let mut result = [Err(Length)];
+	backend.hash_batch(&jobs[..1], &mut result);
+	assert_eq!(result, [bcrypt(b"one", &salt, WorkFactor::EXP4)]);
+	backend.hash_batch(&[], &mut []);
+}
+
+#[test]
+fn cooperative_hashing() {
+	use super::BcryptState;
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let expected = bcrypt(b"hunter2", &salt, WorkFactor::EXP5).unwrap();
+
+	let mut calls = std::vec::Vec::new();
+	let hash = BcryptState::new(b"hunter2", &salt, WorkFactor::EXP5).unwrap().finish_with(10, |remaining| calls.push(remaining));
+	assert_eq!(hash, expected);
+	assert_eq!(calls, [22, 12, 2]);
+
+	let mut state = BcryptState::new(b"hunter2", &salt, WorkFactor::EXP5).unwrap();
+	let mut count = 0;
+	assert_eq!(state.finish_with(0, |_| count += 1), expected);
+	assert_eq!(count, 31);
+	assert_eq!(state.finish_with(1, |_| unreachable!()), expected);
+}
+
+#[test]
+fn legacy_digests() {
+	use super::verify_digest;
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash = bcrypt(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+	let mut full = [0xff_u8; 24];
+	full[..23].copy_from_slice(&hash);
+
+	assert_eq!(verify_digest(b"hunter2", &salt, WorkFactor::EXP4, &hash), Ok(true));
+	assert_eq!(verify_digest(b"hunter2", &salt, WorkFactor::EXP4, &full), Ok(true));
+	assert_eq!(verify_digest(b"hunter3", &salt, WorkFactor::EXP4, &full), Ok(false));
+	assert_eq!(verify_digest(b"hunter2", &salt, WorkFactor::EXP4, &hash[..22]), Ok(false));
+	assert_eq!(verify_digest(b"hunter2", &salt, WorkFactor::EXP4, &[]), Ok(false));
+
+	full[0] ^= 1;
+	assert_eq!(verify_digest(b"hunter2", &salt, WorkFactor::EXP4, &full), Ok(false));
+	assert_eq!(verify_digest(b"hunter\x002", &salt, WorkFactor::EXP4, &hash), Err(ZeroByte));
+
+	#[cfg(feature = "unstable")]
+	{
+		let ciphertext = super::unstable::bcrypt_with_message(b"hunter2", &salt, WorkFactor::EXP4, &super::BCRYPT_MESSAGE).unwrap();
+		assert_eq!(verify_digest(b"hunter2", &salt, WorkFactor::EXP4, &ciphertext), Ok(true));
+	}
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verify_padding() {
+	use std::time::{Duration, Instant};
+
+	use super::VerifyPadding;
+	use super::clock::SystemClock;
+	use super::mcf::{self, HashString};
+
+	let padding = VerifyPadding::new(SystemClock::new(), Duration::from_millis(20), Duration::from_secs(1));
+	assert_eq!(padding.padded_duration(), Duration::from_millis(20));
+
+	let start = Instant::now();
+	assert!(padding.run(|| HashString::parse("not a hash").is_err()));
+	assert!(start.elapsed() >= Duration::from_millis(20));
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let hash_string = mcf::hash(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+	assert_eq!(padding.verify(b"hunter2", &hash_string), Ok(true));
+	assert_eq!(padding.verify(b"hunter\x002", &hash_string), Err(ZeroByte));
+
+	padding.run(|| std::thread::sleep(Duration::from_millis(30)));
+	assert!(padding.padded_duration() >= Duration::from_millis(30));
+
+	let start = Instant::now();
+	padding.run(|| ());
+	assert!(start.elapsed() >= Duration::from_millis(30));
+}
+
+#[cfg(all(feature = "test-support", feature = "std"))]
+#[test]
+fn verify_padding_bounded() {
+	use core::time::Duration;
+
+	use super::VerifyPadding;
+	use super::clock::Clock;
+	use super::test_support::FakeClock;
+
+	let ms = Duration::from_millis;
+	let clock = FakeClock::new(Duration::ZERO);
+	let padding = VerifyPadding::new(&clock, ms(20), ms(100));
+
+	// Sleeping advances the fake clock instead of waiting.
+	padding.run(|| clock.advance(ms(5)));
+	assert_eq!(clock.now(), ms(20));
+
+	padding.run(|| clock.advance(ms(60)));
+	assert_eq!(padding.padded_duration(), ms(60));
+
+	// A stall longer than the maximum isn’t padded, and only raises the padding to the maximum.
+	let start = clock.now();
+	padding.run(|| clock.advance(ms(500)));
+	assert_eq!(clock.now() - start, ms(500));
+	assert_eq!(padding.padded_duration(), ms(100));
+
+	let start = clock.now();
+	padding.run(|| ());
+	assert_eq!(clock.now() - start, ms(100));
+}
+
+#[test]
+fn error_codes() {
+	assert_eq!(Length.error_code(), -1);
+	assert_eq!(ZeroByte.error_code(), -2);
+	assert_eq!(Empty.error_code(), -7);
+
+	#[cfg(feature = "mcf")]
+	assert_eq!(super::mcf::ParseError::NonCanonical.error_code(), -3);
+
+	#[cfg(feature = "compact-errors")]
+	{
+		assert_eq!(std::format!("{}", Length), "-1");
+		assert_eq!(std::format!("{}", ZeroByte), "-2");
+
+		#[cfg(feature = "mcf")]
+		assert_eq!(std::format!("{}", super::mcf::ParseError::Version), "-3");
+	}
+
+	#[cfg(not(feature = "compact-errors"))]
+	assert_eq!(std::format!("{}", Length), "password too long");
+}
+
+#[cfg(feature = "mcf")]
+#[test]
+fn verify_outcomes() {
+	use super::mcf::{self, HashString, Version, VerifyOutcome};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let v2b = mcf::hash(b"hunter2", &salt, WorkFactor::EXP5).unwrap();
+	let relabel = |version| HashString::parse(&v2b.replacen("2b", version, 1)).unwrap();
+
+	assert_eq!(mcf::verify_outcome(b"hunter2", &v2b, WorkFactor::EXP5), Ok(VerifyOutcome::Match));
+	assert_eq!(mcf::verify_outcome(b"hunter2", &v2b, WorkFactor::EXP4), Ok(VerifyOutcome::Match));
+	assert_eq!(mcf::verify_outcome(b"hunter2", &relabel("2y"), WorkFactor::EXP5), Ok(VerifyOutcome::Match));
+	assert_eq!(mcf::verify_outcome(b"hunter2", &v2b, WorkFactor::EXP6), Ok(VerifyOutcome::NeedsRehash { version: Version::V2b, work_factor: WorkFactor::EXP5 }));
+	assert_eq!(mcf::verify_outcome(b"hunter2", &relabel("2a"), WorkFactor::EXP5), Ok(VerifyOutcome::NeedsRehash { version: Version::V2a, work_factor: WorkFactor::EXP5 }));
+	assert_eq!(mcf::verify_outcome(b"hunter3", &relabel("2a"), WorkFactor::EXP6), Ok(VerifyOutcome::Mismatch));
+	assert_eq!(mcf::verify_outcome(b"hunter\x002", &v2b, WorkFactor::EXP5), Err(ZeroByte));
+
+	assert!(VerifyOutcome::Match.is_match());
+	assert!(VerifyOutcome::NeedsRehash { version: Version::V2a, work_factor: WorkFactor::EXP5 }.is_match());
+	assert!(!VerifyOutcome::Mismatch.is_match());
+	assert!(VerifyOutcome::LegacyQuirkMatch { policy: 0 }.is_match());
+	assert!(!VerifyOutcome::TimedOut.is_match());
+	assert!(VerifyOutcome::LegacyQuirkMatch { policy: 0 }.needs_rehash());
+	assert!(!VerifyOutcome::Match.needs_rehash());
+}
+
+#[cfg(feature = "derived-salt")]
+#[test]
+fn derived_salts() {
+	use super::sha512::hmac;
+
+	let namespace = *b"example.com/auth";
+	let salt = Salt::derive(&namespace, b"alice");
+
+	assert_eq!(salt.to_bytes()[..], hmac(&namespace, &[b"bcrypt-only salt\0alice"])[..16]);
+	assert_eq!(Salt::derive(&namespace, b"alice").to_bytes(), salt.to_bytes());
+	assert_ne!(Salt::derive(&namespace, b"bob").to_bytes(), salt.to_bytes());
+	assert_ne!(Salt::derive(b"example.org/auth", b"alice").to_bytes(), salt.to_bytes());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verifiers() {
+	use super::mcf::{self, Version, VerifyOutcome};
+	use super::{KeyPolicy, Pepper, Verifier, VerifierOptions};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+	let pepper = |bytes: &[u8]| Pepper::new(bytes.to_vec());
+
+	let mut options = VerifierOptions::new(WorkFactor::EXP5);
+	options.legacy_key_policies.push(KeyPolicy { strip_newline: true, ..KeyPolicy::DEFAULT });
+	options.peppers = std::vec![pepper(b"new pepper"), pepper(b"old pepper")];
+	let verifier = Verifier::new(options);
+
+	let current = verifier.hash(b"hunter2", &salt).unwrap();
+	assert_eq!(current.work_factor(), WorkFactor::EXP5);
+	assert_ne!(current, mcf::hash(b"hunter2", &salt, WorkFactor::EXP5).unwrap());
+	assert_eq!(verifier.verify(b"hunter2", Some(&current)), Ok(VerifyOutcome::Match));
+	assert_eq!(verifier.verify(b"hunter3", Some(&current)), Ok(VerifyOutcome::Mismatch));
+	assert_eq!(verifier.verify(b"hunter2\n", Some(&current)), Ok(VerifyOutcome::LegacyQuirkMatch { policy: 0 }));
+
+	let mut old_options = VerifierOptions::new(WorkFactor::EXP4);
+	old_options.peppers.push(pepper(b"old pepper"));
+	let old = Verifier::new(old_options).hash(b"hunter2", &salt).unwrap();
+	assert_eq!(verifier.verify(b"hunter2", Some(&old)), Ok(VerifyOutcome::NeedsRehash { version: Version::V2b, work_factor: WorkFactor::EXP4 }));
+
+	assert_eq!(verifier.verify(b"hunter2", None), Ok(VerifyOutcome::Mismatch));
+	assert_eq!(verifier.verify(b"hunter2", Some("$2b$05$garbage")), Ok(VerifyOutcome::Mismatch));
+	assert_eq!(verifier.verify(&[1; 73], None), Err(Length));
+
+	let unpeppered = Verifier::new(VerifierOptions::new(WorkFactor::EXP4));
+	let plain = mcf::hash(b"hunter2", &salt, WorkFactor::EXP4).unwrap();
+	assert_eq!(unpeppered.hash(b"hunter2", &salt), Ok(plain));
+	assert_eq!(unpeppered.verify(b"hunter2", Some(&plain)), Ok(VerifyOutcome::Match));
+}
+
+#[cfg(feature = "unstable")]
+#[test]
+fn extended_cost() {
+	use super::unstable::{ExtendedState, ExtendedWorkFactor};
+
+	let salt = Salt::from_bytes(&[0x5a; 16]);
+
+	assert_eq!(ExtendedWorkFactor::exp(3), None);
+	assert_eq!(ExtendedWorkFactor::exp(64), None);
+	assert_eq!(ExtendedWorkFactor::exp(63).unwrap().linear_rounds(), 1 << 63);
+	assert_eq!(ExtendedState::new(b"hunter2", &salt, ExtendedWorkFactor::exp(40).unwrap()).unwrap().remaining_rounds(), 1 << 40);
+
+	// Within bcrypt’s range, it’s bcrypt, and a checkpoint picks up where it left off.
+	let mut state = ExtendedState::new(b"hunter2", &salt, WorkFactor::EXP5.into()).unwrap();
+	assert_eq!(state.run(10), None);
+	let checkpoint = state.checkpoint();
+	drop(state);
+
+	let mut resumed = ExtendedState::resume(b"hunter2", &salt, &checkpoint).unwrap();
+	assert_eq!(resumed.remaining_rounds(), 22);
+	assert_eq!(resumed.run(100), Some(bcrypt(b"hunter2", &salt, WorkFactor::EXP5).unwrap()));
+	assert_eq!(ExtendedState::resume(&[1; 73], &salt, &checkpoint).map(drop), Err(Length));
+
+	#[cfg(feature = "mcf")]
+	{
+		use super::mcf::ParseError;
+		use super::unstable::{EXTENDED_HASH_STRING_SIZE, ExtendedHashString};
+
+		let hash = [0x3c; 23];
+		let hash_string = ExtendedHashString::new(ExtendedWorkFactor::exp(40).unwrap(), &salt, &hash);
+		let s = std::format!("{}", hash_string);
+		assert_eq!(s.len(), EXTENDED_HASH_STRING_SIZE);
+		assert_eq!(s[..15], *"$bcrypt-ext$40$");
+		assert_eq!(s[15..], super::mcf::HashString::new(super::mcf::Version::V2b, WorkFactor::EXP4, &salt, &hash)[7..]);
+		assert_eq!(ExtendedHashString::parse(&s), Ok(hash_string));
+		assert_eq!(hash_string.hash(), hash);
+		assert_eq!(hash_string.salt().to_bytes(), salt.to_bytes());
+
+		assert_eq!(ExtendedHashString::parse(&s.replacen("40", "64", 1)), Err(ParseError::WorkFactor));
+		assert_eq!(ExtendedHashString::parse(&s.replacen("ext", "EXT", 1)), Err(ParseError::Version));
+		assert_eq!(ExtendedHashString::parse(&s[1..]), Err(ParseError::Length));
+	}
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn load_simulation() {
+	use core::num::NonZeroUsize;
+	use super::{Simulation, simulate_load};
+
+	let report = simulate_load(&Simulation {
+		work_factor: WorkFactor::EXP4,
+		concurrency: NonZeroUsize::new(2).unwrap(),
+		operations: 22,
+		hashes: 1,
+		verifies: 3,
+	});
+
+	assert_eq!((report.hashes, report.verifies), (6, 16));
+	assert!(report.per_second() > 0.0);
+
+	let median = report.percentile(50.0).unwrap();
+	let slowest = report.percentile(100.0).unwrap();
+	assert!(report.percentile(0.0).unwrap() <= median && median <= report.percentile(99.0).unwrap());
+	assert_eq!(report.percentile(99.0), Some(slowest));
+
+	let empty = simulate_load(&Simulation {
+		work_factor: WorkFactor::EXP4,
+		concurrency: NonZeroUsize::new(1).unwrap(),
+		operations: 0,
+		hashes: 0,
+		verifies: 0,
+	});
+	assert_eq!((empty.hashes, empty.verifies, empty.percentile(50.0)), (0, 0, None));
+}
+
+#[cfg(feature = "bcrypt-pbkdf")]
+#[test]
+fn pbkdf() {
+	use super::{InvalidPbkdfParameters, PBKDF_OUTPUT_SIZE_MAX, bcrypt_pbkdf};
+
+	fn derive<const N: usize>(pass: &[u8], salt: &[u8], rounds: u32) -> [u8; N] {
+		let mut output = [0; N];
+		bcrypt_pbkdf(pass, salt, rounds, &mut output).unwrap();
+		output
+	}
+
+	// From OpenBSD’s regression tests.
+	assert_eq!(derive(b"password", b"salt", 4), *b"\x5b\xbf\x0c\xc2\x93\x58\x7f\x1c\x36\x35\x55\x5c\x27\x79\x65\x98\xd4\x7e\x57\x90\x71\xbf\x42\x7e\x9d\x8f\xbe\x84\x2a\xba\x34\xd9");
+	assert_eq!(derive(b"password", b"\x00", 4), *b"\xc1\x2b\x56\x62\x35\xee\xe0\x4c\x21\x25\x98\x97\x0a\x57\x9a\x67");
+	assert_eq!(derive(b"\x00", b"salt", 4), *b"\x60\x51\xbe\x18\xc2\xf4\xf8\x2c\xbf\x0e\xfe\xe5\x47\x1b\x4b\xb9");
+	assert_eq!(derive(b"password\x00", b"salt\x00", 4), *b"\x74\x10\xe4\x4c\xf4\xfa\x07\xbf\xaa\xc8\xa9\x28\xb1\x72\x7f\xac\x00\x13\x75\xe7\xbf\x73\x84\x37\x0f\x48\xef\xd1\x21\x74\x30\x50");
+	assert_eq!(derive(b"pass\x00wor", b"sa\x00l", 4), *b"\xc2\xbf\xfd\x9d\xb3\x8f\x65\x69\xef\xef\x43\x72\xf4\xde\x83\xc0");
+	assert_eq!(derive(b"pass\x00word", b"sa\x00lt", 4), *b"\x4b\xa4\xac\x39\x25\xc0\xe8\xd7\xf0\xcd\xb6\xbb\x16\x84\xa5\x6f");
+	assert_eq!(derive(b"password", b"salt", 8), *b"\xe1\x36\x7e\xc5\x15\x1a\x33\xfa\xac\x4c\xc1\xc1\x44\xcd\x23\xfa\x15\xd5\x54\x84\x93\xec\xc9\x9b\x9b\x5d\x9c\x0d\x3b\x27\xbe\xc7\x62\x27\xea\x66\x08\x8b\x84\x9b\x20\xab\x7a\xa4\x78\x01\x02\x46\xe7\x4b\xba\x51\x72\x3f\xef\xa9\xf9\x47\x4d\x65\x08\x84\x5e\x8d");
+	assert_eq!(derive(b"password", b"salt", 42), *b"\x83\x3c\xf0\xdc\xf5\x6d\xb6\x56\x08\xe8\xf0\xdc\x0c\xe8\x82\xbd");
+
+	let mut output = [0; 16];
+	assert_eq!(bcrypt_pbkdf(b"password", b"salt", 0, &mut output), Err(InvalidPbkdfParameters));
+	assert_eq!(bcrypt_pbkdf(b"", b"salt", 4, &mut output), Err(InvalidPbkdfParameters));
+	assert_eq!(bcrypt_pbkdf(b"password", b"", 4, &mut output), Err(InvalidPbkdfParameters));
+	assert_eq!(bcrypt_pbkdf(b"password", b"salt", 4, &mut []), Err(InvalidPbkdfParameters));
+	assert_eq!(bcrypt_pbkdf(b"password", b"salt", 4, &mut [0; PBKDF_OUTPUT_SIZE_MAX + 1]), Err(InvalidPbkdfParameters));
 }
@@ -0,0 +1,86 @@
+use core::hash::Hash;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::BcryptError;
+use super::clock::Clock;
+use super::mcf::{self, HashString};
+
+/// The result of a throttled verification.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ThrottledVerify {
+	/// The key matched, and the identity’s bucket was refilled.
+	Match,
+
+	/// The key didn’t match.
+	Mismatch,
+
+	/// The identity has no attempts left, so nothing was hashed. Another attempt will be allowed after `retry_after`.
+	Throttled {
+		retry_after: Duration,
+	},
+}
+
+/// Limits verification attempts per identity with a token bucket, so guessing at one account from many places is slowed down without separate infrastructure.
+///
+/// Each identity starts with `burst` attempts, and gets one more back every `interval` up to `burst` again. Every attempt takes one, whether or not it matches, but a match refills the bucket, so a user who gets their password wrong a few times and then right isn’t held back. An attempt with none left returns [`ThrottledVerify::Throttled`] without hashing.
+///
+/// Buckets that have refilled completely are forgotten, so memory is bounded by the number of identities with recent failures.
+#[derive(Debug)]
+pub struct Throttle<K, C> {
+	burst: u32,
+	interval: Duration,
+	clock: C,
+	// the time at which each identity’s bucket will be full again
+	buckets: Mutex<HashMap<K, Duration>>,
+}
+
+impl<K: Clone + Eq + Hash, C: Clock> Throttle<K, C> {
+	/// Creates a throttle allowing `burst` attempts at once and one more every `interval`. Panics if `burst` is 0.
+	pub fn new(clock: C, burst: u32, interval: Duration) -> Self {
+		assert!(burst != 0, "a throttle needs to allow at least one attempt");
+
+		Self {
+			burst,
+			interval,
+			clock,
+			buckets: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// [`mcf::verify`], if `identity` has an attempt left.
+	pub fn verify(&self, identity: K, key: &[u8], hash_string: &HashString) -> Result<ThrottledVerify, BcryptError> {
+		if let Some(retry_after) = self.take(&identity) {
+			return Ok(ThrottledVerify::Throttled { retry_after });
+		}
+
+		if mcf::verify(key, hash_string)? {
+			self.buckets.lock().unwrap().remove(&identity);
+			Ok(ThrottledVerify::Match)
+		} else {
+			Ok(ThrottledVerify::Mismatch)
+		}
+	}
+
+	/// Takes an attempt from an identity’s bucket, or returns how long until one is available.
+	fn take(&self, identity: &K) -> Option<Duration> {
+		let now = self.clock.now();
+		let mut buckets = self.buckets.lock().unwrap();
+		let full_at = buckets.get(identity).copied().filter(|&full_at| full_at > now).unwrap_or(now);
+		let next_full_at = full_at + self.interval;
+		let window = self.interval * self.burst;
+
+		if next_full_at - now > window {
+			return Some(next_full_at - now - window);
+		}
+
+		// forget full buckets instead of growing the map, which keeps pruning amortized constant-time
+		if buckets.len() == buckets.capacity() && !buckets.contains_key(identity) {
+			buckets.retain(|_, full_at| *full_at > now);
+		}
+
+		buckets.insert(identity.clone(), next_full_at);
+		None
+	}
+}
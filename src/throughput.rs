@@ -0,0 +1,58 @@
+use core::num::NonZeroUsize;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::vec::Vec;
+
+use super::{Salt, WorkFactor, bcrypt_runtime};
+
+/// The result of a throughput measurement.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Throughput {
+	/// The total number of hashes completed across all threads.
+	pub hashes: u64,
+
+	/// The wall-clock time the measurement took.
+	pub elapsed: Duration,
+}
+
+impl Throughput {
+	/// The sustained number of hashes completed per second.
+	pub fn per_second(&self) -> f64 {
+		self.hashes as f64 / self.elapsed.as_secs_f64()
+	}
+}
+
+/// Measures sustained bcrypt throughput at a work factor by hashing continuously on `threads` threads for at least `duration`. Each thread finishes the hash it’s working on when the time runs out, so the measurement can run over by up to one hash.
+pub fn measure_throughput(work_factor: WorkFactor, duration: Duration, threads: NonZeroUsize) -> Throughput {
+	let start = Instant::now();
+	let deadline = start + duration;
+
+	let hashes = thread::scope(|scope| {
+		let workers: Vec<_> = (0..threads.get()).map(|t| {
+			scope.spawn(move || {
+				let mut salt_bytes = [0_u8; 16];
+				salt_bytes[..8].copy_from_slice(&(t as u64).to_be_bytes());
+				let salt = Salt::from_bytes(&salt_bytes);
+				let mut count = 0_u64;
+
+				loop {
+					bcrypt_runtime(b"throughput", &salt, work_factor).unwrap();
+					count += 1;
+
+					if Instant::now() >= deadline {
+						break count;
+					}
+				}
+			})
+		}).collect();
+
+		workers.into_iter()
+			.map(|w| w.join().unwrap())
+			.sum()
+	});
+
+	Throughput {
+		hashes,
+		elapsed: start.elapsed(),
+	}
+}
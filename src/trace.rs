@@ -0,0 +1,47 @@
+//! Spans and events for the `tracing` feature.
+
+use core::convert::TryFrom;
+use std::time::Instant;
+
+use tracing::field::Empty;
+use tracing::span::EnteredSpan;
+
+use super::{BcryptError, WorkFactor};
+
+/// A `bcrypt` span, entered until [`finish`](Self::finish) records how the operation ended and how long it took.
+pub struct Traced {
+	span: EnteredSpan,
+	start: Instant,
+}
+
+impl Traced {
+	/// Enters a span for `operation` – `"hash"` or `"verify"` – at `work_factor`, run by the backend called `backend`.
+	pub fn start(operation: &'static str, backend: &'static str, work_factor: WorkFactor) -> Self {
+		let span = tracing::info_span!("bcrypt", operation, cost = work_factor.log_rounds(), backend, outcome = Empty, error_code = Empty, duration_us = Empty);
+		Self { span: span.entered(), start: Instant::now() }
+	}
+
+	/// Records `outcome` – `"ok"` for a hash, `"match"` or `"mismatch"` for a verification – and the duration on the span, and emits an event with both.
+	pub fn finish(self, outcome: &'static str) {
+		let duration_us = self.duration_us();
+
+		self.span.record("outcome", outcome);
+		self.span.record("duration_us", duration_us);
+		tracing::info!(outcome, duration_us, "bcrypt finished");
+	}
+
+	/// Records an `"error"` outcome with the error’s code, like [`finish`](Self::finish).
+	pub fn fail(self, err: BcryptError) {
+		let duration_us = self.duration_us();
+		let error_code = err.error_code();
+
+		self.span.record("outcome", "error");
+		self.span.record("error_code", error_code);
+		self.span.record("duration_us", duration_us);
+		tracing::info!(outcome = "error", error_code, duration_us, "bcrypt finished");
+	}
+
+	fn duration_us(&self) -> u64 {
+		u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX)
+	}
+}
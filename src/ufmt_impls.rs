@@ -0,0 +1,175 @@
+//! `uDisplay` and `uDebug` for the `ufmt` feature, writing what the `core::fmt` impls do.
+
+use ufmt::{Formatter, uDebug, uDisplay, uWrite};
+
+use super::{BcryptError, InvalidWorkFactor, KeyBufError, WorkFactor};
+
+#[cfg(feature = "mcf")]
+use super::{Salt, base64, mcf::{HashString, ParseError, SALT_STRING_SIZE, Setting, Version, VerifyOutcome}};
+
+#[cfg(feature = "bcrypt-pbkdf")]
+use super::InvalidPbkdfParameters;
+
+impl uDisplay for BcryptError {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(self.description())
+	}
+}
+
+impl uDebug for BcryptError {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(match self {
+			BcryptError::Length => "Length",
+			BcryptError::ZeroByte => "ZeroByte",
+			BcryptError::Empty => "Empty",
+		})
+	}
+}
+
+impl uDisplay for InvalidWorkFactor {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str("bcrypt work factor outside 4 to 31")
+	}
+}
+
+impl uDebug for InvalidWorkFactor {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.debug_tuple("InvalidWorkFactor")?.field(&self.0)?.finish()
+	}
+}
+
+impl uDisplay for KeyBufError {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(match self {
+			KeyBufError::Full => "password buffer full",
+			KeyBufError::ZeroByte => "password contains a NUL character",
+		})
+	}
+}
+
+impl uDebug for KeyBufError {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(match self {
+			KeyBufError::Full => "Full",
+			KeyBufError::ZeroByte => "ZeroByte",
+		})
+	}
+}
+
+#[cfg(feature = "bcrypt-pbkdf")]
+impl uDisplay for InvalidPbkdfParameters {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str("invalid bcrypt_pbkdf parameters")
+	}
+}
+
+#[cfg(feature = "bcrypt-pbkdf")]
+impl uDebug for InvalidPbkdfParameters {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str("InvalidPbkdfParameters")
+	}
+}
+
+/// The base-2 exponent, like `12`. `WorkFactor` has no `core::fmt::Display`.
+impl uDisplay for WorkFactor {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		uDisplay::fmt(&self.log_rounds(), f)
+	}
+}
+
+impl uDebug for WorkFactor {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.debug_tuple("WorkFactor")?.field(&self.log_rounds())?.finish()
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDisplay for ParseError {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(self.description())
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDebug for ParseError {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(match self {
+			ParseError::Length => "Length",
+			ParseError::Version => "Version",
+			ParseError::WorkFactor => "WorkFactor",
+			ParseError::Encoding => "Encoding",
+			ParseError::NonCanonical => "NonCanonical",
+		})
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDisplay for HashString {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(self.as_str())
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDebug for HashString {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		debug_str(f, "HashString", self.as_str())
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDisplay for Setting {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(self.as_str())
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDebug for Setting {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		debug_str(f, "Setting", self.as_str())
+	}
+}
+
+/// Formats a salt in bcrypt’s base64, as it appears in a hash string.
+#[cfg(feature = "mcf")]
+impl uDisplay for Salt {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		let mut chars = [0_u8; SALT_STRING_SIZE];
+		base64::encode(&self.to_bytes(), &mut chars);
+		f.write_str(core::str::from_utf8(&chars).unwrap())
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDebug for Version {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		f.write_str(match self {
+			Version::V2a => "V2a",
+			Version::V2b => "V2b",
+			Version::V2y => "V2y",
+		})
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl uDebug for VerifyOutcome {
+	fn fmt<W: uWrite + ?Sized>(&self, f: &mut Formatter<'_, W>) -> Result<(), W::Error> {
+		match self {
+			VerifyOutcome::Match => f.write_str("Match"),
+			VerifyOutcome::Mismatch => f.write_str("Mismatch"),
+			VerifyOutcome::NeedsRehash { version, work_factor } => f.debug_struct("NeedsRehash")?.field("version", version)?.field("work_factor", work_factor)?.finish(),
+			VerifyOutcome::LegacyQuirkMatch { policy } => f.debug_struct("LegacyQuirkMatch")?.field("policy", policy)?.finish(),
+			VerifyOutcome::TimedOut => f.write_str("TimedOut"),
+		}
+	}
+}
+
+/// Writes a tuple struct holding a string, as `core::fmt`’s `Debug` would, for strings with no characters it escapes. ufmt has no `uDebug` for `str`.
+#[cfg(feature = "mcf")]
+fn debug_str<W: uWrite + ?Sized>(f: &mut Formatter<'_, W>, name: &str, s: &str) -> Result<(), W::Error> {
+	f.write_str(name)?;
+	f.write_str("(\"")?;
+	f.write_str(s)?;
+	f.write_str("\")")
+}
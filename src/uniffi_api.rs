@@ -0,0 +1,72 @@
+//! `hash`, `verify`, `needs_rehash`, and `calibrate` for Kotlin and Swift, through UniFFI. Build with `cargo rustc --lib --features uniffi --release --crate-type cdylib`, then run `uniffi-bindgen generate --library` on the library for each language’s bindings.
+
+use core::fmt;
+use core::time::Duration;
+use std::string::String;
+
+use super::clock::SystemClock;
+use super::mcf::{self, HashString, ParseError, Version};
+use super::rng::OsRng;
+use super::{BcryptError, ClockStalled, Salt, WorkFactor};
+
+/// An error from the UniFFI API, thrown as an exception with the error’s message.
+#[derive(Debug, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum HashError {
+	/// The cost wasn’t 4 to 31.
+	InvalidCost,
+	/// The hash string wasn’t a valid `$2a$`, `$2b$`, or `$2y$` hash string.
+	InvalidHashString(ParseError),
+	/// The password was too long or contained a NUL character.
+	InvalidPassword(BcryptError),
+	/// The clock didn’t advance while calibrating.
+	ClockStalled,
+}
+
+impl fmt::Display for HashError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::InvalidCost => f.write_str("invalid cost; expected 4 to 31"),
+			Self::InvalidHashString(err) => err.fmt(f),
+			Self::InvalidPassword(err) => err.fmt(f),
+			Self::ClockStalled => ClockStalled.fmt(f),
+		}
+	}
+}
+
+fn work_factor(cost: u32) -> Result<WorkFactor, HashError> {
+	WorkFactor::exp(cost).ok_or(HashError::InvalidCost)
+}
+
+fn parse(hash_string: &str) -> Result<HashString, HashError> {
+	HashString::parse(hash_string).map_err(HashError::InvalidHashString)
+}
+
+/// Hashes a password into a `$2b$` hash string with a salt from the operating system.
+#[uniffi::export]
+pub fn hash(password: String, cost: u32) -> Result<String, HashError> {
+	let hash_string = mcf::hash(password.as_bytes(), &Salt::generate(OsRng), work_factor(cost)?).map_err(HashError::InvalidPassword)?;
+	Ok(hash_string.as_str().into())
+}
+
+/// Checks whether a password matches a `$2a$`, `$2b$`, or `$2y$` hash string.
+#[uniffi::export]
+pub fn verify(password: String, hash_string: String) -> Result<bool, HashError> {
+	mcf::verify(password.as_bytes(), &parse(&hash_string)?).map_err(HashError::InvalidPassword)
+}
+
+/// Checks whether a hash string should be replaced after a successful login: its cost is below `cost`, or it’s a `$2a$` hash string.
+#[uniffi::export]
+pub fn needs_rehash(hash_string: String, cost: u32) -> Result<bool, HashError> {
+	let target = work_factor(cost)?;
+	let hash_string = parse(&hash_string)?;
+	Ok(hash_string.work_factor() < target || hash_string.version() == Version::V2a)
+}
+
+/// Finds the highest cost whose hash takes at most `target_ms` milliseconds on this device, timing hashes on the system clock.
+#[uniffi::export]
+pub fn calibrate(target_ms: u64) -> Result<u32, HashError> {
+	super::calibrate(Duration::from_millis(target_ms), SystemClock::new())
+		.map(WorkFactor::log_rounds)
+		.map_err(|ClockStalled| HashError::ClockStalled)
+}
@@ -0,0 +1,228 @@
+//! Experimental APIs, published to gather feedback before their designs settle. **Nothing here is covered by semver:** any of it can change or disappear in a patch release, so depend on an exact version when enabling the `unstable` feature.
+
+use core::fmt;
+
+use super::{BLF_N, BcryptError, BcryptState, HASH_SIZE, Salt, WorkFactor, bcrypt_round, bcrypt_setup, check_key, encipher_message, record_rounds, runtime_initial_state};
+
+#[cfg(feature = "mcf")]
+use super::mcf::{self, HashString, ParseError, Version};
+
+/// bcrypt with a different 24-byte message in place of `OrpheanBeholderScryDoubt`, returning all 24 bytes of the enciphered result rather than the 23 that bcrypt keeps. With the standard message, the first 23 bytes are the bcrypt hash.
+///
+/// For domain-separating bcrypt-derived values in new protocols. The result is not a bcrypt hash, and no other implementation will reproduce it unless it makes the same substitution.
+pub fn bcrypt_with_message(key: &[u8], salt: &Salt, work_factor: WorkFactor, message: &[u8; 24]) -> Result<[u8; 24], BcryptError> {
+	check_key(key)?;
+
+	let mut state = runtime_initial_state();
+	bcrypt_setup(&mut state, key, salt);
+
+	for _ in 0..work_factor.linear_rounds() {
+		bcrypt_round(&mut state, key, salt);
+	}
+
+	record_rounds(work_factor.linear_rounds().into());
+
+	let mut words = [0_u32; 6];
+
+	for (word, chunk) in words.iter_mut().zip(message.chunks_exact(4)) {
+		*word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+	}
+
+	let cdata = encipher_message(&state, words);
+	state.zero();
+
+	let mut result = [0_u8; 24];
+
+	for (chunk, word) in result.chunks_exact_mut(4).zip(&cdata) {
+		chunk.copy_from_slice(&word.to_be_bytes());
+	}
+
+	Ok(result)
+}
+
+/// A work factor for [`ExtendedState`]: a base-2 exponent of up to 63, for derivations meant to take hours or days – archival key derivation, or time-lock puzzles. Up to 31, it’s the same as a [`WorkFactor`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ExtendedWorkFactor(u32);
+
+impl ExtendedWorkFactor {
+	/// Creates an extended work factor from a base-2 exponent between 4 and 63.
+	pub const fn exp(log_rounds: u32) -> Option<Self> {
+		if log_rounds >= 4 && log_rounds <= 63 {
+			Some(Self(log_rounds))
+		} else {
+			None
+		}
+	}
+
+	/// The base-2 exponent.
+	pub const fn log_rounds(self) -> u32 {
+		self.0
+	}
+
+	/// The number of rounds.
+	pub const fn linear_rounds(self) -> u64 {
+		1 << self.0
+	}
+}
+
+impl From<WorkFactor> for ExtendedWorkFactor {
+	fn from(work_factor: WorkFactor) -> Self {
+		Self(work_factor.log_rounds())
+	}
+}
+
+/// The size of an [`ExtendedState`] checkpoint: the remaining round count, then the Blowfish subkeys and S-boxes, all big-endian.
+pub const CHECKPOINT_SIZE: usize = 8 + 4 * (BLF_N + 2 + 4 * 256);
+
+/// A [`BcryptState`] with a 64-bit round counter, for an [`ExtendedWorkFactor`]. Up to a work factor of 31, the hash is bcrypt’s.
+///
+/// A derivation that takes hours should survive a restart, so the state can be saved with [`checkpoint`](Self::checkpoint) and continued with [`resume`](Self::resume).
+pub struct ExtendedState {
+	inner: BcryptState,
+}
+
+impl ExtendedState {
+	/// Starts hashing, doing the cheap setup but none of the expensive rounds. Fails for the same keys as [`bcrypt`](crate::bcrypt).
+	pub fn new(key: &[u8], salt: &Salt, work_factor: ExtendedWorkFactor) -> Result<Self, BcryptError> {
+		check_key(key)?;
+
+		let mut state = runtime_initial_state();
+		bcrypt_setup(&mut state, key, salt);
+
+		Ok(Self {
+			inner: BcryptState::resume(key, salt, state, work_factor.linear_rounds())?,
+		})
+	}
+
+	/// Runs up to `rounds` more expensive rounds. Returns the hash once all of them have run, and on every call after that.
+	pub fn run(&mut self, rounds: u32) -> Option<[u8; HASH_SIZE]> {
+		self.inner.run(rounds)
+	}
+
+	/// The number of expensive rounds left to run.
+	pub const fn remaining_rounds(&self) -> u64 {
+		self.inner.remaining_rounds()
+	}
+
+	/// Saves the hash’s progress. The key and salt aren’t included, and have to be given again to resume.
+	///
+	/// The checkpoint is derived from the key as much as the finished hash would be, so store it as carefully, and zero it once it’s no longer needed.
+	pub fn checkpoint(&self) -> [u8; CHECKPOINT_SIZE] {
+		let state = self.inner.blowfish_state();
+		let mut checkpoint = [0_u8; CHECKPOINT_SIZE];
+		let (remaining, words) = checkpoint.split_at_mut(8);
+		remaining.copy_from_slice(&self.remaining_rounds().to_be_bytes());
+
+		for (chunk, word) in words.chunks_exact_mut(4).zip(state.p.iter().chain(state.s.iter().flatten())) {
+			chunk.copy_from_slice(&word.to_be_bytes());
+		}
+
+		checkpoint
+	}
+
+	/// Continues a hash from a [`checkpoint`](Self::checkpoint), given the same key and salt it was started with. A different key or salt gives a wrong hash rather than an error.
+	pub fn resume(key: &[u8], salt: &Salt, checkpoint: &[u8; CHECKPOINT_SIZE]) -> Result<Self, BcryptError> {
+		let (remaining, words) = checkpoint.split_at(8);
+		let mut words = words.chunks_exact(4).map(|chunk| u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+		let mut state = runtime_initial_state();
+
+		for word in state.p.iter_mut().chain(state.s.iter_mut().flatten()) {
+			*word = words.next().unwrap();
+		}
+
+		let mut remaining_rounds = [0_u8; 8];
+		remaining_rounds.copy_from_slice(remaining);
+
+		Ok(Self {
+			inner: BcryptState::resume(key, salt, state, u64::from_be_bytes(remaining_rounds))?,
+		})
+	}
+}
+
+impl fmt::Debug for ExtendedState {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("ExtendedState")
+			.field("remaining_rounds", &self.remaining_rounds())
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(feature = "mcf")]
+const EXTENDED_PREFIX: &str = "$bcrypt-ext$";
+
+/// The length of an [`ExtendedHashString`].
+#[cfg(feature = "mcf")]
+pub const EXTENDED_HASH_STRING_SIZE: usize = EXTENDED_PREFIX.len() + mcf::HASH_STRING_SIZE - 4;
+
+/// A hash from an [`ExtendedState`], serialized as `$bcrypt-ext$`, the work factor in two digits, `$`, and the salt and hash as in a bcrypt hash string. It’s a prefix of its own, so nothing expecting bcrypt mistakes one for a bcrypt hash, even at a work factor bcrypt supports.
+#[cfg(feature = "mcf")]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct ExtendedHashString {
+	work_factor: ExtendedWorkFactor,
+	salt_and_hash: HashString,
+}
+
+#[cfg(feature = "mcf")]
+impl ExtendedHashString {
+	/// Formats the parts of an extended hash.
+	pub fn new(work_factor: ExtendedWorkFactor, salt: &Salt, hash: &[u8; HASH_SIZE]) -> Self {
+		Self {
+			work_factor,
+			salt_and_hash: HashString::new(Version::V2b, WorkFactor::EXP4, salt, hash),
+		}
+	}
+
+	/// Parses an extended hash string.
+	pub fn parse(s: &str) -> Result<Self, ParseError> {
+		if s.len() != EXTENDED_HASH_STRING_SIZE {
+			return Err(ParseError::Length);
+		}
+
+		let rest = s.strip_prefix(EXTENDED_PREFIX).ok_or(ParseError::Version)?;
+		let digits = rest.as_bytes();
+
+		if !digits[0].is_ascii_digit() || !digits[1].is_ascii_digit() || digits[2] != b'$' {
+			return Err(ParseError::WorkFactor);
+		}
+
+		let work_factor = ExtendedWorkFactor::exp(u32::from(digits[0] - b'0') * 10 + u32::from(digits[1] - b'0')).ok_or(ParseError::WorkFactor)?;
+
+		let mut bytes = [0_u8; mcf::HASH_STRING_SIZE];
+		bytes[..7].copy_from_slice(b"$2b$04$");
+		bytes[7..].copy_from_slice(&digits[3..]);
+
+		Ok(Self {
+			work_factor,
+			salt_and_hash: HashString::parse(core::str::from_utf8(&bytes).map_err(|_| ParseError::Encoding)?)?,
+		})
+	}
+
+	/// The work factor.
+	pub const fn work_factor(&self) -> ExtendedWorkFactor {
+		self.work_factor
+	}
+
+	/// The salt.
+	pub const fn salt(&self) -> Salt {
+		self.salt_and_hash.salt()
+	}
+
+	/// The hash.
+	pub const fn hash(&self) -> [u8; HASH_SIZE] {
+		self.salt_and_hash.hash()
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl fmt::Display for ExtendedHashString {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}{:02}${}", EXTENDED_PREFIX, self.work_factor.log_rounds(), &self.salt_and_hash.as_str()[7..])
+	}
+}
+
+#[cfg(feature = "mcf")]
+impl fmt::Debug for ExtendedHashString {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_tuple("ExtendedHashString").field(&format_args!("{}", self)).finish()
+	}
+}
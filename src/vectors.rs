@@ -0,0 +1,314 @@
+//! Checking this implementation against external test vector files, so conformance with upstream vector sets can be refreshed without regenerating the files built into the tests.
+//!
+//! Two formats are read: pyca/bcrypt’s, with [`check_pyca_vectors`], and a line-based one that suits generated corpora, with [`check_vectors`].
+
+use std::fmt;
+use std::io::{self, BufRead};
+use std::str;
+use std::vec::Vec;
+
+use super::mcf::{self, HashString, Setting};
+use super::{KEY_SIZE_MAX, bcrypt_runtime};
+
+/// An error checking test vectors.
+#[derive(Debug)]
+pub enum VectorError {
+	/// Reading the vectors failed.
+	Io(io::Error),
+
+	/// A line wasn’t a valid test vector.
+	Syntax {
+		/// The 1-based line number.
+		line: usize,
+	},
+
+	/// A vector’s hash didn’t match.
+	Mismatch {
+		/// The 1-based line number.
+		line: usize,
+	},
+}
+
+impl fmt::Display for VectorError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			VectorError::Io(err) => write!(f, "failed to read test vectors: {}", err),
+			VectorError::Syntax { line } => write!(f, "invalid test vector on line {}", line),
+			VectorError::Mismatch { line } => write!(f, "test vector on line {} failed", line),
+		}
+	}
+}
+
+impl std::error::Error for VectorError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			VectorError::Io(err) => Some(err),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for VectorError {
+	fn from(err: io::Error) -> Self {
+		VectorError::Io(err)
+	}
+}
+
+fn decode_hex(hex: &str, bytes: &mut [u8; KEY_SIZE_MAX]) -> Option<usize> {
+	let hex = hex.as_bytes();
+
+	if hex.len() % 2 != 0 || hex.len() / 2 > bytes.len() {
+		return None;
+	}
+
+	for (b, pair) in bytes.iter_mut().zip(hex.chunks_exact(2)) {
+		let high = char::from(pair[0]).to_digit(16)?;
+		let low = char::from(pair[1]).to_digit(16)?;
+		*b = (high << 4 | low) as u8;
+	}
+
+	Some(hex.len() / 2)
+}
+
+/// Checks a key against a hash string both by hashing the key with the hash string’s parameters and by verifying it against the hash string.
+fn check_vector(key: &[u8], hash_string: &HashString) -> bool {
+	let hashed = bcrypt_runtime(key, &hash_string.salt(), hash_string.work_factor());
+	let verified = mcf::verify(key, hash_string);
+	hashed == Ok(hash_string.hash()) && verified == Ok(true)
+}
+
+/// Checks every test vector read from `reader`, one per line: the key in hexadecimal, a comma, and the expected hash string. Blank lines and lines starting with `#` are ignored. Returns the number of vectors checked.
+pub fn check_vectors<R: BufRead>(reader: R) -> Result<usize, VectorError> {
+	let mut count = 0;
+
+	for (index, line) in reader.lines().enumerate() {
+		let line_number = index + 1;
+		let line = line?;
+		let line = line.trim();
+
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let syntax_error = || VectorError::Syntax { line: line_number };
+		let (key_hex, hash_string) = line.split_once(',').ok_or_else(syntax_error)?;
+		let mut key = [0_u8; KEY_SIZE_MAX];
+		let key_len = decode_hex(key_hex, &mut key).ok_or_else(syntax_error)?;
+		let key = &key[..key_len];
+		let hash_string = HashString::parse(hash_string).map_err(|_| syntax_error())?;
+
+		if !check_vector(key, &hash_string) {
+			return Err(VectorError::Mismatch { line: line_number });
+		}
+
+		count += 1;
+	}
+
+	Ok(count)
+}
+
+/// A token of the Python subset pyca/bcrypt’s vectors are written in.
+#[derive(Debug, Eq, PartialEq)]
+enum Token {
+	OpenBracket,
+	CloseBracket,
+	OpenParen,
+	CloseParen,
+	Comma,
+	Bytes(Vec<u8>),
+	End,
+}
+
+/// Splits Python source into [`Token`]s, tracking the line each one starts on.
+struct Tokens<'a> {
+	source: &'a [u8],
+	position: usize,
+	line: usize,
+}
+
+impl Tokens<'_> {
+	fn peek_byte(&self) -> Option<u8> {
+		self.source.get(self.position).copied()
+	}
+
+	fn next_byte(&mut self) -> Option<u8> {
+		let b = self.peek_byte()?;
+		self.position += 1;
+
+		if b == b'\n' {
+			self.line += 1;
+		}
+
+		Some(b)
+	}
+
+	fn next_hex_digit(&mut self) -> Option<u8> {
+		char::from(self.next_byte()?).to_digit(16).map(|digit| digit as u8)
+	}
+
+	/// Reads the rest of a byte string literal after its opening quote, decoding the escapes the vectors use.
+	fn bytes(&mut self, quote: u8) -> Option<Vec<u8>> {
+		let mut bytes = Vec::new();
+
+		loop {
+			let b = match self.next_byte()? {
+				b'\n' => return None,
+				b if b == quote => return Some(bytes),
+				b'\\' => match self.next_byte()? {
+					b'x' => self.next_hex_digit()? << 4 | self.next_hex_digit()?,
+					b'n' => b'\n',
+					b'r' => b'\r',
+					b't' => b'\t',
+					b'0' => 0,
+					b @ (b'\\' | b'\'' | b'"') => b,
+					_ => return None,
+				},
+				b if b.is_ascii() => b,
+				_ => return None,
+			};
+
+			bytes.push(b);
+		}
+	}
+
+	/// The next token and the line it starts on, or `None` for anything outside the subset.
+	fn next_token(&mut self) -> (usize, Option<Token>) {
+		loop {
+			match self.peek_byte() {
+				Some(b'#') => {
+					while self.peek_byte().is_some_and(|b| b != b'\n') {
+						self.position += 1;
+					}
+				}
+				Some(b) if b.is_ascii_whitespace() => {
+					self.next_byte();
+				}
+				_ => break,
+			}
+		}
+
+		let line = self.line;
+
+		let token = match self.next_byte() {
+			None => Some(Token::End),
+			Some(b'[') => Some(Token::OpenBracket),
+			Some(b']') => Some(Token::CloseBracket),
+			Some(b'(') => Some(Token::OpenParen),
+			Some(b')') => Some(Token::CloseParen),
+			Some(b',') => Some(Token::Comma),
+			Some(b'b') => match self.next_byte() {
+				Some(quote @ (b'"' | b'\'')) => self.bytes(quote).map(Token::Bytes),
+				_ => None,
+			},
+			Some(_) => None,
+		};
+
+		// adjacent literals are concatenated, which the vectors use to split long keys across lines
+		if let Some(Token::Bytes(mut bytes)) = token {
+			loop {
+				let (position, next_line) = (self.position, self.line);
+
+				match self.next_token().1 {
+					Some(Token::Bytes(more)) => bytes.extend(more),
+					_ => {
+						self.position = position;
+						self.line = next_line;
+						return (line, Some(Token::Bytes(bytes)));
+					}
+				}
+			}
+		}
+
+		(line, token)
+	}
+
+	fn expect(&mut self, expected: Token) -> Option<()> {
+		(self.next_token().1? == expected).then_some(())
+	}
+
+	fn next_bytes(&mut self) -> Option<Vec<u8>> {
+		match self.next_token().1? {
+			Token::Bytes(bytes) => Some(bytes),
+			_ => None,
+		}
+	}
+
+	/// Parses the rest of a `(password, salt, expected)` tuple after its opening parenthesis.
+	fn vector(&mut self) -> Option<[Vec<u8>; 3]> {
+		let password = self.next_bytes()?;
+		self.expect(Token::Comma)?;
+		let salt = self.next_bytes()?;
+		self.expect(Token::Comma)?;
+		let expected = self.next_bytes()?;
+
+		match self.next_token().1? {
+			Token::Comma => self.expect(Token::CloseParen)?,
+			Token::CloseParen => {}
+			_ => return None,
+		}
+
+		Some([password, salt, expected])
+	}
+}
+
+/// Checks every test vector read from `reader` in the format pyca/bcrypt’s test suite keeps them in: a Python list of `(password, salt, expected)` tuples of byte string literals, where the salt is the setting the expected hash string was computed with. Comments are ignored, so the list can be saved with its license header. Returns the number of vectors checked, and reports errors by the line each vector starts on.
+pub fn check_pyca_vectors<R: BufRead>(mut reader: R) -> Result<usize, VectorError> {
+	let mut source = Vec::new();
+	reader.read_to_end(&mut source)?;
+
+	let mut tokens = Tokens {
+		source: &source,
+		position: 0,
+		line: 1,
+	};
+
+	let (line, token) = tokens.next_token();
+
+	if token != Some(Token::OpenBracket) {
+		return Err(VectorError::Syntax { line });
+	}
+
+	let mut count = 0;
+
+	loop {
+		let (line, token) = tokens.next_token();
+
+		match token {
+			Some(Token::OpenParen) => {}
+			Some(Token::CloseBracket) => break,
+			_ => return Err(VectorError::Syntax { line }),
+		}
+
+		let syntax_error = VectorError::Syntax { line };
+		let [password, salt, expected] = tokens.vector().ok_or(syntax_error)?;
+		let setting = str::from_utf8(&salt).ok().and_then(|salt| Setting::parse(salt).ok());
+		let hash_string = str::from_utf8(&expected).ok().and_then(|expected| HashString::parse(expected).ok());
+
+		let (Some(setting), Some(hash_string)) = (setting, hash_string) else {
+			return Err(VectorError::Syntax { line });
+		};
+
+		if setting != hash_string.setting() || !check_vector(&password, &hash_string) {
+			return Err(VectorError::Mismatch { line });
+		}
+
+		count += 1;
+
+		let (line, token) = tokens.next_token();
+
+		match token {
+			Some(Token::Comma) => {}
+			Some(Token::CloseBracket) => break,
+			_ => return Err(VectorError::Syntax { line }),
+		}
+	}
+
+	let (line, token) = tokens.next_token();
+
+	if token != Some(Token::End) {
+		return Err(VectorError::Syntax { line });
+	}
+
+	Ok(count)
+}
+
@@ -0,0 +1,148 @@
+use core::fmt;
+use core::hint::black_box;
+use std::boxed::Box;
+use std::vec::Vec;
+
+use super::{BcryptError, HASH_SIZE, KeyPolicy, Pepper, Salt, WorkFactor, base64};
+use super::mcf::{HashString, Version, VerifyOutcome, hashes_equal};
+use super::observer::{NoopObserver, Observer, bcrypt_observed};
+use super::sha512::hmac;
+
+const PEPPER_MAC_SIZE: usize = 48;
+
+/// The salt of the dummy hash run when there’s no stored hash to verify against. What it is doesn’t matter, only that the hash costs the same as a real one.
+const DUMMY_SALT: Salt = Salt::from_bytes(&[0x5a; 16]);
+
+/// How a [`Verifier`] hashes and verifies keys.
+#[derive(Debug)]
+pub struct VerifierOptions {
+	/// The work factor new hashes get. Verified hashes below it are reported as needing a rehash.
+	pub target: WorkFactor,
+
+	/// How keys are prepared before they’re hashed.
+	pub key_policy: KeyPolicy,
+
+	/// Other ways keys may have been prepared by whatever stored the hashes – a script that hashed the newline `echo` adds, say. A key that only matches as one of these prepared it is reported as a [`LegacyQuirkMatch`](VerifyOutcome::LegacyQuirkMatch).
+	pub legacy_key_policies: Vec<KeyPolicy>,
+
+	/// Peppers mixed into keys with HMAC-SHA-512 before they’re hashed, current one first; new hashes use the first, and a key that only matches with a later one is reported as needing a rehash. With none, keys are hashed as they are.
+	pub peppers: Vec<Pepper>,
+}
+
+impl VerifierOptions {
+	/// Options for hashing at `target`, with the default key policy and no pepper.
+	pub fn new(target: WorkFactor) -> Self {
+		Self {
+			target,
+			key_policy: KeyPolicy::DEFAULT,
+			legacy_key_policies: Vec::new(),
+			peppers: Vec::new(),
+		}
+	}
+}
+
+/// Hashing and verification configured once, for application code to call without getting any of it wrong: cost policy, peppers, legacy key preparation, hashing a dummy when there’s no stored hash, and metrics.
+///
+/// [`verify`](Self::verify) hashes the key once for every combination of pepper and key policy, whether an earlier one matched or not, and hashes a dummy the same number of times when there’s no stored hash, so how long it takes doesn’t say which matched or whether the user exists.
+pub struct Verifier {
+	options: VerifierOptions,
+	observer: Box<dyn Observer + Send + Sync>,
+}
+
+impl Verifier {
+	/// Creates a verifier.
+	pub fn new(options: VerifierOptions) -> Self {
+		Self::with_observer(options, NoopObserver)
+	}
+
+	/// [`new`](Self::new), reporting each hash to `observer` as [`bcrypt_observed`] does.
+	pub fn with_observer<O: Observer + Send + Sync + 'static>(options: VerifierOptions, observer: O) -> Self {
+		Self {
+			options,
+			observer: Box::new(observer),
+		}
+	}
+
+	/// Hashes a key for storage, at the target work factor with the current pepper.
+	pub fn hash(&self, key: &[u8], salt: &Salt) -> Result<HashString, BcryptError> {
+		let key = self.options.key_policy.prepare(key)?;
+		let pepper = self.options.peppers.first();
+		let hash = self.hash_candidate(key, pepper, salt, self.options.target)?;
+		Ok(HashString::new(Version::V2b, self.options.target, salt, &hash))
+	}
+
+	/// Checks a key against the hash string stored for a user: `None` if the user doesn’t exist. A stored hash string that doesn’t parse doesn’t match anything.
+	///
+	/// Only fails if the key is unacceptable to the key policy, whatever is stored.
+	pub fn verify(&self, key: &[u8], stored: Option<&str>) -> Result<VerifyOutcome, BcryptError> {
+		self.options.key_policy.prepare(key)?;
+
+		let hash_string = stored.and_then(|s| HashString::parse(s).ok());
+		let (salt, work_factor) = match &hash_string {
+			Some(hash_string) => (hash_string.salt(), hash_string.work_factor()),
+			None => (DUMMY_SALT, self.options.target),
+		};
+
+		let policies = core::iter::once(&self.options.key_policy).chain(&self.options.legacy_key_policies);
+		let mut matched = None;
+
+		for p in 0..self.options.peppers.len().max(1) {
+			let pepper = self.options.peppers.get(p);
+
+			for (k, policy) in policies.clone().enumerate() {
+				if let Ok(key) = policy.prepare(key) {
+					let hash = self.hash_candidate(key, pepper, &salt, work_factor)?;
+
+					if let Some(hash_string) = &hash_string {
+						if hashes_equal(&hash, &hash_string.hash()) && matched.is_none() {
+							matched = Some((p, k));
+						}
+					}
+				}
+			}
+		}
+
+		Ok(match (matched, hash_string) {
+			(None, _) | (_, None) => VerifyOutcome::Mismatch,
+			(Some((_, k)), Some(_)) if k > 0 => VerifyOutcome::LegacyQuirkMatch { policy: k - 1 },
+			(Some((p, _)), Some(hash_string)) => {
+				let version = hash_string.version();
+				let work_factor = hash_string.work_factor();
+
+				if p == 0 && work_factor >= self.options.target && version != Version::V2a {
+					VerifyOutcome::Match
+				} else {
+					VerifyOutcome::NeedsRehash { version, work_factor }
+				}
+			}
+		})
+	}
+
+	/// Hashes a prepared key, with the pepper mixed in if there is one.
+	fn hash_candidate(&self, key: &[u8], pepper: Option<&Pepper>, salt: &Salt, work_factor: WorkFactor) -> Result<[u8; HASH_SIZE], BcryptError> {
+		let pepper = match pepper {
+			Some(pepper) => pepper,
+			None => return bcrypt_observed(key, salt, work_factor, &&*self.observer),
+		};
+
+		// Encoded in bcrypt’s base64, so the peppered key has no zero bytes and fits in bcrypt’s key size.
+		let mut mac = hmac(pepper.as_bytes(), &[b"bcrypt-only pepper\0", key]);
+		let mut peppered = [0_u8; base64::encoded_len(PEPPER_MAC_SIZE)];
+		base64::encode(&mac[..PEPPER_MAC_SIZE], &mut peppered);
+		let result = bcrypt_observed(&peppered, salt, work_factor, &&*self.observer);
+
+		mac.fill(0);
+		black_box(&mut mac);
+		peppered.fill(0);
+		black_box(&mut peppered);
+		result
+	}
+}
+
+impl fmt::Debug for Verifier {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Verifier")
+			.field("options", &self.options)
+			.finish_non_exhaustive()
+	}
+}
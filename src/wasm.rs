@@ -0,0 +1,24 @@
+//! `hash` and `verify` for JavaScript, through wasm-bindgen. Build with `cargo rustc --lib --features wasm --target wasm32-unknown-unknown --release --crate-type cdylib`, then run `wasm-bindgen` on the `.wasm` file for browsers or Node.
+
+use std::string::{String, ToString};
+use wasm_bindgen::prelude::*;
+
+use super::mcf::{self, HashString};
+use super::{SALT_SIZE, Salt, WorkFactor};
+
+/// Hashes a password into a `$2b$` hash string with a salt from `crypto.getRandomValues`. Throws an `Error` if the cost isn’t 4 to 31, or the password is too long or contains a NUL character.
+#[wasm_bindgen]
+pub fn hash(password: &str, cost: u32) -> Result<String, JsError> {
+	let work_factor = WorkFactor::exp(cost).ok_or_else(|| JsError::new("invalid cost; expected 4 to 31"))?;
+	let mut salt = [0_u8; SALT_SIZE];
+	getrandom::getrandom(&mut salt).map_err(|err| JsError::new(&err.to_string()))?;
+	let hash_string = mcf::hash(password.as_bytes(), &Salt::from_bytes(&salt), work_factor).map_err(|err| JsError::new(&err.to_string()))?;
+	Ok(hash_string.as_str().into())
+}
+
+/// Checks whether a password matches a `$2a$`, `$2b$`, or `$2y$` hash string. Throws an `Error` if the hash string isn’t valid, or the password is too long or contains a NUL character.
+#[wasm_bindgen]
+pub fn verify(password: &str, hash_string: &str) -> Result<bool, JsError> {
+	let hash_string = HashString::parse(hash_string).map_err(|err| JsError::new(&err.to_string()))?;
+	mcf::verify(password.as_bytes(), &hash_string).map_err(|err| JsError::new(&err.to_string()))
+}
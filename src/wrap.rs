@@ -0,0 +1,113 @@
+//! Wrapped hashes: an existing hash string hashed again with bcrypt, so a store of low-cost hashes can be hardened without waiting for each user to log in.
+//!
+//! A wrapped hash is serialized as `$bcrypt-wrap`, the inner hash’s setting (`$2b$05$` and its salt), then the outer hash’s `$NN$`, salt, and hash. The outer hash’s key is the whole inner hash string. On a successful login, [`WrappedHash::unwrap`] recovers the inner hash string, so the caller can replace the record with a fresh single hash of the password.
+
+use core::fmt;
+use core::str::FromStr;
+
+use super::{BcryptError, Salt, WorkFactor, bcrypt_runtime};
+use super::mcf::{self, HashString, ParseError, SETTING_SIZE, Version, hashes_equal};
+
+const PREFIX: &str = "$bcrypt-wrap";
+
+/// The length of a wrapped hash string.
+pub const WRAPPED_STRING_SIZE: usize = PREFIX.len() + SETTING_SIZE + mcf::HASH_STRING_SIZE - 3;
+
+/// A hash string wrapped in a second bcrypt hash.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct WrappedHash {
+	inner: HashString,
+	outer: HashString,
+}
+
+impl WrappedHash {
+	/// Wraps a stored hash string in bcrypt with `salt` and `work_factor`.
+	pub fn wrap(inner: &HashString, salt: &Salt, work_factor: WorkFactor) -> Self {
+		// a hash string is 60 bytes of ASCII, always a valid key
+		let outer = mcf::hash(inner.as_str().as_bytes(), salt, work_factor).unwrap();
+
+		Self {
+			inner: setting_only(inner),
+			outer,
+		}
+	}
+
+	/// Checks whether a key matches, by hashing it with the inner hash’s setting and then the outer hash’s. Takes as long as both hashes.
+	pub fn verify(&self, key: &[u8]) -> Result<bool, BcryptError> {
+		Ok(self.unwrap(key)?.is_some())
+	}
+
+	/// Checks whether a key matches, like [`verify`](Self::verify), returning the inner hash string if it does.
+	pub fn unwrap(&self, key: &[u8]) -> Result<Option<HashString>, BcryptError> {
+		let inner_hash = bcrypt_runtime(key, &self.inner.salt(), self.inner.work_factor())?;
+		let inner = HashString::new(self.inner.version(), self.inner.work_factor(), &self.inner.salt(), &inner_hash);
+		let outer_hash = bcrypt_runtime(inner.as_str().as_bytes(), &self.outer.salt(), self.outer.work_factor()).unwrap();
+
+		Ok(if hashes_equal(&outer_hash, &self.outer.hash()) {
+			Some(inner)
+		} else {
+			None
+		})
+	}
+
+	/// The inner hash’s version.
+	pub const fn inner_version(&self) -> Version {
+		self.inner.version()
+	}
+
+	/// The inner hash’s work factor.
+	pub const fn inner_work_factor(&self) -> WorkFactor {
+		self.inner.work_factor()
+	}
+
+	/// The outer hash’s work factor.
+	pub const fn outer_work_factor(&self) -> WorkFactor {
+		self.outer.work_factor()
+	}
+
+	/// Parses a wrapped hash string.
+	pub fn parse(s: &str) -> Result<Self, ParseError> {
+		if s.len() != WRAPPED_STRING_SIZE {
+			return Err(ParseError::Length);
+		}
+
+		let rest = s.strip_prefix(PREFIX).ok_or(ParseError::Version)?;
+
+		if !rest.is_char_boundary(SETTING_SIZE) {
+			return Err(ParseError::Encoding);
+		}
+
+		let (setting, outer) = rest.split_at(SETTING_SIZE);
+		let mut inner = [b'.'; mcf::HASH_STRING_SIZE];
+		inner[..SETTING_SIZE].copy_from_slice(setting.as_bytes());
+		let mut outer_bytes = [0_u8; mcf::HASH_STRING_SIZE];
+		outer_bytes[..3].copy_from_slice(b"$2b");
+		outer_bytes[3..].copy_from_slice(outer.as_bytes());
+
+		Ok(Self {
+			inner: HashString::parse(core::str::from_utf8(&inner).map_err(|_| ParseError::Encoding)?)?,
+			outer: HashString::parse(core::str::from_utf8(&outer_bytes).map_err(|_| ParseError::Encoding)?)?,
+		})
+	}
+}
+
+/// The hash string’s setting with an all-zero hash, so a wrapped hash never holds the inner hash itself.
+fn setting_only(hash_string: &HashString) -> HashString {
+	HashString::new(hash_string.version(), hash_string.work_factor(), &hash_string.salt(), &[0; super::HASH_SIZE])
+}
+
+impl FromStr for WrappedHash {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, ParseError> {
+		Self::parse(s)
+	}
+}
+
+impl fmt::Display for WrappedHash {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(PREFIX)?;
+		f.write_str(&self.inner.as_str()[..SETTING_SIZE])?;
+		f.write_str(&self.outer.as_str()[3..])
+	}
+}